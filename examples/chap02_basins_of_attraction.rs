@@ -0,0 +1,28 @@
+extern crate my_alife;
+
+use my_alife::algorithm::basins_of_attraction::StateTransitionGraph;
+use std::env;
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::Write;
+
+// セルラーオートマトンの状態遷移グラフをdot形式とJSON形式で書き出す
+// cargo run --example chap02_basins_of_attraction --release -- 110 12
+// dot -Tpng -Kneato rule110_n12.dot -o rule110_n12.png
+fn main() -> Result<(), impl Debug> {
+    let args = env::args().collect::<Vec<_>>();
+    let rule = args.get(1).and_then(|s| s.parse::<u8>().ok()).unwrap_or(110);
+    let space_size = args.get(2).and_then(|s| s.parse::<usize>().ok()).unwrap_or(12);
+
+    StateTransitionGraph::new(rule, space_size).and_then(|graph| {
+        for attractor in graph.attractors() {
+            println!("period: {}, basin size: {}", attractor.period(), attractor.basin_size);
+        }
+        println!("garden of eden states: {}", graph.garden_of_eden_states().len());
+
+        let name = format!("rule{}_n{}", rule, space_size);
+        File::create(format!("{}.dot", name))?.write_all(graph.to_dot().as_bytes())?;
+        File::create(format!("{}.json", name))?.write_all(graph.to_json().as_bytes())?;
+        Ok(())
+    })
+}
@@ -0,0 +1,30 @@
+extern crate my_alife;
+extern crate ndarray;
+
+use my_alife::visualizer::spacetime_diagram::{rule_contact_sheet, spacetime_image};
+use ndarray::Array1;
+use std::env;
+use std::fmt::Debug;
+
+// ウィンドウを開かずにセルラーオートマトンの時空図を画像ファイルに書き出す
+// cargo run --example chap02_cellular_automata_1d_export --release -- 30 rule30.png
+// cargo run --example chap02_cellular_automata_1d_export --release -- all rules.png
+fn main() -> Result<(), impl Debug> {
+    let args = env::args().collect::<Vec<_>>();
+    let rule = args.get(1).map(|s| s.as_str()).unwrap_or("30");
+    let path = args.get(2).map(|s| s.as_str()).unwrap_or("cellular_automata_1d.png");
+
+    if rule == "all" {
+        // 中央の1セルだけが生きている状態から始める
+        let len = 63;
+        let mut initial_array = Array1::<u32>::zeros(len);
+        initial_array[len / 2] = 1;
+        rule_contact_sheet(&initial_array, 32, 16).save(path)
+    } else {
+        let len = 600;
+        let mut initial_array = Array1::<u32>::zeros(len);
+        initial_array[len / 2] = 1;
+        let rule = rule.parse::<u8>().expect("rule must be 0-255 or all");
+        spacetime_image(&initial_array, rule, 300).save(path)
+    }
+}
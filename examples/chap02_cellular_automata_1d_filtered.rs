@@ -0,0 +1,35 @@
+extern crate my_alife;
+extern crate ndarray;
+extern crate ndarray_rand;
+extern crate rand;
+
+use my_alife::algorithm::cellular_automata::{cellular_automata, spacetime_diagram};
+use my_alife::algorithm::domain_filter::DomainFilter;
+use my_alife::visualizer::array_visualizer::ArrayVisualizer;
+use ndarray::Array1;
+use ndarray_rand::RandomExt;
+use rand::distributions::Range;
+use std::fmt::Debug;
+
+// ルール54の時空図から領域を取り除いて、粒子(グライダー)だけを表示する
+fn main() -> Result<(), impl Debug> {
+    let len = 600;
+    let rule = 54;
+    let initial_array = Array1::random(len, Range::new(0, 2));
+    // 最初の数百フェーズを使って背景になっている領域を探す
+    let history = spacetime_diagram(&initial_array, rule, 300);
+    let filter = DomainFilter::detect(rule, &history, 4, 4, 0.1);
+
+    let next_state = Array1::<u32>::zeros(len);
+    let visualizer = ArrayVisualizer::new(
+        "Cellular Automata 1d (filtered)",
+        "res/shaders/matrix_visualizer_vertex.glsl",
+        "res/shaders/matrix_visualizer_fragment.glsl",
+        600,
+        &initial_array,
+    );
+    visualizer.and_then(|mut visualizer| {
+        visualizer.set_domain_filter(filter);
+        visualizer.draw_loop((initial_array, next_state), rule, cellular_automata)
+    })
+}
@@ -1,19 +1,50 @@
 extern crate my_alife;
 
-use my_alife::algorithm::gray_scott::{initial_matrix, laplacian};
-use my_alife::visualizer::matrix_visualizer::MatrixVisualizer;
+use my_alife::algorithm::gray_scott::{preset, GrayScott, GrayScottParams, PRESETS};
+use my_alife::algorithm::stencil::{cast_state, Real};
+use my_alife::visualizer::matrix_visualizer::{Matrix, MatrixVisualizer};
+use std::cell::RefCell;
+use std::env;
 use std::fmt::Debug;
+use std::mem;
+use std::process;
 
-// model parameter
-const F: f32 = 0.04;
-const K: f32 = 0.06;
-
+// プリセットの名前を指定して実行する(省略するとamorphous)。2番目の引数にf64を指定すると倍精度で計算する
+// ドラッグしたところにvを書き込み、上下の矢印キーでF、左右の矢印キーでKを変え、Rで最初の状態に戻す
+// cargo run --example chap02_gray_scott --release -- spot f64
 fn main() -> Result<(), impl Debug> {
-    let state = initial_matrix();
+    let name = env::args().nth(1).unwrap_or_else(|| "amorphous".to_string());
+    let preset = preset(&name).unwrap_or_else(|| {
+        eprintln!("unknown preset: {}", name);
+        for p in PRESETS {
+            eprintln!("  {:<10} F = {:<6} K = {:<6} {}", p.name, p.f, p.k, p.description);
+        }
+        process::exit(1);
+    });
+
+    let state = preset.initial_state((256, 256));
     let matrix = MatrixVisualizer::new(
-        "Gray Scott",
+        &format!("Gray Scott ({})", preset.name),
         "res/shaders/matrix_visualizer_vertex.glsl",
         "res/shaders/matrix_visualizer_fragment.glsl",
     );
-    matrix?.draw_loop(state, F, K, laplacian)
+    let mut matrix = matrix?;
+    // シミュレーターは最初に1つだけ作り、毎フレーム使い回す
+    if env::args().nth(2).map_or(false, |p| p == "f64") {
+        let state = cast_state::<f32, f64>(&state);
+        let simulator = RefCell::new(GrayScott::from_state(preset.params(), state.clone()));
+        matrix.draw_loop_tunable(state, preset.params(), |uv, params| update(&simulator, uv, params))
+    } else {
+        let simulator = RefCell::new(GrayScott::from_state(preset.params(), state.clone()));
+        matrix.draw_loop_tunable(state, preset.params(), |uv, params| update(&simulator, uv, params))
+    }
+}
+
+// 描画側の状態`uv`を`simulator`と入れ替えて進める。ブラシやリセットで書き換えた`uv`もそのまま使われる
+fn update<T: Real>(simulator: &RefCell<GrayScott<T>>, uv: &mut (Matrix<T>, Matrix<T>), params: &GrayScottParams) {
+    let mut simulator = simulator.borrow_mut();
+    *simulator.params_mut() = *params;
+    mem::swap(uv, simulator.state_mut());
+    simulator.step();
+    mem::swap(uv, simulator.state_mut());
 }
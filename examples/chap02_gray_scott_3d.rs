@@ -0,0 +1,36 @@
+extern crate failure;
+extern crate my_alife;
+extern crate ndarray;
+
+use my_alife::algorithm::gray_scott::preset;
+use my_alife::algorithm::gray_scott_3d::{stable_params, GrayScott3d};
+use my_alife::visualizer::matrix_visualizer::MatrixVisualizer;
+use ndarray::Axis;
+use std::cell::{Cell, RefCell};
+use std::env;
+
+// 3次元のGray-Scottモデルを計算し、z軸に沿って動いていく断面を描画する
+// 引数はプリセットの名前(省略するとmu)と格子の一辺の長さ(省略すると64)
+// cargo run --example chap02_gray_scott_3d --release -- mu 64
+fn main() -> Result<(), failure::Error> {
+    let args = env::args().collect::<Vec<_>>();
+    let name = args.get(1).map(|s| s.as_str()).unwrap_or("mu");
+    let size = args.get(2).map_or(Ok(64), |s| s.parse())?;
+    let preset = preset(name).ok_or_else(|| failure::err_msg(format!("unknown preset: {}", name)))?;
+
+    let simulator = GrayScott3d::new(stable_params(preset.params()), (size, size, size));
+    simulator.check_stability()?;
+    let state = simulator.slice(Axis(0), size / 2);
+    let mut matrix = MatrixVisualizer::new(
+        &format!("Gray Scott 3D ({})", preset.name),
+        "res/shaders/matrix_visualizer_vertex.glsl",
+        "res/shaders/matrix_visualizer_fragment.glsl",
+    )?;
+    // 描画のたびに時間を進め、断面の位置を1層ずつずらす
+    matrix.draw_loop(state, (RefCell::new(simulator), Cell::new(0)), |uv, &(ref simulator, ref frame)| {
+        let mut simulator = simulator.borrow_mut();
+        simulator.step();
+        *uv = simulator.slice(Axis(0), frame.get() % size);
+        frame.set(frame.get() + 1);
+    })
+}
@@ -0,0 +1,42 @@
+extern crate failure;
+extern crate my_alife;
+
+use my_alife::algorithm::adi::{step_model, AdiSolver};
+use my_alife::algorithm::gray_scott::preset;
+use my_alife::visualizer::matrix_visualizer::MatrixVisualizer;
+use std::cell::RefCell;
+use std::env;
+
+// 拡散を交互方向陰解法(ADI)で解き、陽的Euler法では発散する大きな刻み幅でGray-Scottモデルを計算する
+// 引数はプリセットの名前(省略するとspot)と時間の刻み幅(省略すると4)。1回の描画で進める時間は刻み幅によらず同じにする
+// cargo run --example chap02_gray_scott_adi --release -- spot 4
+fn main() -> Result<(), failure::Error> {
+    let args = env::args().collect::<Vec<_>>();
+    let name = args.get(1).map(|s| s.as_str()).unwrap_or("spot");
+    let dt = args.get(2).map_or(Ok(4.0), |s| s.parse::<f32>())?;
+    let preset = preset(name).ok_or_else(|| failure::err_msg(format!("unknown preset: {}", name)))?;
+
+    let mut params = preset.params();
+    let duration = params.dt * params.substeps as f32;
+    params.dt = dt;
+    params.substeps = ((duration / dt).round() as usize).max(1);
+    println!(
+        "dt = {}, substeps = {}, D dt / dx^2 = {} (explicit Euler needs <= 0.25)",
+        params.dt,
+        params.substeps,
+        params.du.max(params.dv) * params.dt / (params.dx * params.dx)
+    );
+
+    let state = preset.initial_state((256, 256));
+    let solver = AdiSolver::new(state.clone());
+    let mut matrix = MatrixVisualizer::new(
+        &format!("Gray Scott ADI ({}, dt = {})", preset.name, dt),
+        "res/shaders/matrix_visualizer_vertex.glsl",
+        "res/shaders/matrix_visualizer_fragment.glsl",
+    )?;
+    matrix.draw_loop(state, RefCell::new(solver), |uv, solver| {
+        let mut solver = solver.borrow_mut();
+        step_model(&mut solver, &params, &params.discretization());
+        uv.clone_from(solver.state());
+    })
+}
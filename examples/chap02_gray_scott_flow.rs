@@ -0,0 +1,58 @@
+extern crate failure;
+extern crate my_alife;
+extern crate ndarray;
+
+use my_alife::algorithm::diffusion_tensor::DiffusionTensor;
+use my_alife::algorithm::flow::{Advection, Flow, Velocity};
+use my_alife::algorithm::gray_scott::{preset, GrayScott};
+use my_alife::visualizer::matrix_visualizer::MatrixVisualizer;
+use ndarray::Array2;
+use std::cell::RefCell;
+use std::env;
+
+// 流れや異方的な拡散のある場所でGray-Scottモデルを計算する
+// 引数は流れの種類とプリセットの名前(省略するとshearとspot)
+// shear: 上下で逆向きに流れるせん断流(風上差分)
+// uniform: 右下に向かう一様な流れ(風上差分)
+// vortex: 中心の周りを回る渦(セミラグランジュ法)
+// anisotropic: 流れはなく、斜め30度の向きに強く拡散する
+// cargo run --example chap02_gray_scott_flow --release -- vortex spot
+fn main() -> Result<(), failure::Error> {
+    let args = env::args().collect::<Vec<_>>();
+    let mode = args.get(1).map(|s| s.as_str()).unwrap_or("shear");
+    let name = args.get(2).map(|s| s.as_str()).unwrap_or("spot");
+    let preset = preset(name).ok_or_else(|| failure::err_msg(format!("unknown preset: {}", name)))?;
+    let shape = (256, 256);
+    let params = preset.params();
+
+    let mut simulator = GrayScott::from_state(params, preset.initial_state(shape));
+    match mode {
+        "shear" => simulator.set_flow(Some(Flow::new(Velocity::Shear(0.001), Advection::Upwind)))?,
+        "uniform" => simulator.set_flow(Some(Flow::new(Velocity::Constant(2e-4, 1e-4), Advection::Upwind)))?,
+        "vortex" => {
+            // 角速度一定で回る渦。外側ほど速い
+            let omega = 0.005;
+            let (cy, cx) = (shape.0 as f32 / 2.0, shape.1 as f32 / 2.0);
+            let vx = Array2::from_shape_fn(shape, |(i, _)| -omega * (i as f32 - cy) * params.dx);
+            let vy = Array2::from_shape_fn(shape, |(_, j)| omega * (j as f32 - cx) * params.dx);
+            simulator.set_flow(Some(Flow::new(Velocity::Field(vx, vy), Advection::SemiLagrangian)))?
+        }
+        "anisotropic" => {
+            simulator.set_anisotropy(DiffusionTensor::oriented(::std::f32::consts::PI / 6.0, 1.2, 0.6))?
+        }
+        _ => return Err(failure::err_msg(format!("unknown mode: {}", mode))),
+    }
+    simulator.check_stability()?;
+
+    let state = simulator.state().clone();
+    let mut matrix = MatrixVisualizer::new(
+        &format!("Gray Scott ({}, {})", preset.name, mode),
+        "res/shaders/matrix_visualizer_vertex.glsl",
+        "res/shaders/matrix_visualizer_fragment.glsl",
+    )?;
+    matrix.draw_loop(state, RefCell::new(simulator), |uv, simulator| {
+        let mut simulator = simulator.borrow_mut();
+        simulator.step();
+        uv.clone_from(simulator.state());
+    })
+}
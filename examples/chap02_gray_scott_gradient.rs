@@ -0,0 +1,56 @@
+extern crate failure;
+extern crate my_alife;
+extern crate ndarray;
+
+use my_alife::algorithm::gray_scott::{GrayScott, GrayScottParams, InitialCondition, ParameterMaps};
+use my_alife::algorithm::parameter_map::{from_image, linear, radial};
+use my_alife::format::image::GrayImage;
+use my_alife::visualizer::matrix_visualizer::MatrixVisualizer;
+use ndarray::Axis;
+use std::cell::RefCell;
+use std::env;
+use std::mem;
+
+// 場所ごとにFとKを変えて、1回の計算で模様の移り変わりを見る
+// linear(省略時): 上から下にFが小さく、左から右にKが大きくなる((F, K)の相図と同じ配置)
+// radial: 中心から外側に向かってKが大きくなる
+// それ以外: pbmかpgmの画像のパスとみなし、黒いところはK = 0.055、白いところはK = 0.065にする
+// cargo run --example chap02_gray_scott_gradient --release -- radial
+fn main() -> Result<(), failure::Error> {
+    let shape = (256, 256);
+    let mode = env::args().nth(1).unwrap_or_else(|| "linear".to_string());
+    let maps = match mode.as_str() {
+        "linear" => ParameterMaps {
+            f: Some(linear(shape, Axis(0), (0.06, 0.01))),
+            k: Some(linear(shape, Axis(1), (0.045, 0.07))),
+            ..Default::default()
+        },
+        "radial" => ParameterMaps {
+            k: Some(radial(shape, (128.0, 128.0), 128.0, (0.055, 0.065))),
+            ..Default::default()
+        },
+        path => ParameterMaps {
+            k: Some(from_image(&GrayImage::open(path)?, shape, (0.055, 0.065))),
+            ..Default::default()
+        },
+    };
+    let params = GrayScottParams::new(0.035, 0.06);
+    // 全体に模様ができるよう、種はばらまいておく
+    let state = InitialCondition::RandomSquares { count: 40, seed: 1 }.state(shape);
+    // シミュレーターは最初に1つだけ作り、毎フレーム使い回す
+    let mut simulator = GrayScott::from_state(params, state.clone());
+    simulator.set_maps(maps)?;
+    let simulator = RefCell::new(simulator);
+
+    let mut matrix = MatrixVisualizer::new(
+        &format!("Gray Scott ({})", mode),
+        "res/shaders/matrix_visualizer_vertex.glsl",
+        "res/shaders/matrix_visualizer_fragment.glsl",
+    )?;
+    matrix.draw_loop(state, (), |uv, _| {
+        let mut simulator = simulator.borrow_mut();
+        mem::swap(uv, simulator.state_mut());
+        simulator.step();
+        mem::swap(uv, simulator.state_mut());
+    })
+}
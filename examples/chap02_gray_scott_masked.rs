@@ -0,0 +1,39 @@
+extern crate failure;
+extern crate my_alife;
+extern crate ndarray;
+
+use my_alife::algorithm::gray_scott::{preset, GrayScott, GrayScottParams, InitialCondition};
+use my_alife::algorithm::stencil::Boundary;
+use my_alife::format::image::GrayImage;
+use ndarray::Array2;
+use std::env;
+
+// 障害物のマスクの中で模様を作り、画像ファイルに書き出す
+// マスクの画像(pbmかpgm)は黒い部分の中で模様ができる。省略するとドーナツ型になる
+// cargo run --example chap02_gray_scott_masked --release -- mask.pgm gray_scott_masked.png
+fn main() -> Result<(), failure::Error> {
+    let args = env::args().collect::<Vec<_>>();
+    let inside = match args.get(1) {
+        Some(path) => GrayImage::open(path)?.threshold(128),
+        None => Array2::from_shape_fn((256, 256), |(i, j)| {
+            let (y, x) = (i as f32 - 128.0, j as f32 - 128.0);
+            let r = (x * x + y * y).sqrt();
+            r > 40.0 && r < 120.0
+        }),
+    };
+    let output = args.get(2).map(|s| s.as_str()).unwrap_or("gray_scott_masked.png");
+
+    let spot = preset("spot").unwrap();
+    let params = GrayScottParams {
+        boundary: Boundary::Neumann,
+        ..spot.params()
+    };
+    // 中央がマスクの外になることもあるので、種はばらまいておく
+    let state = InitialCondition::RandomSquares { count: 16, seed: 1 }.state(inside.dim());
+    let mut simulator = GrayScott::from_state(params, state);
+    simulator.set_mask(Some(inside.mapv(|e| !e)))?;
+    for _ in 0..2000 {
+        simulator.step();
+    }
+    GrayImage::from_matrix(&simulator.state().0).save(output)
+}
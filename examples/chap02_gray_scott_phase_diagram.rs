@@ -0,0 +1,16 @@
+extern crate my_alife;
+
+use my_alife::algorithm::phase_diagram::{sweep, SweepConfig};
+use std::fmt::Debug;
+
+// ウィンドウを開かずに(F, K)の相図を作り、モザイク画像とCSVを書き出す
+// cargo run --example chap02_gray_scott_phase_diagram --release
+fn main() -> Result<(), impl Debug> {
+    let config = SweepConfig {
+        f_count: 20,
+        k_count: 20,
+        max_steps: 2000,
+        ..Default::default()
+    };
+    sweep(&config).save("gray_scott_phase_diagram.png", "gray_scott_phase_diagram.csv")
+}
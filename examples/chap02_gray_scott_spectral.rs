@@ -0,0 +1,56 @@
+extern crate failure;
+extern crate my_alife;
+
+use my_alife::algorithm::gray_scott::preset;
+use my_alife::algorithm::spectral::{step_model, SpectralSolver};
+use my_alife::format::image::GrayImage;
+use my_alife::format::npy::{NpzReader, NpzWriter};
+use my_alife::visualizer::matrix_visualizer::Matrix;
+use std::env;
+
+// 拡散をフーリエ空間で解くソルバーで、大きな格子のGray-Scottモデルを計算して画像ファイルと.npzファイルに書き出す
+// 引数は格子の一辺の長さ(省略すると2048)、更新回数(省略すると500)、プリセットの名前(省略するとspot)、
+// 続きから計算する場合は前回書き出した.npzファイルのパス(格子の大きさは一辺の長さの引数と同じでなければならない)
+// cargo run --example chap02_gray_scott_spectral --release -- 2048 500 spot
+// cargo run --example chap02_gray_scott_spectral --release -- 2048 500 spot gray_scott_spectral_spot.npz
+fn main() -> Result<(), failure::Error> {
+    let args = env::args().collect::<Vec<_>>();
+    let size = args.get(1).map_or(Ok(2048), |s| s.parse())?;
+    let updates = args.get(2).map_or(Ok(500), |s| s.parse())?;
+    let name = args.get(3).map(|s| s.as_str()).unwrap_or("spot");
+    let preset = preset(name).ok_or_else(|| failure::err_msg(format!("unknown preset: {}", name)))?;
+
+    let params = preset.params();
+    let discretization = params.discretization();
+    let state = match args.get(4) {
+        Some(path) => {
+            let mut npz = NpzReader::open(path)?;
+            let state: (Matrix<f32>, Matrix<f32>) = (npz.by_name("u")?, npz.by_name("v")?);
+            // 一辺の長さの引数と食い違う場合は、どちらが正しいかわからないのでエラーにする
+            if state.0.dim() != (size, size) || state.1.dim() != (size, size) {
+                return Err(failure::err_msg(format!(
+                    "{} contains a grid of {:?} and {:?}, but the size argument is {}",
+                    path,
+                    state.0.dim(),
+                    state.1.dim(),
+                    size
+                )));
+            }
+            state
+        }
+        None => preset.initial_state((size, size)),
+    };
+    let mut solver = SpectralSolver::new(state);
+    for i in 0..updates {
+        step_model(&mut solver, &params, &discretization)?;
+        if (i + 1) % 100 == 0 {
+            println!("{} / {}", i + 1, updates);
+        }
+    }
+    let (u, v) = solver.into_state();
+    let mut npz = NpzWriter::create(format!("gray_scott_spectral_{}.npz", name))?;
+    npz.add_array("u", &u)?;
+    npz.add_array("v", &v)?;
+    npz.finish()?;
+    GrayImage::from_matrix(&u).save(format!("gray_scott_spectral_{}.png", name))
+}
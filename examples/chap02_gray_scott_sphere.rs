@@ -0,0 +1,50 @@
+extern crate failure;
+extern crate my_alife;
+
+use my_alife::algorithm::gray_scott::preset;
+use my_alife::algorithm::mesh::{icosphere, initial_state, step_model, MeshSolver};
+use my_alife::format::mesh::{open_obj, save_ply};
+use std::env;
+
+// 球面(または読み込んだOBJファイルのメッシュ)の上でGray-Scottモデルを計算し、uの値で色をつけたPLYファイルに書き出す
+// 引数はプリセットの名前(省略するとstripe)、icosphereの分割回数かOBJファイルのパス(省略すると5)、更新回数(省略すると2000)
+// cargo run --example chap02_gray_scott_sphere --release -- stripe 5 2000
+// cargo run --example chap02_gray_scott_sphere --release -- stripe bunny.obj 2000
+fn main() -> Result<(), failure::Error> {
+    let args = env::args().collect::<Vec<_>>();
+    let name = args.get(1).map(|s| s.as_str()).unwrap_or("stripe");
+    let source = args.get(2).map(|s| s.as_str()).unwrap_or("5");
+    let updates = args.get(3).map_or(Ok(2000), |s| s.parse())?;
+    let preset = preset(name).ok_or_else(|| failure::err_msg(format!("unknown preset: {}", name)))?;
+    let params = preset.params();
+
+    let mut mesh = match source.parse() {
+        Ok(subdivisions) => icosphere(subdivisions),
+        Err(_) => open_obj(source)?,
+    };
+    // 辺の長さを格子の間隔にそろえると、平面と同じ大きさの模様ができる
+    let scale = params.dx / mesh.mean_edge_length();
+    mesh.scale(scale);
+
+    let center = *mesh
+        .vertices
+        .first()
+        .ok_or_else(|| failure::err_msg("mesh has no vertices"))?;
+    let state = initial_state(&mesh, center, 10.0 * params.dx);
+    let mut solver = MeshSolver::new(&mesh, state)?;
+    let max_dt = solver.max_stable_dt(params.du.max(params.dv));
+    if params.dt > max_dt {
+        return Err(failure::err_msg(format!(
+            "time step {} is unstable on this mesh (max {})",
+            params.dt, max_dt
+        )));
+    }
+    let discretization = params.discretization();
+    for i in 0..updates {
+        step_model(&mut solver, &params, &discretization);
+        if (i + 1) % 100 == 0 {
+            println!("{} / {}", i + 1, updates);
+        }
+    }
+    save_ply(format!("gray_scott_sphere_{}.ply", name), &mesh, &solver.state().0, (0.0, 1.0))
+}
@@ -0,0 +1,41 @@
+extern crate failure;
+extern crate my_alife;
+
+use my_alife::algorithm::gray_scott::{preset, GrayScott};
+use my_alife::format::vtk::{Encoding, ImageData, TimeSeries};
+use std::env;
+
+// Gray-Scottモデルの時系列をParaViewで開けるVTK形式(.vti, .pvd)で書き出す
+// 引数はプリセットの名前(省略するとspot)、更新回数(省略すると2000)、書き出す間隔(1以上、省略すると100)、
+// 書き出し先のディレクトリ(省略するとgray_scott_vtk)
+// cargo run --example chap02_gray_scott_vtk --release -- spot 2000 100 gray_scott_vtk
+fn main() -> Result<(), failure::Error> {
+    let args = env::args().collect::<Vec<_>>();
+    let name = args.get(1).map(|s| s.as_str()).unwrap_or("spot");
+    let updates = args.get(2).map_or(Ok(2000), |s| s.parse())?;
+    let interval: usize = args.get(3).map_or(Ok(100), |s| s.parse())?;
+    if interval < 1 {
+        return Err(failure::err_msg("interval must be at least 1"));
+    }
+    let directory = args.get(4).map(|s| s.as_str()).unwrap_or("gray_scott_vtk");
+    let preset = preset(name).ok_or_else(|| failure::err_msg(format!("unknown preset: {}", name)))?;
+
+    let params = preset.params();
+    let dx = params.dx;
+    let dt = params.dt * params.substeps as f32;
+    let mut simulator = GrayScott::from_state(params, preset.initial_state((256, 256)));
+    let mut series = TimeSeries::new(directory, name, Encoding::Appended)?;
+    for i in 0..updates + 1 {
+        if i > 0 {
+            simulator.step();
+        }
+        if i % interval == 0 {
+            let (ref u, ref v) = *simulator.state();
+            let mut image = ImageData::from_uv(u, v)?;
+            image.spacing = [dx, dx, dx];
+            series.add((i as f32 * dt) as f64, &image)?;
+        }
+    }
+    println!("{}", series.pvd_path().display());
+    Ok(())
+}
@@ -0,0 +1,34 @@
+extern crate my_alife;
+
+use my_alife::algorithm::reaction_diffusion::{model_preset, model_presets, ReactionDiffusion, Simulator};
+use my_alife::algorithm::stencil::cast_state;
+use my_alife::visualizer::matrix_visualizer::MatrixVisualizer;
+use std::env;
+use std::fmt::Debug;
+use std::process;
+
+// Gray-Scott以外も含む反応拡散系のプリセットの名前を指定して実行する(省略するとbarkley)。2番目の引数にf64を指定すると倍精度で計算する
+// cargo run --example chap02_reaction_diffusion --release -- brusselator
+fn main() -> Result<(), impl Debug> {
+    let name = env::args().nth(1).unwrap_or_else(|| "barkley".to_string());
+    let preset = model_preset(&name).unwrap_or_else(|| {
+        eprintln!("unknown preset: {}", name);
+        for p in model_presets() {
+            eprintln!("  {:<16} {}", p.name, p.description);
+        }
+        process::exit(1);
+    });
+
+    let matrix = MatrixVisualizer::new(
+        &format!("Reaction Diffusion ({})", preset.name),
+        "res/shaders/matrix_visualizer_vertex.glsl",
+        "res/shaders/matrix_visualizer_fragment.glsl",
+    );
+    let shape = (256, 256);
+    if env::args().nth(2).map_or(false, |p| p == "f64") {
+        let state = cast_state::<f32, f64>(&preset.model.initial_state(shape));
+        matrix?.draw_simulation(Simulator::new(preset.model, preset.discretization, state)?)
+    } else {
+        matrix?.draw_simulation(preset.simulator(shape)?)
+    }
+}
@@ -0,0 +1,42 @@
+extern crate failure;
+extern crate my_alife;
+
+use my_alife::algorithm::reaction_network::NetworkSimulator;
+use my_alife::format::reaction_network::open_network;
+use my_alife::visualizer::matrix_visualizer::MatrixVisualizer;
+use std::cell::RefCell;
+use std::env;
+
+// 化学種と反応式を書いたファイルを読み込み、反応拡散系を計算する
+// 引数はファイルのパス(省略するとres/networks/gray_scott.toml)と描画する化学種の名前(省略すると最初の化学種)
+// cargo run --example chap02_reaction_network --release -- res/networks/rock_paper_scissors.toml A
+fn main() -> Result<(), failure::Error> {
+    let args = env::args().collect::<Vec<_>>();
+    let path = args.get(1).map(|s| s.as_str()).unwrap_or("res/networks/gray_scott.toml");
+    let (network, discretization) = open_network(path)?;
+    let name = match args.get(2) {
+        Some(name) => name.clone(),
+        None => network
+            .species
+            .first()
+            .map(|s| s.name.clone())
+            .ok_or_else(|| failure::err_msg("network has no species"))?,
+    };
+
+    let state = network.initial_state((256, 256));
+    let simulator = NetworkSimulator::new(network, discretization, state)?;
+    simulator.check_stability()?;
+    let species = simulator
+        .species(&name)
+        .ok_or_else(|| failure::err_msg(format!("unknown species: {}", name)))?;
+    let mut matrix = MatrixVisualizer::new(
+        &format!("Reaction Network ({}, {})", path, name),
+        "res/shaders/matrix_visualizer_vertex.glsl",
+        "res/shaders/matrix_visualizer_fragment.glsl",
+    )?;
+    matrix.draw_loop((species.clone(), species), RefCell::new(simulator), |uv, simulator| {
+        let mut simulator = simulator.borrow_mut();
+        simulator.step();
+        uv.0 = simulator.species(&name).unwrap();
+    })
+}
@@ -0,0 +1,279 @@
+use algorithm::cellular_automata::next_cell_state;
+use failure;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// 状態遷移グラフを列挙できるセルの数の上限(2^MAX_SPACE_SIZE個の状態を持つことになる)
+pub const MAX_SPACE_SIZE: usize = 24;
+
+/// アトラクタ(状態遷移グラフの閉路)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attractor {
+    /// 閉路をたどる順に並べた状態。最初の要素は閉路の中で最も小さい状態
+    pub cycle: Vec<u32>,
+    /// 閉路に流れ込む状態(閉路上の状態を含む)の数
+    pub basin_size: usize,
+}
+
+impl Attractor {
+    /// 周期(閉路の長さ)
+    pub fn period(&self) -> usize {
+        self.cycle.len()
+    }
+}
+
+/// 周期境界のセルラーオートマトンの状態遷移グラフ(Wuenscheの"basins of attraction")
+/// セルの数が少なければ全状態を列挙して、どの状態がどの状態に遷移するかを調べられる
+///
+/// 状態はセルの並びをそのまま2進数として読んだ整数で表す。
+/// 左端のセルが最上位bitになるので、例えば`0011`(4セル)は`3`である
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::basins_of_attraction::StateTransitionGraph;
+///
+/// // ルール0は全ての状態を1ステップで全て0の状態に移す
+/// let graph = StateTransitionGraph::new(0, 4).unwrap();
+/// let attractors = graph.attractors();
+/// assert_eq!(attractors.len(), 1);
+/// assert_eq!(attractors[0].cycle, vec![0]);
+/// assert_eq!(attractors[0].basin_size, 16);
+/// assert_eq!(graph.garden_of_eden_states().len(), 15);
+///
+/// // ルール204は恒等写像なので全ての状態が不動点になる
+/// let graph = StateTransitionGraph::new(204, 4).unwrap();
+/// assert_eq!(graph.attractors().len(), 16);
+/// assert!(graph.garden_of_eden_states().is_empty());
+/// ```
+pub struct StateTransitionGraph {
+    rule: u8,
+    space_size: usize,
+    successors: Vec<u32>,
+}
+
+impl StateTransitionGraph {
+    /// 全ての状態について遷移先を計算してStateTransitionGraphインスタンスを生成する
+    ///
+    /// # Arguments
+    /// * `rule` - ウルフラムのルールコーディングの数字
+    /// * `space_size` - セルの数(1から`MAX_SPACE_SIZE`まで)
+    pub fn new(rule: u8, space_size: usize) -> Result<StateTransitionGraph, failure::Error> {
+        if space_size == 0 || space_size > MAX_SPACE_SIZE {
+            return Err(failure::err_msg(format!(
+                "space_size must be between 1 and {}, but got {}",
+                MAX_SPACE_SIZE, space_size
+            )));
+        }
+        let state_count = 1usize << space_size;
+        let successors = (0..state_count as u32)
+            .map(|state| Self::next_state(state, rule, space_size))
+            .collect();
+        Ok(StateTransitionGraph {
+            rule: rule,
+            space_size: space_size,
+            successors: successors,
+        })
+    }
+
+    fn next_state(state: u32, rule: u8, space_size: usize) -> u32 {
+        // i番目(左から)のセルの生死
+        let cell = |i: usize| (state >> (space_size - 1 - i)) & 1;
+        let mut next = 0;
+        for i in 0..space_size {
+            let l = cell((i + space_size - 1) % space_size);
+            let c = cell(i);
+            let r = cell((i + 1) % space_size);
+            next |= next_cell_state(l, c, r, rule) << (space_size - 1 - i);
+        }
+        next
+    }
+
+    /// ウルフラムのルールコーディングの数字
+    pub fn rule(&self) -> u8 {
+        self.rule
+    }
+
+    /// セルの数
+    pub fn space_size(&self) -> usize {
+        self.space_size
+    }
+
+    /// 状態の総数
+    pub fn state_count(&self) -> usize {
+        self.successors.len()
+    }
+
+    /// `state`の次の状態
+    pub fn successor(&self, state: u32) -> u32 {
+        self.successors[state as usize]
+    }
+
+    /// `state`をセルの並び(`0`と`1`の文字列)にする
+    pub fn state_to_string(&self, state: u32) -> String {
+        (0..self.space_size)
+            .map(|i| {
+                if (state >> (self.space_size - 1 - i)) & 1 == 1 {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect()
+    }
+
+    /// 各状態の入次数(その状態に遷移してくる状態の数)
+    pub fn in_degrees(&self) -> Vec<u32> {
+        let mut in_degrees = vec![0; self.successors.len()];
+        for next in &self.successors {
+            in_degrees[*next as usize] += 1;
+        }
+        in_degrees
+    }
+
+    /// 入次数ごとの状態の数
+    pub fn in_degree_distribution(&self) -> BTreeMap<u32, usize> {
+        let mut distribution = BTreeMap::new();
+        for in_degree in self.in_degrees() {
+            *distribution.entry(in_degree).or_insert(0) += 1;
+        }
+        distribution
+    }
+
+    /// Garden-of-Eden状態(どの状態からも遷移してこない状態)
+    pub fn garden_of_eden_states(&self) -> Vec<u32> {
+        self.in_degrees()
+            .iter()
+            .enumerate()
+            .filter(|&(_, in_degree)| *in_degree == 0)
+            .map(|(state, _)| state as u32)
+            .collect()
+    }
+
+    /// 各状態がどのアトラクタに流れ込むか(`attractors`の添字)
+    pub fn basin_labels(&self) -> Vec<usize> {
+        self.label_basins().0
+    }
+
+    /// 全てのアトラクタと、それぞれのbasinの大きさ
+    pub fn attractors(&self) -> Vec<Attractor> {
+        self.label_basins().1
+    }
+
+    fn label_basins(&self) -> (Vec<usize>, Vec<Attractor>) {
+        const UNLABELED: usize = ::std::usize::MAX;
+        let state_count = self.successors.len();
+        let mut labels = vec![UNLABELED; state_count];
+        // 今たどっている経路の出発点を記録して、経路上の状態に戻ってきたかを判定する
+        let mut visited_from = vec![UNLABELED; state_count];
+        let mut attractors: Vec<Attractor> = Vec::new();
+        let mut path = Vec::new();
+
+        for start in 0..state_count {
+            if labels[start] != UNLABELED {
+                continue;
+            }
+            path.clear();
+            let mut state = start;
+            let label = loop {
+                if labels[state] != UNLABELED {
+                    // すでにわかっているbasinに合流した
+                    break labels[state];
+                }
+                if visited_from[state] == start {
+                    // 経路上の状態に戻ってきたので閉路が見つかった
+                    let position = path.iter().position(|s| *s == state).unwrap();
+                    let mut cycle = path[position..].iter().map(|s| *s as u32).collect::<Vec<_>>();
+                    let min_position = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap();
+                    cycle.rotate_left(min_position);
+                    attractors.push(Attractor {
+                        cycle: cycle,
+                        basin_size: 0,
+                    });
+                    break attractors.len() - 1;
+                }
+                visited_from[state] = start;
+                path.push(state);
+                state = self.successors[state] as usize;
+            };
+            for s in &path {
+                labels[*s] = label;
+            }
+            attractors[label].basin_size += path.len();
+        }
+        (labels, attractors)
+    }
+
+    /// Graphvizのdot形式で出力する
+    /// アトラクタ上の状態と遷移は赤、Garden-of-Eden状態は白抜きで描かれる
+    pub fn to_dot(&self) -> String {
+        let in_degrees = self.in_degrees();
+        let attractors = self.attractors();
+        let mut on_cycle = vec![false; self.successors.len()];
+        for attractor in &attractors {
+            for state in &attractor.cycle {
+                on_cycle[*state as usize] = true;
+            }
+        }
+
+        let mut dot = String::new();
+        writeln!(dot, "digraph rule{}_n{} {{", self.rule, self.space_size).unwrap();
+        writeln!(dot, "  node [shape=point, width=0.08];").unwrap();
+        for (state, next) in self.successors.iter().enumerate() {
+            let name = self.state_to_string(state as u32);
+            if on_cycle[state] {
+                writeln!(dot, "  \"{}\" [color=red];", name).unwrap();
+            } else if in_degrees[state] == 0 {
+                writeln!(dot, "  \"{}\" [shape=circle, label=\"\"];", name).unwrap();
+            }
+            let style = if on_cycle[state] { " [color=red]" } else { "" };
+            writeln!(dot, "  \"{}\" -> \"{}\"{};", name, self.state_to_string(*next), style).unwrap();
+        }
+        writeln!(dot, "}}").unwrap();
+        dot
+    }
+
+    /// JSON形式で出力する
+    /// アトラクタの周期・状態・basinの大きさ、Garden-of-Eden状態、入次数の分布、全状態の遷移先を含む
+    pub fn to_json(&self) -> String {
+        let attractors = self
+            .attractors()
+            .iter()
+            .map(|a| {
+                format!(
+                    "{{\"period\": {}, \"basin_size\": {}, \"cycle\": [{}]}}",
+                    a.period(),
+                    a.basin_size,
+                    join(&a.cycle)
+                )
+            })
+            .collect::<Vec<_>>();
+        let garden_of_eden_states = self.garden_of_eden_states();
+        let in_degree_distribution = self.in_degree_distribution();
+
+        let mut json = String::new();
+        writeln!(json, "{{").unwrap();
+        writeln!(json, "  \"rule\": {},", self.rule).unwrap();
+        writeln!(json, "  \"space_size\": {},", self.space_size).unwrap();
+        writeln!(json, "  \"state_count\": {},", self.state_count()).unwrap();
+        writeln!(json, "  \"attractors\": [\n    {}\n  ],", attractors.join(",\n    ")).unwrap();
+        writeln!(json, "  \"garden_of_eden_count\": {},", garden_of_eden_states.len()).unwrap();
+        writeln!(json, "  \"garden_of_eden_states\": [{}],", join(&garden_of_eden_states)).unwrap();
+        writeln!(
+            json,
+            "  \"in_degree_distribution\": {{{}}},",
+            in_degree_distribution
+                .iter()
+                .map(|(d, n)| format!("\"{}\": {}", d, n))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+        .unwrap();
+        writeln!(json, "  \"successors\": [{}]", join(&self.successors)).unwrap();
+        writeln!(json, "}}").unwrap();
+        json
+    }
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    values.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
@@ -1,4 +1,4 @@
-use ndarray::Array1;
+use ndarray::{Array1, Array2};
 use std::mem;
 
 /// セルラーオートマトンのルール
@@ -24,12 +24,53 @@ pub fn cellular_automata(state: &mut (Array1<u32>, Array1<u32>), rule: u8, space
         let l = current_state[(i + space_size - 1) % space_size];
         let c = current_state[(i + space_size) % space_size];
         let r = current_state[(i + space_size + 1) % space_size];
-        let neighbor_cell_code = (2u32.pow(2)) * l + (2u32.pow(1)) * c + 2u32.pow(0) * r;
-        if ((rule >> neighbor_cell_code) & 1) == 1 {
-            next_state[i] = 1;
-        } else {
-            next_state[i] = 0;
-        }
+        next_state[i] = next_cell_state(l, c, r, rule);
     }
     mem::swap(current_state, next_state);
 }
+
+/// 左(`l`)、中央(`c`)、右(`r`)のセルの生死から、中央のセルの次のフェーズの生死を求める
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::cellular_automata::next_cell_state;
+///
+/// // ルール30: 001 -> 1, 111 -> 0
+/// assert_eq!(next_cell_state(0, 0, 1, 30), 1);
+/// assert_eq!(next_cell_state(1, 1, 1, 30), 0);
+/// ```
+pub fn next_cell_state(l: u32, c: u32, r: u32, rule: u8) -> u32 {
+    let neighbor_cell_code = (2u32.pow(2)) * l + (2u32.pow(1)) * c + 2u32.pow(0) * r;
+    if ((rule >> neighbor_cell_code) & 1) == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+/// `initial_state`から`steps`フェーズ分計算し、時空図(1行が1フェーズ)を作る
+/// 1行目は`initial_state`そのもの
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::cellular_automata::spacetime_diagram;
+/// use ndarray::Array1;
+///
+/// let mut initial_state = Array1::<u32>::zeros(7);
+/// initial_state[3] = 1;
+/// let diagram = spacetime_diagram(&initial_state, 90, 3);
+/// assert_eq!(diagram.row(2).to_vec(), vec![0, 1, 0, 0, 0, 1, 0]);
+/// ```
+pub fn spacetime_diagram(initial_state: &Array1<u32>, rule: u8, steps: usize) -> Array2<u32> {
+    let space_size = initial_state.len();
+    let mut diagram = Array2::<u32>::zeros((steps, space_size));
+    let mut state = (initial_state.clone(), Array1::<u32>::zeros(space_size));
+    for t in 0..steps {
+        diagram.row_mut(t).assign(&state.0);
+        cellular_automata(&mut state, rule, space_size);
+    }
+    diagram
+}
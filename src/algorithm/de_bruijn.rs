@@ -0,0 +1,235 @@
+use failure;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+/// 1次元セルラーオートマトン(近傍は両脇の1セルずつ)のde Bruijnグラフ
+/// 頂点は連続する2セルの状態(`k^2`個)、辺は連続する3セルの状態(`k^3`個)で、辺にはルールの出力がラベルとしてつく。
+/// 配置(両側に無限に続くセルの並び)はこのグラフ上の両側無限の道と1対1に対応し、
+/// その配置の次の状態は道の辺のラベルを並べたものになる
+///
+/// `rule_table`は近傍`(l, c, r)`を`k`進数`l * k^2 + c * k + r`として読んだ添字に出力を並べたもの。
+/// `k = 2`ならウルフラムのルールコーディングの数字のbit列と同じ並びになる
+pub struct DeBruijnGraph {
+    states: usize,
+    rule_table: Vec<usize>,
+}
+
+impl DeBruijnGraph {
+    /// `k`状態のルールからDeBruijnGraphインスタンスを生成する
+    ///
+    /// # Arguments
+    /// * `states` - セルの取りうる状態の数`k`
+    /// * `rule_table` - 近傍ごとの出力(長さ`k^3`)
+    pub fn new(states: usize, rule_table: Vec<usize>) -> Result<DeBruijnGraph, failure::Error> {
+        if states < 2 {
+            return Err(failure::err_msg("states must be at least 2"));
+        }
+        if rule_table.len() != states.pow(3) {
+            return Err(failure::err_msg(format!(
+                "rule_table must have {} entries, but got {}",
+                states.pow(3),
+                rule_table.len()
+            )));
+        }
+        if let Some(output) = rule_table.iter().find(|o| **o >= states) {
+            return Err(failure::err_msg(format!("output {} is out of range", output)));
+        }
+        Ok(DeBruijnGraph {
+            states: states,
+            rule_table: rule_table,
+        })
+    }
+
+    /// 2状態のルール(ウルフラムのルールコーディング)からDeBruijnGraphインスタンスを生成する
+    pub fn elementary(rule: u8) -> DeBruijnGraph {
+        DeBruijnGraph {
+            states: 2,
+            rule_table: (0..8).map(|i| ((rule >> i) & 1) as usize).collect(),
+        }
+    }
+
+    /// 頂点の数
+    pub fn node_count(&self) -> usize {
+        self.states * self.states
+    }
+
+    /// 頂点`node`から出る辺の(行き先の頂点, ラベル)
+    pub fn edges(&self, node: usize) -> Vec<(usize, usize)> {
+        (0..self.states)
+            .map(|s| {
+                let neighborhood = node * self.states + s;
+                (neighborhood % self.node_count(), self.rule_table[neighborhood])
+            })
+            .collect()
+    }
+
+    /// 大域写像が全射かどうか
+    /// 部分集合構成で、全頂点の集合からラベル列をたどって空集合に行き着く(原像を持たない有限パターンがある)かを調べる
+    pub fn is_surjective(&self) -> bool {
+        let all = (0..self.node_count()).collect::<BTreeSet<_>>();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+        visited.insert(all.clone());
+        queue.push_back(all);
+        while let Some(nodes) = queue.pop_front() {
+            for label in 0..self.states {
+                let next = nodes
+                    .iter()
+                    .flat_map(|n| self.edges(*n))
+                    .filter(|&(_, l)| l == label)
+                    .map(|(to, _)| to)
+                    .collect::<BTreeSet<_>>();
+                if next.is_empty() {
+                    return false;
+                }
+                if visited.insert(next.clone()) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        true
+    }
+
+    /// 大域写像が(両側無限の配置について)単射かどうか
+    /// 同じラベル列を持つ2本の道を組にしたグラフを作り、両側無限の道の上に対角成分以外の頂点
+    /// (異なる2つの配置が同じ像を持つ箇所)が現れないかを調べる
+    pub fn is_injective(&self) -> bool {
+        let node_count = self.node_count();
+        let pair = |a: usize, b: usize| a * node_count + b;
+        let mut successors = vec![Vec::new(); node_count * node_count];
+        for a in 0..node_count {
+            for b in 0..node_count {
+                for (to_a, label_a) in self.edges(a) {
+                    for (to_b, label_b) in self.edges(b) {
+                        if label_a == label_b {
+                            successors[pair(a, b)].push(pair(to_a, to_b));
+                        }
+                    }
+                }
+            }
+        }
+
+        // 入次数か出次数が0の頂点を取り除き続けると、両側無限の道の上にある頂点だけが残る
+        let mut alive = vec![true; successors.len()];
+        loop {
+            let mut in_degrees = vec![0; successors.len()];
+            for (from, tos) in successors.iter().enumerate() {
+                if alive[from] {
+                    for to in tos {
+                        in_degrees[*to] += 1;
+                    }
+                }
+            }
+            let mut removed = false;
+            for node in 0..successors.len() {
+                if alive[node] && (in_degrees[node] == 0 || !successors[node].iter().any(|to| alive[*to])) {
+                    alive[node] = false;
+                    removed = true;
+                }
+            }
+            if !removed {
+                break;
+            }
+        }
+        (0..node_count).all(|a| (0..node_count).all(|b| a == b || !alive[pair(a, b)]))
+    }
+}
+
+/// 2状態のルールの性質
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleProperties {
+    /// ウルフラムのルールコーディングの数字
+    pub rule: u8,
+    /// 大域写像が全射かどうか
+    pub surjective: bool,
+    /// 大域写像が単射かどうか(全射でもあるので可逆になる)
+    pub injective: bool,
+    /// 左右を反転したルール
+    pub mirror: u8,
+    /// 0と1を反転したルール
+    pub complement: u8,
+    /// 左右と0と1の両方を反転したルール
+    pub mirror_complement: u8,
+}
+
+impl RuleProperties {
+    /// 同値なルール(自分自身、mirror、complement、mirror_complement)の中で最も小さい番号
+    /// 同値類の代表として使う
+    pub fn equivalence_class(&self) -> u8 {
+        *[self.rule, self.mirror, self.complement, self.mirror_complement]
+            .iter()
+            .min()
+            .unwrap()
+    }
+}
+
+/// ルールのde Bruijnグラフを作り、全射性・単射性と同値なルールを求める
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::de_bruijn::rule_properties;
+///
+/// let rule30 = rule_properties(30);
+/// assert!(rule30.surjective);
+/// assert!(!rule30.injective);
+/// assert_eq!((rule30.mirror, rule30.complement, rule30.mirror_complement), (86, 135, 149));
+///
+/// assert!(!rule_properties(110).surjective);
+/// assert!(rule_properties(15).injective);
+/// ```
+pub fn rule_properties(rule: u8) -> RuleProperties {
+    let graph = DeBruijnGraph::elementary(rule);
+    let mirror = mirror_rule(rule);
+    let complement = complement_rule(rule);
+    RuleProperties {
+        rule: rule,
+        surjective: graph.is_surjective(),
+        injective: graph.is_injective(),
+        mirror: mirror,
+        complement: complement,
+        mirror_complement: complement_rule(mirror),
+    }
+}
+
+/// 左右を反転したルール
+pub fn mirror_rule(rule: u8) -> u8 {
+    (0..8).fold(0, |mirrored, code| {
+        let (l, c, r) = ((code >> 2) & 1, (code >> 1) & 1, code & 1);
+        let output = (rule >> (r << 2 | c << 1 | l)) & 1;
+        mirrored | output << code
+    })
+}
+
+/// 0と1を反転したルール
+pub fn complement_rule(rule: u8) -> u8 {
+    (0..8).fold(0, |complemented, code| {
+        let output = 1 - ((rule >> (7 - code)) & 1);
+        complemented | output << code
+    })
+}
+
+/// 256通りのルールを同値類(88個)に分ける
+/// 各同値類は小さい順に並んでいて、先頭が代表になる
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::de_bruijn::equivalence_classes;
+///
+/// let classes = equivalence_classes();
+/// assert_eq!(classes.len(), 88);
+/// assert_eq!(classes.iter().find(|c| c[0] == 30).unwrap(), &vec![30, 86, 135, 149]);
+/// ```
+pub fn equivalence_classes() -> Vec<Vec<u8>> {
+    let mut classes: Vec<Vec<u8>> = Vec::new();
+    for rule in 0..256usize {
+        let rule = rule as u8;
+        let mirror = mirror_rule(rule);
+        let mut class = vec![rule, mirror, complement_rule(rule), complement_rule(mirror)];
+        if *class.iter().min().unwrap() != rule {
+            continue;
+        }
+        class.sort();
+        class.dedup();
+        classes.push(class);
+    }
+    classes
+}
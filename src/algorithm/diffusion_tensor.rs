@@ -0,0 +1,96 @@
+use algorithm::stencil::{cast, Real};
+
+/// 拡散テンソル[[xx, xy], [xy, yy]]。xは列の増える向き、yは行の増える向き
+/// 各成分の拡散係数に掛けて、D(xx ∂²/∂x² + 2xy ∂²/∂x∂y + yy ∂²/∂y²)として使う。
+/// 混合微分は斜めの4つのセルを使った中心差分で計算する
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::diffusion_tensor::DiffusionTensor;
+///
+/// // 90度回すとxとyが入れ替わる
+/// let tensor = DiffusionTensor::oriented(::std::f32::consts::FRAC_PI_2, 2.0, 0.5);
+/// assert!((tensor.xx - 0.5).abs() < 1e-6 && (tensor.yy - 2.0).abs() < 1e-6 && tensor.xy.abs() < 1e-6);
+///
+/// let tensor = DiffusionTensor::oriented(::std::f32::consts::FRAC_PI_4, 3.0, 1.0);
+/// assert!((tensor.xy - 1.0).abs() < 1e-6);
+/// assert!(tensor.is_positive_semidefinite());
+/// assert!(!DiffusionTensor { xx: 1.0, xy: 2.0, yy: 1.0 }.is_positive_semidefinite());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiffusionTensor {
+    /// x方向の成分
+    pub xx: f32,
+    /// 斜め方向の成分
+    pub xy: f32,
+    /// y方向の成分
+    pub yy: f32,
+}
+
+impl Default for DiffusionTensor {
+    fn default() -> DiffusionTensor {
+        DiffusionTensor::isotropic()
+    }
+}
+
+impl DiffusionTensor {
+    /// 等方的な拡散(単位行列)
+    pub fn isotropic() -> DiffusionTensor {
+        DiffusionTensor {
+            xx: 1.0,
+            xy: 0.0,
+            yy: 1.0,
+        }
+    }
+
+    /// x軸から角度`angle`(ラジアン。yの増える向きに回す)の向きに`major`倍、それに垂直な向きに`minor`倍拡散するテンソル
+    pub fn oriented(angle: f32, major: f32, minor: f32) -> DiffusionTensor {
+        let (sin, cos) = angle.sin_cos();
+        DiffusionTensor {
+            xx: major * cos * cos + minor * sin * sin,
+            xy: (major - minor) * sin * cos,
+            yy: major * sin * sin + minor * cos * cos,
+        }
+    }
+
+    /// 半正定値(どの向きにも負の拡散がない)か
+    pub fn is_positive_semidefinite(&self) -> bool {
+        self.xx >= 0.0 && self.yy >= 0.0 && self.xx * self.yy >= self.xy * self.xy
+    }
+
+    /// 差分化したラプラシアンの固有値の最大値の、等方的な拡散(8/dx²)に対する比
+    /// 陽的な積分法の安定性は、拡散係数にこの値を掛けた等方的な拡散と同じとみなせる
+    pub fn spectral_factor(&self) -> f32 {
+        (4.0 * self.xx + 4.0 * self.yy + 2.0 * self.xy.abs()) / 8.0
+    }
+
+    /// 9点差分で計算するための成分(xx, xy, yy)。等方的なら5点差分で済むのでNone
+    pub(crate) fn stencil<T: Real>(&self) -> Option<(T, T, T)> {
+        if *self == DiffusionTensor::isotropic() {
+            None
+        } else {
+            Some((cast(self.xx), cast(self.xy), cast(self.yy)))
+        }
+    }
+}
+
+/// 9点差分で xx ∂²/∂x² + 2xy ∂²/∂x∂y + yy ∂²/∂y² を計算する
+///
+/// # Arguments
+/// * `(xx, xy, yy)` - `DiffusionTensor::stencil`の成分
+/// * `center` - 中心のセルの値
+/// * `neighbors` - 上, 下, 左, 右のセルの値
+/// * `diagonals` - 右下, 左下, 右上, 左上のセルの値
+/// * `inverse_dx2` - 格子の間隔の2乗の逆数
+pub(crate) fn anisotropic_laplacian<T: Real>(
+    (xx, xy, yy): (T, T, T),
+    center: T,
+    neighbors: [T; 4],
+    diagonals: [T; 4],
+    inverse_dx2: T,
+) -> T {
+    let two = cast::<T>(2.0);
+    let cross = diagonals[0] - diagonals[1] - diagonals[2] + diagonals[3];
+    let second = |a: T, b: T| a + b - two * center;
+    (xx * second(neighbors[2], neighbors[3]) + yy * second(neighbors[0], neighbors[1]) + xy * cross / two) * inverse_dx2
+}
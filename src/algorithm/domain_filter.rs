@@ -0,0 +1,499 @@
+use algorithm::cellular_automata::next_cell_state;
+use ndarray::{Array2, ArrayView1};
+use std::collections::HashSet;
+use visualizer::matrix_visualizer::Matrix;
+
+/// 領域とみなすために最低限必要な、周期的なパターンが続く長さ
+/// 短すぎると、たまたま同じ並びになった数セルまで領域として扱ってしまう
+pub const MIN_WINDOW: usize = 8;
+
+/// 正則な領域(時空間的に周期的なパターン)
+/// 空間周期`spatial_period`の並び`words[0]`に時間発展させると、`words[1]`, `words[2]`, ...と変化し、
+/// `temporal_period`ステップ後に`shift`セルずれた`words[0]`に戻る
+#[derive(Clone, Debug, PartialEq)]
+pub struct Domain {
+    /// 空間周期
+    pub spatial_period: usize,
+    /// 時間周期
+    pub temporal_period: usize,
+    /// 1時間周期でパターンが右にずれるセルの数
+    pub shift: usize,
+    /// 1時間周期の間に現れる並び
+    pub words: Vec<Vec<u32>>,
+}
+
+impl Domain {
+    /// 領域の中で現れうる長さ`spatial_period`の並び(全ての`words`の全ての回転)
+    fn phases(&self) -> Vec<Vec<u32>> {
+        let mut phases = Vec::new();
+        for word in &self.words {
+            for r in 0..word.len() {
+                let mut phase = word.clone();
+                phase.rotate_left(r);
+                phases.push(phase);
+            }
+        }
+        phases
+    }
+}
+
+/// `row`の`start`から`window`セルが周期`p`で繰り返している場合、その最初の`p`セルを返す
+fn periodic_head(row: &ArrayView1<u32>, start: usize, p: usize, window: usize) -> Option<Vec<u32>> {
+    let width = row.len();
+    let cell = |i: usize| row[(start + i) % width];
+    if (p..window).all(|i| cell(i) == cell(i - p)) {
+        Some((0..p).map(|i| cell(i)).collect())
+    } else {
+        None
+    }
+}
+
+/// 周期境界で長さ`word.len()`の並びを1ステップ時間発展させる
+fn evolve(word: &[u32], rule: u8) -> Vec<u32> {
+    let n = word.len();
+    (0..n)
+        .map(|i| next_cell_state(word[(i + n - 1) % n], word[i], word[(i + 1) % n], rule))
+        .collect()
+}
+
+/// `word`を回転すると`target`になる場合、その回転量(左回転)を返す
+fn rotation(word: &[u32], target: &[u32]) -> Option<usize> {
+    let n = word.len();
+    (0..n).find(|r| (0..n).all(|i| word[(i + r) % n] == target[i]))
+}
+
+/// `word`の回転のうち辞書順で最小のもの
+fn canonical(word: &[u32]) -> Vec<u32> {
+    let n = word.len();
+    (0..n)
+        .map(|r| (0..n).map(|i| word[(i + r) % n]).collect::<Vec<_>>())
+        .min()
+        .unwrap()
+}
+
+/// `word`がより短い周期の繰り返しでないかどうか
+fn is_primitive(word: &[u32]) -> bool {
+    let n = word.len();
+    (1..n)
+        .filter(|p| n % p == 0)
+        .all(|p| (0..n).any(|i| word[i] != word[i % p]))
+}
+
+/// ルールが持つ正則な領域の候補を、空間周期と時間周期の小さい順に全て列挙する
+///
+/// # Arguments
+/// * `rule` - ウルフラムのルールコーディングの数字
+/// * `max_spatial_period` - 空間周期の上限(大きくしすぎると`2^max_spatial_period`通り調べることになる)
+/// * `max_temporal_period` - 時間周期の上限
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::domain_filter::find_domains;
+///
+/// // ルール54の背景は空間周期4、時間周期2の領域(0001と1110が交互に現れる)
+/// let domains = find_domains(54, 4, 4);
+/// assert!(domains.iter().any(|d| d.spatial_period == 4 && d.temporal_period == 2));
+/// ```
+pub fn find_domains(rule: u8, max_spatial_period: usize, max_temporal_period: usize) -> Vec<Domain> {
+    let mut domains = Vec::new();
+    let mut seen = HashSet::new();
+    for p in 1..max_spatial_period + 1 {
+        for code in 0..1u64 << p {
+            let word = (0..p).map(|i| ((code >> (p - 1 - i)) & 1) as u32).collect::<Vec<_>>();
+            if !is_primitive(&word) || canonical(&word) != word || seen.contains(&word) {
+                continue;
+            }
+            let mut words = vec![word.clone()];
+            let mut current = word.clone();
+            for q in 1..max_temporal_period + 1 {
+                current = evolve(&current, rule);
+                if let Some(r) = rotation(&current, &word) {
+                    for w in &words {
+                        seen.insert(canonical(w));
+                    }
+                    domains.push(Domain {
+                        spatial_period: p,
+                        temporal_period: q,
+                        shift: (p - r) % p,
+                        words: words,
+                    });
+                    break;
+                }
+                words.push(current.clone());
+            }
+        }
+    }
+    domains.sort_by_key(|d| (d.spatial_period * d.temporal_period, d.spatial_period));
+    domains
+}
+
+/// 時空図から正則な領域を取り除き、残った欠陥(粒子)だけを見えるようにするフィルタ
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::cellular_automata::spacetime_diagram;
+/// use my_alife::algorithm::domain_filter::{track_particles, DomainFilter};
+/// use ndarray::Array1;
+///
+/// // ルール54の背景(0001と1011を交互に繰り返す)だけなら、すべて領域として取り除かれる
+/// let background = Array1::from_shape_fn(80, |j| if j % 4 == 3 { 1 } else { 0 });
+/// let history = spacetime_diagram(&background, 54, 40);
+/// let filter = DomainFilter::detect(54, &history, 4, 4, 0.1);
+/// assert_eq!(filter.domains().len(), 1);
+/// assert_eq!((filter.domains()[0].spatial_period, filter.domains()[0].temporal_period), (4, 2));
+/// assert!(filter.mask(&history).iter().all(|&domain| domain));
+///
+/// // 隣り合う2つのセルを反転させると、左向きに1ステップ1セル進む粒子が1つだけ残る
+/// let mut initial_state = background.clone();
+/// initial_state[40] = 1 - initial_state[40];
+/// initial_state[41] = 1 - initial_state[41];
+/// let history = spacetime_diagram(&initial_state, 54, 60);
+/// let filter = DomainFilter::detect(54, &history, 4, 4, 0.1);
+/// let mask = filter.mask(&history);
+/// assert!(mask.iter().filter(|&&domain| !domain).count() > 0);
+/// let tracks = track_particles(&mask);
+/// assert_eq!(tracks.particles.len(), 1);
+/// assert!(tracks.collisions.is_empty());
+/// assert_eq!(tracks.particles[0].positions.len(), 60);
+/// assert!((tracks.particles[0].velocity + 1.0).abs() < 0.01);
+/// ```
+pub struct DomainFilter {
+    rule: u8,
+    domains: Vec<Domain>,
+    phases: Vec<HashSet<Vec<u32>>>,
+}
+
+impl DomainFilter {
+    /// 取り除く領域を指定してDomainFilterインスタンスを生成する
+    ///
+    /// # Arguments
+    /// * `rule` - ウルフラムのルールコーディングの数字
+    /// * `domains` - 取り除く領域(`find_domains`で求めたもの)
+    pub fn new(rule: u8, domains: Vec<Domain>) -> DomainFilter {
+        let phases = domains
+            .iter()
+            .map(|d| d.phases().into_iter().collect::<HashSet<_>>())
+            .collect();
+        DomainFilter {
+            rule: rule,
+            domains: domains,
+            phases: phases,
+        }
+    }
+
+    /// 時空図`history`の中で`min_coverage`以上の割合のセルを覆っている領域を取り除くフィルタを作る
+    ///
+    /// # Arguments
+    /// * `rule` - ウルフラムのルールコーディングの数字
+    /// * `history` - `cellular_automata`で計算した時空図(1行が1フェーズ)
+    /// * `max_spatial_period` - 探す領域の空間周期の上限
+    /// * `max_temporal_period` - 探す領域の時間周期の上限
+    /// * `min_coverage` - 領域とみなす被覆率(0.0から1.0)
+    pub fn detect(
+        rule: u8,
+        history: &Array2<u32>,
+        max_spatial_period: usize,
+        max_temporal_period: usize,
+        min_coverage: f64,
+    ) -> DomainFilter {
+        let cell_count = history.len().max(1) as f64;
+        let domains = find_domains(rule, max_spatial_period, max_temporal_period)
+            .into_iter()
+            .filter(|domain| {
+                let single = DomainFilter::new(rule, vec![domain.clone()]);
+                let covered = single.mask(history).iter().filter(|m| **m).count();
+                covered as f64 / cell_count >= min_coverage
+            })
+            .collect();
+        DomainFilter::new(rule, domains)
+    }
+
+    /// 取り除く領域
+    pub fn domains(&self) -> &[Domain] {
+        &self.domains
+    }
+
+    /// 1フェーズ分のセルのうち、領域に含まれるものを`true`にする
+    /// 周期的な並びがMIN_WINDOWセル以上(かつ2周期分以上)続き、かつ1つ前のフェーズ`previous`の同じ位置の並びを
+    /// 時間発展させたものと一致する場合に領域とみなす(`previous`が`None`なら前のフェーズとの比較はしない)
+    ///
+    /// # Arguments
+    /// * `previous` - 1つ前のフェーズ
+    /// * `row` - 今のフェーズ
+    pub fn row_mask(&self, previous: Option<ArrayView1<u32>>, row: ArrayView1<u32>) -> Vec<bool> {
+        let width = row.len();
+        let mut mask = vec![false; width];
+        for (domain, phases) in self.domains.iter().zip(self.phases.iter()) {
+            let p = domain.spatial_period;
+            // 周期性を確かめられるように少なくとも2周期分は見る
+            let window = ((MIN_WINDOW + p - 1) / p * p).max(2 * p);
+            if window > width {
+                continue;
+            }
+            for start in 0..width {
+                let head = match periodic_head(&row, start, p, window) {
+                    Some(ref head) if phases.contains(head) => head.clone(),
+                    _ => continue,
+                };
+                if let Some(ref previous) = previous {
+                    match periodic_head(previous, start, p, window) {
+                        Some(ref previous_head) if evolve(previous_head, self.rule) == head => {}
+                        _ => continue,
+                    }
+                }
+                for i in 0..window {
+                    mask[(start + i) % width] = true;
+                }
+            }
+        }
+        mask
+    }
+
+    /// 時空図の各セルのうち、領域に含まれるものを`true`にする
+    pub fn mask(&self, history: &Array2<u32>) -> Array2<bool> {
+        let mut mask = Array2::from_elem(history.dim(), false);
+        for t in 0..history.rows() {
+            let previous = if t == 0 { None } else { Some(history.row(t - 1)) };
+            for (x, m) in self.row_mask(previous, history.row(t)).into_iter().enumerate() {
+                mask[(t, x)] = m;
+            }
+        }
+        mask
+    }
+
+    /// 領域を白、欠陥を黒にした画像(`MatrixVisualizer`や`GrayImage::from_matrix`に渡せる)
+    pub fn filtered(&self, history: &Array2<u32>) -> Matrix<f32> {
+        self.mask(history).map(|m| if *m { 1.0 } else { 0.0 })
+    }
+}
+
+/// 領域の間を動く粒子(グライダー)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Particle {
+    /// 粒子の番号(`ParticleTracks::particles`の添字)
+    pub id: usize,
+    /// 各フェーズでの(時刻, 中心の位置)
+    pub positions: Vec<(usize, f64)>,
+    /// 1フェーズあたりに右に進むセルの数(最小二乗法で求める)
+    pub velocity: f64,
+}
+
+/// 粒子同士の衝突(合流や分裂)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Collision {
+    /// 衝突した時刻
+    pub time: usize,
+    /// 衝突した位置(衝突後の粒子の中心の平均)
+    pub position: f64,
+    /// 衝突した粒子の番号
+    pub incoming: Vec<usize>,
+    /// 衝突で生まれた粒子の番号
+    pub outgoing: Vec<usize>,
+}
+
+/// 粒子の追跡結果
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticleTracks {
+    /// 全ての粒子
+    pub particles: Vec<Particle>,
+    /// 全ての衝突
+    pub collisions: Vec<Collision>,
+}
+
+/// 1フェーズ内で連続する欠陥セルの区間
+struct Segment {
+    start: usize,
+    len: usize,
+}
+
+impl Segment {
+    fn center(&self, width: usize) -> f64 {
+        (self.start as f64 + (self.len as f64 - 1.0) / 2.0) % width as f64
+    }
+
+    /// `other`と(両側に`margin`セル広げて)重なっているかどうか
+    fn touches(&self, other: &Segment, margin: usize, width: usize) -> bool {
+        (0..self.len + 2 * margin).any(|i| {
+            let x = (self.start + width * margin + i - margin) % width;
+            (x + width - other.start) % width < other.len
+        })
+    }
+}
+
+fn segments(row: ArrayView1<bool>) -> Vec<Segment> {
+    let width = row.len();
+    // 領域のセルから数え始めると、端をまたぐ区間も1つにまとめられる
+    let origin = match (0..width).find(|x| row[*x]) {
+        Some(x) => x,
+        None => return vec![Segment { start: 0, len: width }],
+    };
+    let mut segments = Vec::new();
+    let mut current: Option<Segment> = None;
+    for i in 0..width {
+        let x = (origin + i) % width;
+        if !row[x] {
+            match current {
+                Some(ref mut segment) => segment.len += 1,
+                None => current = Some(Segment { start: x, len: 1 }),
+            }
+        } else if let Some(segment) = current.take() {
+            segments.push(segment);
+        }
+    }
+    if let Some(segment) = current {
+        segments.push(segment);
+    }
+    segments
+}
+
+/// 粒子を見失っても追跡を続けるフェーズの数
+/// 位相のずれた領域同士の境界は、フェーズによっては幅が0になり欠陥として見えなくなることがある
+pub const TRACKING_GAP: usize = 2;
+
+/// `DomainFilter::mask`の結果から欠陥(領域に含まれないセル)を粒子として追跡する
+/// 隣り合うフェーズで重なる欠陥の区間は同じ粒子とみなし、複数の区間が合流・分裂したところを衝突とする
+/// 見失った粒子も`TRACKING_GAP`フェーズの間は追跡を続ける
+pub fn track_particles(mask: &Array2<bool>) -> ParticleTracks {
+    let width = mask.cols();
+    let mut particles: Vec<Particle> = Vec::new();
+    let mut collisions = Vec::new();
+    // (最後に見つかった区間, 粒子の番号, 最後に見つかった時刻)
+    let mut previous: Vec<(Segment, usize, usize)> = Vec::new();
+
+    for (t, row) in mask.outer_iter().enumerate() {
+        let current = segments(row);
+        // 前のフェーズの区間と今のフェーズの区間のつながりを連結成分に分ける
+        let mut component = (0..previous.len() + current.len()).collect::<Vec<_>>();
+        for (i, &(ref p, _, last_seen)) in previous.iter().enumerate() {
+            for (j, c) in current.iter().enumerate() {
+                if p.touches(c, t - last_seen, width) {
+                    let a = find(&mut component, i);
+                    let b = find(&mut component, previous.len() + j);
+                    component[a] = b;
+                }
+            }
+        }
+
+        let mut next = Vec::new();
+        let roots = (0..component.len())
+            .map(|i| find(&mut component, i))
+            .collect::<Vec<_>>();
+        let groups = {
+            let mut unique = roots.clone();
+            unique.sort();
+            unique.dedup();
+            unique
+        };
+        let mut current = current.into_iter().map(Some).collect::<Vec<_>>();
+        for root in groups {
+            let incoming_indices = (0..previous.len()).filter(|i| roots[*i] == root).collect::<Vec<_>>();
+            let incoming = incoming_indices.iter().map(|i| previous[*i].1).collect::<Vec<_>>();
+            let members = (0..current.len())
+                .filter(|j| roots[previous.len() + *j] == root)
+                .collect::<Vec<_>>();
+            if incoming.len() == 1 && members.len() == 1 {
+                let segment = current[members[0]].take().unwrap();
+                particles[incoming[0]].positions.push((t, segment.center(width)));
+                next.push((segment, incoming[0], t));
+                continue;
+            }
+            if members.is_empty() && incoming.len() < 2 {
+                // 見失った粒子はしばらく追跡を続ける
+                for i in incoming_indices {
+                    if t - previous[i].2 <= TRACKING_GAP {
+                        let (ref segment, id, last_seen) = previous[i];
+                        next.push((
+                            Segment {
+                                start: segment.start,
+                                len: segment.len,
+                            },
+                            id,
+                            last_seen,
+                        ));
+                    }
+                }
+                continue;
+            }
+            let mut outgoing = Vec::new();
+            for j in members {
+                let segment = current[j].take().unwrap();
+                let id = particles.len();
+                particles.push(Particle {
+                    id: id,
+                    positions: vec![(t, segment.center(width))],
+                    velocity: 0.0,
+                });
+                outgoing.push(id);
+                next.push((segment, id, t));
+            }
+            if incoming.len() >= 2 || (incoming.len() == 1 && outgoing.len() >= 2) {
+                let position = if outgoing.is_empty() {
+                    incoming
+                        .iter()
+                        .map(|id| particles[*id].positions.last().unwrap().1)
+                        .sum::<f64>()
+                        / incoming.len() as f64
+                } else {
+                    outgoing.iter().map(|id| particles[*id].positions[0].1).sum::<f64>() / outgoing.len() as f64
+                };
+                collisions.push(Collision {
+                    time: t,
+                    position: position,
+                    incoming: incoming,
+                    outgoing: outgoing,
+                });
+            }
+        }
+        previous = next;
+    }
+
+    for particle in particles.iter_mut() {
+        particle.velocity = velocity(&particle.positions, width);
+    }
+    ParticleTracks {
+        particles: particles,
+        collisions: collisions,
+    }
+}
+
+/// union-findで`i`の属する連結成分の代表を求める
+fn find(component: &mut Vec<usize>, i: usize) -> usize {
+    let mut root = i;
+    while component[root] != root {
+        root = component[root];
+    }
+    component[i] = root;
+    root
+}
+
+/// 周期境界をまたいだ移動をつなげてから、位置を時刻について最小二乗法で直線近似した傾き
+fn velocity(positions: &[(usize, f64)], width: usize) -> f64 {
+    if positions.len() < 2 {
+        return 0.0;
+    }
+    let width = width as f64;
+    let mut unwrapped = vec![positions[0].1];
+    for pair in positions.windows(2) {
+        let mut delta = pair[1].1 - pair[0].1;
+        if delta > width / 2.0 {
+            delta -= width;
+        } else if delta < -width / 2.0 {
+            delta += width;
+        }
+        let last = *unwrapped.last().unwrap();
+        unwrapped.push(last + delta);
+    }
+    let n = positions.len() as f64;
+    let mean_t = positions.iter().map(|p| p.0 as f64).sum::<f64>() / n;
+    let mean_x = unwrapped.iter().sum::<f64>() / n;
+    let covariance = positions
+        .iter()
+        .zip(unwrapped.iter())
+        .map(|(p, x)| (p.0 as f64 - mean_t) * (x - mean_x))
+        .sum::<f64>();
+    let variance = positions.iter().map(|p| (p.0 as f64 - mean_t).powi(2)).sum::<f64>();
+    covariance / variance
+}
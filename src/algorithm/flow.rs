@@ -0,0 +1,221 @@
+use algorithm::stencil::{cast, Fields, Neighbor, Real};
+use rayon::prelude::*;
+use visualizer::matrix_visualizer::Matrix;
+
+/// 流れの速度場。xは列の増える向き、yは行の増える向きで、単位は(`dx`と同じ単位の長さ)/時間
+#[derive(Clone, Debug, PartialEq)]
+pub enum Velocity {
+    /// 一様な流れ(vx, vy)
+    Constant(f32, f32),
+    /// 単純せん断流。格子の中央の行からの距離yに比例した横向きの流れ vx = rate * y, vy = 0
+    /// 周期境界では上下の端で流れが不連続になる
+    Shear(f32),
+    /// セルごとの速度(vx, vy)。どちらも格子と同じ大きさでなければならない
+    Field(Matrix<f32>, Matrix<f32>),
+}
+
+impl Velocity {
+    /// (`i`, `j`)のセルでの速度(vx, vy)
+    fn at<T: Real>(&self, i: usize, j: usize, rows: usize, dx: T) -> (T, T) {
+        match *self {
+            Velocity::Constant(vx, vy) => (cast(vx), cast(vy)),
+            Velocity::Shear(rate) => {
+                let y = T::from(i).unwrap() - (T::from(rows).unwrap() - T::one()) / cast(2.0);
+                (cast::<T>(rate) * y * dx, T::zero())
+            }
+            Velocity::Field(ref vx, ref vy) => (cast(vx[[i, j]]), cast(vy[[i, j]])),
+        }
+    }
+
+    /// 格子全体での|vx| + |vy|の最大値
+    pub(crate) fn max_speed(&self, rows: usize, dx: f32) -> f32 {
+        match *self {
+            Velocity::Constant(vx, vy) => vx.abs() + vy.abs(),
+            Velocity::Shear(rate) => rate.abs() * (rows.max(1) as f32 - 1.0) / 2.0 * dx,
+            Velocity::Field(ref vx, ref vy) => vx
+                .iter()
+                .zip(vy.iter())
+                .fold(0.0, |m, (x, y)| m.max(x.abs() + y.abs())),
+        }
+    }
+
+    /// 1次の風上差分で、(`i`, `j`)のセルの移流項を上, 下, 左, 右の隣のセルとの差の重み付き和にしたときの重み
+    /// 流れの来る側の隣のセルにだけ、流れの速さ / dxの重みが付く
+    pub(crate) fn upwind_weights<T: Real>(&self, i: usize, j: usize, rows: usize, dx: T) -> [T; 4] {
+        let (vx, vy) = self.at(i, j, rows, dx);
+        let (ax, ay) = (vx.abs() / dx, vy.abs() / dx);
+        let mut weights = [T::zero(); 4];
+        weights[if vy > T::zero() { 0 } else { 1 }] = ay;
+        weights[if vx > T::zero() { 2 } else { 3 }] = ax;
+        weights
+    }
+}
+
+/// 移流の計算方法
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Advection {
+    /// 1次の風上差分。移流項を時間微分に加えるので、どの積分法とも組み合わせられる
+    /// Courant数(|vx| + |vy|) * dt / dxと拡散数の和が1以下で安定(`StencilSolver::check_flow`)
+    Upwind,
+    /// セミラグランジュ法。反応拡散で`dt`進めたあと、流れを`dt`だけ遡った位置の値を双線形補間で取ってくる
+    /// 刻み幅によらず安定だが、風上差分より数値拡散が大きい
+    SemiLagrangian,
+}
+
+/// 反応拡散系に加える流れ(移流)
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::flow::{Advection, Flow, Velocity};
+/// use my_alife::algorithm::stencil::StencilSolver;
+/// use ndarray::Array2;
+///
+/// // 1列だけuが1の帯を右向きの一様な流れで運ぶ
+/// let u = Array2::from_shape_fn((4, 32), |(_, j)| if j == 4 { 1.0 } else { 0.0 });
+/// let center = |solver: &StencilSolver| {
+///     let row = solver.state().0.row(0).to_vec();
+///     let total: f32 = row.iter().sum();
+///     row.iter().enumerate().map(|(j, e)| j as f32 * e).sum::<f32>() / total
+/// };
+/// for &advection in &[Advection::Upwind, Advection::SemiLagrangian] {
+///     let mut solver = StencilSolver::new((u.clone(), Array2::zeros((4, 32))));
+///     // 時間1で0.5セル、20ステップで10セル進む
+///     solver.set_flow(Some(Flow::new(Velocity::Constant(0.005, 0.0), advection))).unwrap();
+///     assert!(solver.check_flow(0.0, 0.01, 1.0).is_ok());
+///     for _ in 0..20 {
+///         solver.step((0.0, 0.0), 0.01, 1.0, |_, _| (0.0, 0.0));
+///     }
+///     assert!((center(&solver) - 14.0).abs() < 1e-3);
+///     // 周期境界なので総量は変わらない
+///     assert!((solver.state().0.scalar_sum() - u.scalar_sum()).abs() < 1e-4);
+/// }
+///
+/// // 風上差分はCourant数が1を超えると不安定になる。拡散があるとさらに小さい刻み幅が必要になる
+/// let mut solver = StencilSolver::new((u.clone(), Array2::zeros((4, 32))));
+/// solver.set_flow(Some(Flow::new(Velocity::Constant(0.02, 0.0), Advection::Upwind))).unwrap();
+/// assert!(solver.check_flow(0.0, 0.01, 1.0).is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flow {
+    /// 速度場
+    pub velocity: Velocity,
+    /// 移流の計算方法
+    pub advection: Advection,
+}
+
+impl Flow {
+    /// 速度場と移流の計算方法から生成する
+    pub fn new(velocity: Velocity, advection: Advection) -> Flow {
+        Flow {
+            velocity: velocity,
+            advection: advection,
+        }
+    }
+}
+
+/// セミラグランジュ法で1回移流するための設定
+pub(crate) struct SemiLagrangian<'a, T: 'a> {
+    pub velocity: &'a Velocity,
+    pub rows: usize,
+    pub cols: usize,
+    pub periodic: bool,
+    // 格子の外側の各成分の値。Noneなら格子の端のセルの値を使う
+    pub outside: Option<&'a [T]>,
+    pub mask: Option<&'a [bool]>,
+    pub dx: T,
+    pub dt: T,
+}
+
+impl<'a, T: Real> SemiLagrangian<'a, T> {
+    fn is_obstacle(&self, index: usize) -> bool {
+        self.mask.map_or(false, |m| m[index])
+    }
+
+    /// 時間`dt`だけ移流した`state`を`out`に書き込む
+    /// 各セルから流れを遡った位置の値を、周囲の4つのセルから双線形補間で求める
+    pub fn advect<S: Fields<T>>(&self, state: &S, out: &mut S) {
+        let values = state.slices();
+        let mut next = out.slices_mut();
+        let stride = values.stride();
+        for part in 0..values.parts() {
+            let source = values.part(part);
+            next.part_mut(part)
+                .par_chunks_mut(self.cols * stride)
+                .enumerate()
+                .for_each(|(i, row)| self.row(source, row, part * stride, i));
+        }
+    }
+
+    /// `i`行目を`row`に書き込む。`source`と`row`はセルごとに成分を並べたもので、先頭の成分の番号が`first`
+    fn row(&self, source: &[T], row: &mut [T], first: usize, i: usize) {
+        let (rows, cols, dx, dt) = (self.rows, self.cols, self.dx, self.dt);
+        let stride = row.len() / cols;
+        for (j, cell) in row.chunks_mut(stride).enumerate() {
+            let center = i * cols + j;
+            let here = &source[center * stride..(center + 1) * stride];
+            if self.is_obstacle(center) {
+                cell.copy_from_slice(here);
+                continue;
+            }
+            let (vx, vy) = self.velocity.at(i, j, rows, dx);
+            let x = T::from(j).unwrap() - vx * dt / dx;
+            let y = T::from(i).unwrap() - vy * dt / dx;
+            let (x0, y0) = (x.floor(), y.floor());
+            let (fx, fy) = (x - x0, y - y0);
+            // 格子の外側や障害物の値は、拡散と同じ規則で置き換える
+            let periodic = self.periodic;
+            let wrap = |yi: isize, xi: isize| (wrap_index(yi, rows, periodic), wrap_index(xi, cols, periodic));
+            let source_cell = |yi: isize, xi: isize| match wrap(yi, xi) {
+                (Some(row), Some(col)) => {
+                    if self.is_obstacle(row * cols + col) {
+                        Neighbor::Center
+                    } else {
+                        Neighbor::Cell(row * cols + col)
+                    }
+                }
+                _ if self.outside.is_some() => Neighbor::Outside,
+                _ => {
+                    let row = yi.max(0).min(rows as isize - 1) as usize;
+                    let col = xi.max(0).min(cols as isize - 1) as usize;
+                    let index = row * cols + col;
+                    if self.is_obstacle(index) {
+                        Neighbor::Center
+                    } else {
+                        Neighbor::Cell(index)
+                    }
+                }
+            };
+            let (y0, x0) = (y0.to_isize().unwrap(), x0.to_isize().unwrap());
+            let one = T::one();
+            let corners = [
+                ((one - fy) * (one - fx), source_cell(y0, x0)),
+                ((one - fy) * fx, source_cell(y0, x0 + 1)),
+                (fy * (one - fx), source_cell(y0 + 1, x0)),
+                (fy * fx, source_cell(y0 + 1, x0 + 1)),
+            ];
+            for (s, out) in cell.iter_mut().enumerate() {
+                let mut sum = T::zero();
+                for &(weight, neighbor) in &corners {
+                    let value = neighbor.value_with(|n| source[n * stride + s], first + s, here[s], self.outside);
+                    sum = sum + weight * value;
+                }
+                *out = sum;
+            }
+        }
+    }
+}
+
+/// 長さ`len`の軸で、格子の外側かもしれない`index`に当たる添字。周期境界でなければ外側はNone
+fn wrap_index(index: isize, len: usize, periodic: bool) -> Option<usize> {
+    let len = len as isize;
+    if index >= 0 && index < len {
+        Some(index as usize)
+    } else if periodic {
+        Some((((index % len) + len) % len) as usize)
+    } else {
+        None
+    }
+}
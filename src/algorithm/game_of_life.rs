@@ -10,28 +10,39 @@ use std::mem;
 pub fn game_of_life(state: &mut Vec<Vec<u8>>, next_state: &mut Vec<Vec<u8>>, height: usize, width: usize) {
     for i in 0..height {
         for j in 0..width {
-            let nw = state[(i + height - 1) % height][(j + width - 1) % width];
-            let n = state[(i + height - 1) % height][j];
-            let ne = state[(i + height - 1) % height][(j + 1) % width];
-            let w = state[i][(j + width - 1) % width];
-            let c = state[i][j];
-            let e = state[i][(j + 1) % width];
-            let sw = state[(i + 1) % height][(j + width - 1) % width];
-            let s = state[(i + 1) % height][j];
-            let se = state[(i + 1) % height][(j + 1) % width];
-            let neighbor_cell_sum = nw + n + ne + w + e + sw + s + se;
-            if c == 0 && neighbor_cell_sum == 3 {
-                next_state[i][j] = 1;
-            } else if c == 1 && (neighbor_cell_sum == 2 || neighbor_cell_sum == 3) {
-                next_state[i][j] = 1;
-            } else {
-                next_state[i][j] = 0;
-            }
+            next_state[i][j] = next_cell_state(state, i, j, height, width);
         }
     }
     mem::swap(state, next_state);
 }
 
+/// `state`の(`i`, `j`)にあるセルの次の瞬間の生死を、周囲8セルの生死から求める
+/// # Arguments
+/// * `state` - 現在の状態
+/// * `i` - セルの行
+/// * `j` - セルの列
+/// * `height` - セルの縦の数
+/// * `width` - セルの横の数
+pub fn next_cell_state(state: &Vec<Vec<u8>>, i: usize, j: usize, height: usize, width: usize) -> u8 {
+    let nw = state[(i + height - 1) % height][(j + width - 1) % width];
+    let n = state[(i + height - 1) % height][j];
+    let ne = state[(i + height - 1) % height][(j + 1) % width];
+    let w = state[i][(j + width - 1) % width];
+    let c = state[i][j];
+    let e = state[i][(j + 1) % width];
+    let sw = state[(i + 1) % height][(j + width - 1) % width];
+    let s = state[(i + 1) % height][j];
+    let se = state[(i + 1) % height][(j + 1) % width];
+    let neighbor_cell_sum = nw + n + ne + w + e + sw + s + se;
+    if c == 0 && neighbor_cell_sum == 3 {
+        1
+    } else if c == 1 && (neighbor_cell_sum == 2 || neighbor_cell_sum == 3) {
+        1
+    } else {
+        0
+    }
+}
+
 use std::sync::Arc;
 /// ライフゲームのアルゴリズム
 /// 現在のstateを元に次の瞬間のstate(next_state)を計算し返り値として返す
@@ -1,51 +1,785 @@
+use algorithm::diffusion_tensor::DiffusionTensor;
+use algorithm::flow::Flow;
+use algorithm::reaction_diffusion::{check_stability, check_stability_varying, Discretization, ReactionDiffusion};
+use algorithm::stencil::{cast, Boundary, Integrator, Real, StencilSolver};
+use failure;
 use ndarray::Array;
 use ndarray::Array2;
 use ndarray_rand::RandomExt;
 use ndarray_rand::F32;
-use num::cast as num_cast;
-use num::Integer;
-use num_traits::cast as num_trait_cast;
 use rand::distributions::Range;
+use rand::Rng;
+use std::mem;
 use std::ops::AddAssign;
 use visualizer::matrix_visualizer::Matrix;
 
-// simulation parameter
-const DX: f32 = 0.01;
-const DT: u32 = 1;
-const VISUALIZATION_STEP: usize = 8;
-const SPACE_GRID_SIZE: usize = 256;
+/// Gray-Scottモデルのパラメーター
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GrayScottParams {
+    /// uの拡散係数
+    pub du: f32,
+    /// vの拡散係数
+    pub dv: f32,
+    /// 格子の間隔
+    pub dx: f32,
+    /// 時間の刻み幅
+    pub dt: f32,
+    /// 1回の更新(描画)あたりに進める時間ステップ数
+    pub substeps: usize,
+    /// uの供給率(feed rate)
+    pub f: f32,
+    /// vの除去率(kill rate)
+    pub k: f32,
+    /// 格子の端の境界条件
+    pub boundary: Boundary,
+    /// 時間積分の方法
+    pub integrator: Integrator,
+}
+
+impl Default for GrayScottParams {
+    /// 本のサンプルコードと同じパラメーター
+    fn default() -> GrayScottParams {
+        GrayScottParams {
+            du: 2e-5,
+            dv: 1e-5,
+            dx: 0.01,
+            dt: 1.0,
+            substeps: 8,
+            f: 0.04,
+            k: 0.06,
+            boundary: Boundary::Periodic,
+            integrator: Integrator::Euler,
+        }
+    }
+}
+
+impl GrayScottParams {
+    /// `f`と`k`以外はデフォルト値のGrayScottParamsインスタンスを生成する
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::algorithm::gray_scott::GrayScottParams;
+    ///
+    /// let params = GrayScottParams::new(0.022, 0.051);
+    /// assert_eq!(params.du, GrayScottParams::default().du);
+    /// ```
+    pub fn new(f: f32, k: f32) -> GrayScottParams {
+        GrayScottParams {
+            f: f,
+            k: k,
+            ..Default::default()
+        }
+    }
+
+    /// 離散化の設定(`dx`, `dt`, `substeps`, `boundary`, `integrator`)
+    pub fn discretization(&self) -> Discretization {
+        Discretization {
+            dx: self.dx,
+            dt: self.dt,
+            substeps: self.substeps,
+            boundary: self.boundary,
+            integrator: self.integrator,
+        }
+    }
+}
+
+impl ReactionDiffusion for GrayScottParams {
+    fn diffusion(&self) -> (f32, f32) {
+        (self.du, self.dv)
+    }
+
+    /// Gray-Scottモデル方程式の反応項
+    fn reaction<T: Real>(&self, u: T, v: T) -> (T, T) {
+        reaction(cast(self.f), cast(self.k), u, v)
+    }
+
+    fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        initial_state(shape)
+    }
+}
+
+/// 場所ごとに変えるGray-Scottモデルのパラメーターのマップ
+/// マップを指定した(`Some`の)パラメーターはセルごとの値を使い、`None`のパラメーターは`GrayScottParams`の値を使う。
+/// マップは`parameter_map`モジュールの関数で作れる
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::gray_scott::{GrayScottParams, ParameterMaps};
+/// use my_alife::algorithm::parameter_map::linear;
+/// use ndarray::Axis;
+///
+/// // 左から右にKを変えると、1回の計算で模様の移り変わりが見られる
+/// let maps = ParameterMaps {
+///     k: Some(linear((4, 8), Axis(1), (0.05, 0.07))),
+///     ..Default::default()
+/// };
+/// let params = maps.params_at(&GrayScottParams::new(0.04, 0.06), 7);
+/// assert_eq!((params.f, params.k), (0.04, 0.07));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ParameterMaps {
+    /// uの供給率のマップ
+    pub f: Option<Matrix<f32>>,
+    /// vの除去率のマップ
+    pub k: Option<Matrix<f32>>,
+    /// uの拡散係数のマップ
+    pub du: Option<Matrix<f32>>,
+    /// vの拡散係数のマップ
+    pub dv: Option<Matrix<f32>>,
+}
+
+impl ParameterMaps {
+    /// マップが1つもないかどうか
+    pub fn is_empty(&self) -> bool {
+        self.f.is_none() && self.k.is_none() && self.du.is_none() && self.dv.is_none()
+    }
+
+    /// 全てのマップの大きさが`shape`と同じか調べる
+    pub fn check_shape(&self, shape: (usize, usize)) -> Result<(), failure::Error> {
+        let maps = [("f", &self.f), ("k", &self.k), ("du", &self.du), ("dv", &self.dv)];
+        for &(name, map) in &maps {
+            if let Some(ref map) = *map {
+                if map.dim() != shape {
+                    return Err(failure::err_msg(format!(
+                        "{} map shape {:?} does not match the state shape {:?}",
+                        name,
+                        map.dim(),
+                        shape
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `index`番目(行優先で数えたもの)のセルでのパラメーター
+    ///
+    /// # Arguments
+    /// * `params` - マップがないパラメーターに使う値
+    /// * `index` - セルの添字
+    pub fn params_at(&self, params: &GrayScottParams, index: usize) -> GrayScottParams {
+        let value = |map: &Option<Matrix<f32>>, default: f32| match *map {
+            Some(ref map) => {
+                let cols = map.cols();
+                map[[index / cols, index % cols]]
+            }
+            None => default,
+        };
+        GrayScottParams {
+            f: value(&self.f, params.f),
+            k: value(&self.k, params.k),
+            du: value(&self.du, params.du),
+            dv: value(&self.dv, params.dv),
+            ..*params
+        }
+    }
+}
+
+/// Gray-Scottモデルのシミュレーター
+/// 格子は任意の大きさの長方形で良い
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::gray_scott::{GrayScott, GrayScottParams};
+///
+/// let mut simulator = GrayScott::new(GrayScottParams::new(0.035, 0.065), (128, 192));
+/// simulator.step();
+/// assert_eq!(simulator.state().0.dim(), (128, 192));
+/// ```
+pub struct GrayScott<T = f32> {
+    params: GrayScottParams,
+    maps: ParameterMaps,
+    solver: StencilSolver<T>,
+}
+
+impl GrayScott {
+    /// `initial_state`で作った初期状態からGrayScottインスタンスを生成する
+    /// 状態は`f32`で持つ。`f64`で計算する場合は`from_state`を使う
+    ///
+    /// # Arguments
+    /// * `params` - パラメーター
+    /// * `shape` - 格子の大きさ(行数, 列数)
+    pub fn new(params: GrayScottParams, shape: (usize, usize)) -> GrayScott {
+        Self::from_state(params, initial_state(shape))
+    }
+}
+
+impl<T: Real> GrayScott<T> {
+    /// 初期状態を指定してGrayScottインスタンスを生成する
+    /// 状態の値の型(`f32`か`f64`)で計算する。パラメーターは`f32`のまま持ち、計算のときに変換する
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::algorithm::gray_scott::{initial_state, GrayScott, GrayScottParams};
+    /// use my_alife::algorithm::stencil::cast_state;
+    ///
+    /// let state = initial_state((32, 32));
+    /// let mut single = GrayScott::from_state(GrayScottParams::default(), state.clone());
+    /// let mut double = GrayScott::from_state(GrayScottParams::default(), cast_state::<f32, f64>(&state));
+    /// for _ in 0..20 {
+    ///     single.step();
+    ///     double.step();
+    /// }
+    /// // 同じ計算だが、丸め誤差の分だけ結果がずれる
+    /// let drift = cast_state::<f64, f32>(double.state())
+    ///     .0
+    ///     .iter()
+    ///     .zip(single.state().0.iter())
+    ///     .fold(0.0f32, |m, (a, b)| m.max((a - b).abs()));
+    /// assert!(drift > 0.0 && drift < 1e-4);
+    /// ```
+    pub fn from_state(params: GrayScottParams, state: (Matrix<T>, Matrix<T>)) -> GrayScott<T> {
+        GrayScott {
+            params: params,
+            maps: ParameterMaps::default(),
+            solver: StencilSolver::new(state),
+        }
+    }
+
+    /// パラメーター
+    pub fn params(&self) -> &GrayScottParams {
+        &self.params
+    }
+
+    /// パラメーターを変更する
+    pub fn params_mut(&mut self) -> &mut GrayScottParams {
+        &mut self.params
+    }
+
+    /// 場所ごとのパラメーターのマップ
+    pub fn maps(&self) -> &ParameterMaps {
+        &self.maps
+    }
+
+    /// 場所ごとのパラメーターのマップを変更する。マップの大きさが格子と違う場合はエラーになる
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ndarray;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::algorithm::gray_scott::{GrayScott, GrayScottParams, ParameterMaps};
+    /// use my_alife::algorithm::parameter_map::{linear, radial};
+    /// use ndarray::Axis;
+    ///
+    /// let mut simulator = GrayScott::new(GrayScottParams::default(), (64, 64));
+    /// let maps = ParameterMaps {
+    ///     f: Some(linear((64, 64), Axis(0), (0.02, 0.05))),
+    ///     k: Some(radial((64, 64), (32.0, 32.0), 32.0, (0.065, 0.055))),
+    ///     ..Default::default()
+    /// };
+    /// simulator.set_maps(maps).unwrap();
+    /// assert!(simulator.check_stability().is_ok());
+    /// simulator.step();
+    ///
+    /// let wrong = ParameterMaps {
+    ///     f: Some(linear((32, 32), Axis(0), (0.02, 0.05))),
+    ///     ..Default::default()
+    /// };
+    /// assert!(simulator.set_maps(wrong).is_err());
+    /// ```
+    pub fn set_maps(&mut self, maps: ParameterMaps) -> Result<(), failure::Error> {
+        maps.check_shape(self.solver.state().0.dim())?;
+        self.maps = maps;
+        Ok(())
+    }
+
+    /// 障害物のマスク
+    pub fn mask(&self) -> Option<&Array2<bool>> {
+        self.solver.mask()
+    }
+
+    /// 障害物のマスクを変更する。`true`のセルが障害物になり、拡散も反応も起こらなくなる
+    /// 画像から読み込む場合は`GrayImage::threshold`を使う
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ndarray;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::algorithm::gray_scott::{GrayScott, GrayScottParams};
+    /// use my_alife::algorithm::stencil::Boundary;
+    /// use ndarray::Array2;
+    ///
+    /// let params = GrayScottParams {
+    ///     boundary: Boundary::Neumann,
+    ///     ..GrayScottParams::new(0.035, 0.065)
+    /// };
+    /// let mut simulator = GrayScott::new(params, (64, 64));
+    /// // 中心から半径24より外側を障害物にして、円の中だけで模様を作る
+    /// let mask = Array2::from_shape_fn((64, 64), |(i, j)| {
+    ///     let (y, x) = (i as f32 - 32.0, j as f32 - 32.0);
+    ///     x * x + y * y > 24.0 * 24.0
+    /// });
+    /// let corner = simulator.state().0[[0, 0]];
+    /// simulator.set_mask(Some(mask)).unwrap();
+    /// simulator.step();
+    /// assert_eq!(simulator.state().0[[0, 0]], corner);
+    /// ```
+    pub fn set_mask(&mut self, mask: Option<Array2<bool>>) -> Result<(), failure::Error> {
+        self.solver.set_mask(mask)
+    }
+
+    /// 流れ(移流)
+    pub fn flow(&self) -> Option<&Flow> {
+        self.solver.flow()
+    }
+
+    /// 流れを変更する。`None`なら流れはない。速度場の大きさが格子と違う場合はエラーになる
+    /// 風上差分では`check_stability`がCourant数も調べる。
+    /// デフォルトのパラメーターは拡散だけで安定な範囲の上限に近いので、速い流れにはセミラグランジュ法を使う
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::algorithm::gray_scott::{GrayScott, GrayScottParams};
+    /// use my_alife::algorithm::flow::{Advection, Flow, Velocity};
+    ///
+    /// let mut simulator = GrayScott::new(GrayScottParams::default(), (64, 64));
+    /// // 上下の端で速さ約0.0016(1ステップで約0.16セル)のせん断流
+    /// simulator.set_flow(Some(Flow::new(Velocity::Shear(0.005), Advection::Upwind))).unwrap();
+    /// assert!(simulator.check_stability().is_ok());
+    ///
+    /// // 風上差分では拡散数(4 * Du * dt / dx^2 = 0.8)とCourant数の和が1を超えるとエラーになるが、
+    /// // セミラグランジュ法なら安定
+    /// simulator.set_flow(Some(Flow::new(Velocity::Shear(0.01), Advection::Upwind))).unwrap();
+    /// assert!(simulator.check_stability().is_err());
+    /// simulator.set_flow(Some(Flow::new(Velocity::Shear(0.01), Advection::SemiLagrangian))).unwrap();
+    /// assert!(simulator.check_stability().is_ok());
+    /// simulator.step();
+    /// assert!(simulator.state().0.iter().all(|u| u.is_finite()));
+    /// ```
+    pub fn set_flow(&mut self, flow: Option<Flow>) -> Result<(), failure::Error> {
+        self.solver.set_flow(flow)
+    }
+
+    /// 拡散テンソル
+    pub fn anisotropy(&self) -> DiffusionTensor {
+        self.solver.anisotropy()
+    }
+
+    /// 拡散テンソルを変更する。uとvの両方の拡散係数に掛かる。半正定値でない場合はエラーになる
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::algorithm::gray_scott::{GrayScott, GrayScottParams};
+    /// use my_alife::algorithm::diffusion_tensor::DiffusionTensor;
+    ///
+    /// let mut simulator = GrayScott::new(GrayScottParams::default(), (64, 64));
+    /// // 斜め45度の向きに強く拡散すると、縞がその向きに揃いやすくなる
+    /// let tensor = DiffusionTensor::oriented(::std::f32::consts::FRAC_PI_4, 1.5, 0.5);
+    /// simulator.set_anisotropy(tensor).unwrap();
+    /// assert!(simulator.check_stability().is_ok());
+    /// simulator.step();
+    ///
+    /// // 強すぎる拡散は陽的Euler法で不安定になる
+    /// simulator.set_anisotropy(DiffusionTensor::oriented(0.0, 4.0, 1.0)).unwrap();
+    /// assert!(simulator.check_stability().is_err());
+    /// ```
+    pub fn set_anisotropy(&mut self, anisotropy: DiffusionTensor) -> Result<(), failure::Error> {
+        self.solver.set_anisotropy(anisotropy)
+    }
+
+    /// 現在の状態(u, v)
+    pub fn state(&self) -> &(Matrix<T>, Matrix<T>) {
+        self.solver.state()
+    }
+
+    /// 現在の状態(u, v)を書き換える。格子の大きさは変えてはいけない
+    /// 描画側などが持つ状態と`mem::swap`で入れ替えれば、コピーせずにこのインスタンスで時間発展させられる
+    pub fn state_mut(&mut self) -> &mut (Matrix<T>, Matrix<T>) {
+        self.solver.state_mut()
+    }
+
+    /// 現在の状態を取り出す
+    pub fn into_state(self) -> (Matrix<T>, Matrix<T>) {
+        self.solver.into_state()
+    }
+
+    /// 現在の状態とパラメーターで、時間の刻み幅が安定な範囲にあるか調べる
+    /// `dt`を大きくしたときなどに、値が発散する前にエラーにできる
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::algorithm::gray_scott::{GrayScott, GrayScottParams};
+    /// use my_alife::algorithm::stencil::Integrator;
+    ///
+    /// let mut simulator = GrayScott::new(GrayScottParams::default(), (32, 32));
+    /// assert!(simulator.check_stability().is_ok());
+    /// simulator.params_mut().dt = 1.5;
+    /// assert!(simulator.check_stability().is_err());
+    /// // 4次のRunge-Kutta法は安定な範囲が広い
+    /// simulator.params_mut().integrator = Integrator::RungeKutta4;
+    /// assert!(simulator.check_stability().is_ok());
+    /// ```
+    pub fn check_stability(&self) -> Result<(), failure::Error> {
+        let discretization = self.params.discretization();
+        // 異方的な拡散は、固有値が同じになるように拡散係数を大きくした等方的な拡散とみなす
+        let factor = self.solver.anisotropy().spectral_factor();
+        let scaled = |params: GrayScottParams| GrayScottParams {
+            du: params.du * factor,
+            dv: params.dv * factor,
+            ..params
+        };
+        if self.maps.is_empty() {
+            check_stability(&scaled(self.params), &discretization, self.solver.state())?;
+        } else {
+            check_stability_varying(
+                |index| scaled(self.maps.params_at(&self.params, index)),
+                &discretization,
+                self.solver.state(),
+            )?;
+        }
+        let diffusion = if self.maps.is_empty() {
+            self.params.du.max(self.params.dv)
+        } else {
+            (0..self.solver.state().0.len())
+                .map(|index| self.maps.params_at(&self.params, index))
+                .fold(0.0f32, |m, params| m.max(params.du).max(params.dv))
+        };
+        self.solver.check_flow(diffusion, self.params.dx, self.params.dt)
+    }
 
-// model parameter
-const DU: f32 = 2e-5;
-const DV: f32 = 1e-5;
+    /// `params.substeps`ステップだけ時間を進める
+    /// 書き込み先のバッファは使い回すので、新しい配列は確保しない
+    pub fn step(&mut self) {
+        step_solver(&mut self.solver, &self.params, &self.maps);
+    }
+}
 
 /// Matrixの初期状態の一例
 pub fn initial_matrix() -> (Matrix<f32>, Matrix<f32>) {
-    // initialize
-    let mut u = Array2::<f32>::ones((256, 256));
-    let mut v = Array2::<f32>::zeros((256, 256));
-
-    // 中央にSQUARE_SIZE四方の正方形を置く
-    const SQUARE_SIZE: usize = 20;
-    u.slice_mut(s![
-        SPACE_GRID_SIZE / 2 - SQUARE_SIZE / 2..SPACE_GRID_SIZE / 2 + SQUARE_SIZE / 2,
-        SPACE_GRID_SIZE / 2 - SQUARE_SIZE / 2..SPACE_GRID_SIZE / 2 + SQUARE_SIZE / 2,
-    ]).fill(0.5);
-    v.slice_mut(s![
-        SPACE_GRID_SIZE / 2 - SQUARE_SIZE / 2..SPACE_GRID_SIZE / 2 + SQUARE_SIZE / 2,
-        SPACE_GRID_SIZE / 2 - SQUARE_SIZE / 2..SPACE_GRID_SIZE / 2 + SQUARE_SIZE / 2,
-    ]).fill(0.25);
-
-    // 対称性を崩すため少しノイズを入れる
-    let u_rand = Array::random((SPACE_GRID_SIZE, SPACE_GRID_SIZE), F32(Range::new(0., 1.))) * 0.1;
-    let v_rand = Array::random((SPACE_GRID_SIZE, SPACE_GRID_SIZE), F32(Range::new(0., 1.))) * 0.1;
+    initial_state((256, 256))
+}
+
+/// 任意の大きさのMatrixの初期状態を作る
+/// 中央に20四方の正方形を置き、対称性を崩すため少しノイズを入れる
+///
+/// # Arguments
+/// * `shape` - 格子の大きさ(行数, 列数)
+pub fn initial_state(shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+    InitialCondition::CenterSquare.state(shape)
+}
+
+// 初期状態に置く正方形の大きさ
+const SQUARE_SIZE: usize = 20;
+
+/// 初期状態の置き方
+/// 円や文字、ノイズなどを組み合わせた初期状態は`initial_condition::StateGenerator`で作れる
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InitialCondition {
+    /// 中央にSQUARE_SIZE四方の正方形を1つ置く(本のサンプルコードと同じ)
+    CenterSquare,
+    /// ランダムな位置にSQUARE_SIZE四方の正方形を`count`個置く
+    /// 正方形の位置もノイズも`seed`から決まるので、同じseedなら同じ初期状態になる
+    RandomSquares {
+        /// 正方形の個数
+        count: usize,
+        /// 乱数のseed
+        seed: usize,
+    },
+}
+
+impl InitialCondition {
+    /// 初期状態を作る。どちらの場合も対称性を崩すため少しノイズを入れる
+    ///
+    /// # Arguments
+    /// * `shape` - 格子の大きさ(行数, 列数)
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::algorithm::gray_scott::InitialCondition;
+    ///
+    /// let condition = InitialCondition::RandomSquares { count: 3, seed: 7 };
+    /// let (u, v) = condition.state((64, 48));
+    /// assert_eq!(condition.state((64, 48)), (u.clone(), v.clone()));
+    /// assert!(v.iter().any(|&e| e >= 0.25));
+    /// let (other, _) = InitialCondition::RandomSquares { count: 3, seed: 8 }.state((64, 48));
+    /// assert!(other != u);
+    /// ```
+    pub fn state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        use rand::{thread_rng, SeedableRng, StdRng};
+
+        // initialize
+        let mut u = Array2::<f32>::ones(shape);
+        let mut v = Array2::<f32>::zeros(shape);
+
+        let (rows, cols) = shape;
+        match *self {
+            InitialCondition::CenterSquare => {
+                put_square(&mut u, &mut v, rows / 2, cols / 2);
+                add_noise(&mut u, &mut v, &mut thread_rng());
+            }
+            InitialCondition::RandomSquares { count, seed } => {
+                let mut rng = StdRng::from_seed(&[seed][..]);
+                for _ in 0..count {
+                    let row = rng.gen_range(0, rows.max(1));
+                    let col = rng.gen_range(0, cols.max(1));
+                    put_square(&mut u, &mut v, row, col);
+                }
+                add_noise(&mut u, &mut v, &mut rng);
+            }
+        }
+
+        (u, v)
+    }
+}
+
+/// 対称性を崩すため、`rng`で作った[0, 0.1)の一様乱数をuとvに足す
+fn add_noise<R: Rng>(u: &mut Matrix<f32>, v: &mut Matrix<f32>, rng: &mut R) {
+    let u_rand = Array::random_using(u.dim(), F32(Range::new(0., 1.)), rng) * 0.1;
+    let v_rand = Array::random_using(v.dim(), F32(Range::new(0., 1.)), rng) * 0.1;
     u.add_assign(&u_rand);
     v.add_assign(&v_rand);
+}
+
+/// (`row`, `col`)を中心にSQUARE_SIZE四方の正方形を置く。はみ出した部分は切り捨てる
+fn put_square(u: &mut Matrix<f32>, v: &mut Matrix<f32>, row: usize, col: usize) {
+    let (rows, cols) = u.dim();
+    let row_range = row.saturating_sub(SQUARE_SIZE / 2)..(row + SQUARE_SIZE / 2).min(rows);
+    let col_range = col.saturating_sub(SQUARE_SIZE / 2)..(col + SQUARE_SIZE / 2).min(cols);
+    u.slice_mut(s![row_range.clone(), col_range.clone()]).fill(0.5);
+    v.slice_mut(s![row_range, col_range]).fill(0.25);
+}
+
+/// 名前のついたパラメーターの組
+/// Pearson(1993)の分類(alphaからmu)と、本で紹介されているパターンを収録している
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Preset {
+    /// 名前
+    pub name: &'static str,
+    /// どんなパターンになるか
+    pub description: &'static str,
+    /// uの供給率(feed rate)
+    pub f: f32,
+    /// vの除去率(kill rate)
+    pub k: f32,
+    /// おすすめの初期状態
+    pub initial_condition: InitialCondition,
+}
+
+impl Preset {
+    /// `f`と`k`以外はデフォルト値のパラメーター
+    pub fn params(&self) -> GrayScottParams {
+        GrayScottParams::new(self.f, self.k)
+    }
+
+    /// おすすめの初期状態を作る
+    ///
+    /// # Arguments
+    /// * `shape` - 格子の大きさ(行数, 列数)
+    pub fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        self.initial_condition.state(shape)
+    }
+}
+
+/// 全てのプリセット
+pub const PRESETS: &[Preset] = &[
+    // 本で紹介されているパターン
+    Preset {
+        name: "spot",
+        description: "斑点",
+        f: 0.035,
+        k: 0.065,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "stripe",
+        description: "縞",
+        f: 0.022,
+        k: 0.051,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "wave",
+        description: "波",
+        f: 0.025,
+        k: 0.05,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "bubble",
+        description: "泡",
+        f: 0.012,
+        k: 0.05,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "amorphous",
+        description: "不定形",
+        f: 0.04,
+        k: 0.06,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    // Pearson(1993)の分類
+    Preset {
+        name: "alpha",
+        description: "カオス的に生成と消滅を繰り返す波",
+        f: 0.010,
+        k: 0.047,
+        initial_condition: InitialCondition::RandomSquares { count: 8, seed: 1 },
+    },
+    Preset {
+        name: "beta",
+        description: "カオス的に振る舞う波",
+        f: 0.026,
+        k: 0.051,
+        initial_condition: InitialCondition::RandomSquares { count: 8, seed: 1 },
+    },
+    Preset {
+        name: "gamma",
+        description: "縞と斑点が入り混じって揺らぐパターン",
+        f: 0.022,
+        k: 0.051,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "delta",
+        description: "六角形状に並んだ斑点と縞",
+        f: 0.030,
+        k: 0.055,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "epsilon",
+        description: "分裂と消滅を繰り返す斑点",
+        f: 0.018,
+        k: 0.055,
+        initial_condition: InitialCondition::RandomSquares { count: 8, seed: 1 },
+    },
+    Preset {
+        name: "zeta",
+        description: "振動する斑点",
+        f: 0.022,
+        k: 0.061,
+        initial_condition: InitialCondition::RandomSquares { count: 8, seed: 1 },
+    },
+    Preset {
+        name: "eta",
+        description: "うごめく縞",
+        f: 0.034,
+        k: 0.063,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "theta",
+        description: "迷路状の縞",
+        f: 0.030,
+        k: 0.057,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "iota",
+        description: "穴の空いた模様",
+        f: 0.046,
+        k: 0.0594,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "kappa",
+        description: "縞と穴",
+        f: 0.050,
+        k: 0.063,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "lambda",
+        description: "分裂して増える斑点",
+        f: 0.026,
+        k: 0.061,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+    Preset {
+        name: "mu",
+        description: "伸びて分岐する縞",
+        f: 0.046,
+        k: 0.065,
+        initial_condition: InitialCondition::CenterSquare,
+    },
+];
+
+/// 名前からプリセットを探す。大文字と小文字は区別しない
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::gray_scott::preset;
+///
+/// let spot = preset("Spot").unwrap();
+/// assert_eq!((spot.f, spot.k), (0.035, 0.065));
+/// assert!(preset("omega").is_none());
+/// ```
+pub fn preset(name: &str) -> Option<Preset> {
+    let name = name.to_lowercase();
+    PRESETS.iter().find(|p| p.name == name).cloned()
+}
+
+/// `params`に従ってGray-Scottモデルの時間発展を`params.substeps`ステップ計算する
+/// 1回だけ計算するための簡易版で、呼び出すたびにソルバーと書き込み先のバッファを作る。
+/// 毎フレーム呼ぶような場合は`GrayScott`を作って使い回す
+///
+/// # Arguments
+/// * `uv` - 現在の状態(u, v)。任意の大きさの長方形で良い。値の型(`f32`か`f64`)の精度で計算する
+/// * `params` - パラメーター
+pub fn gray_scott<T: Real>(uv: &mut (Matrix<T>, Matrix<T>), params: &GrayScottParams) {
+    let empty = (Array2::zeros((0, 0)), Array2::zeros((0, 0)));
+    let mut solver = StencilSolver::new(mem::replace(uv, empty));
+    step_solver(&mut solver, params, &ParameterMaps::default());
+    *uv = solver.into_state();
+}
 
-    (u, v)
+/// `gray_scott`の、場所ごとにパラメーターを変えられるバージョン
+/// `maps`の大きさが`uv`と違う場合はエラーになり、`uv`は変わらない
+///
+/// # Arguments
+/// * `uv` - 現在の状態(u, v)
+/// * `params` - マップがないパラメーターの値
+/// * `maps` - 場所ごとのパラメーターのマップ
+pub fn gray_scott_with_maps<T: Real>(
+    uv: &mut (Matrix<T>, Matrix<T>),
+    params: &GrayScottParams,
+    maps: &ParameterMaps,
+) -> Result<(), failure::Error> {
+    maps.check_shape(uv.0.dim())?;
+    let empty = (Array2::zeros((0, 0)), Array2::zeros((0, 0)));
+    let mut solver = StencilSolver::new(mem::replace(uv, empty));
+    step_solver(&mut solver, params, maps);
+    *uv = solver.into_state();
+    Ok(())
+}
+
+fn step_solver<T: Real>(solver: &mut StencilSolver<T>, params: &GrayScottParams, maps: &ParameterMaps) {
+    solver.set_boundary(params.boundary);
+    solver.set_integrator(params.integrator);
+    let (dx, dt) = (cast(params.dx), cast(params.dt));
+    if maps.is_empty() {
+        let diffusion = (cast(params.du), cast(params.dv));
+        let (f, k) = (cast(params.f), cast(params.k));
+        for _ in 0..params.substeps {
+            solver.step(diffusion, dx, dt, |u, v| reaction(f, k, u, v));
+        }
+        return;
+    }
+    for _ in 0..params.substeps {
+        solver.step_varying(dx, dt, |index, u, v| {
+            let params = maps.params_at(params, index);
+            (
+                (cast(params.du), cast(params.dv)),
+                reaction(cast(params.f), cast(params.k), u, v),
+            )
+        });
+    }
+}
+
+/// Gray-Scottモデル方程式の反応項を、状態の値の型の精度で計算する
+fn reaction<T: Real>(f: T, k: T, u: T, v: T) -> (T, T) {
+    let uvv = u * v * v;
+    (-uvv + f * (T::one() - u), uvv - (f + k) * v)
 }
 
 /// 与えられたMatrixを拡散させる。ラプラシアンを使って計算する
+/// `gray_scott`と同じく1回だけ計算するための簡易版で、呼び出すたびにバッファを確保する
 ///
 /// # Arguments
 /// * `uv` - 拡散するもとのやつ
@@ -84,23 +818,8 @@ pub fn initial_matrix() -> (Matrix<f32>, Matrix<f32>) {
 /// let mut state = (Array2::<f32>::ones((256, 256)), Array2::<f32>::ones((256, 256)));
 /// let matrix = laplacian(&mut state, 0.4, 0.6);
 /// ```
-pub fn laplacian(uv: &mut (Matrix<f32>, Matrix<f32>), f: f32, k: f32) {
-    let u: &mut Matrix<f32> = &mut uv.0;
-    let v: &mut Matrix<f32> = &mut uv.1;
-    for _ in 0..VISUALIZATION_STEP {
-        // ラプラシアンの計算
-        let laplacian_u: Matrix<f32> =
-            (roll(&u, 1, false) + roll(&u, -1, false) + roll(&u, 1, true) + roll(&u, -1, true) - &*u * 4.0) / (DX * DX);
-        let laplacian_v =
-            (roll(&v, 1, false) + roll(&v, -1, false) + roll(&v, 1, true) + roll(&v, -1, true) - &*v * 4.0) / (DX * DX);
-
-        // Gray-Scottモデル方程式
-        let dudt: Matrix<f32> = (laplacian_u * DU) - (&*u * &*v * &*v) + f * (1.0 - &*u);
-        let dvdt = (laplacian_v * DV) + (&*u * &*v * &*v) - (f + k) * &*v;
-
-        *u = (DT as f32 * dudt) + &*u;
-        *v = (DT as f32 * dvdt) + &*v;
-    }
+pub fn laplacian<T: Real>(uv: &mut (Matrix<T>, Matrix<T>), f: f32, k: f32) {
+    gray_scott(uv, &GrayScottParams::new(f, k));
 }
 
 /// lifetimeパラメーター説明用に作った関数
@@ -151,20 +870,10 @@ pub fn laplacian(uv: &mut (Matrix<f32>, Matrix<f32>), f: f32, k: f32) {
 /// [参考](https://doc.rust-lang.org/book/2018-edition/ch10-03-lifetime-syntax.html)
 /// [日本語訳](https://github.com/hazama-yuinyan/book/blob/master/second-edition/src/ch10-03-lifetime-syntax.md)
 pub fn laplacian_ref<'a, 'b>(u: &'a mut Matrix<f32>, v: &'b mut Matrix<f32>, f: f32, k: f32) -> &'a Matrix<f32> {
-    for _ in 0..VISUALIZATION_STEP {
-        // ラプラシアンの計算
-        let laplacian_u: Matrix<f32> =
-            (roll(&u, 1, false) + roll(&u, -1, false) + roll(&u, 1, true) + roll(&u, -1, true) - &*u * 4.0) / (DX * DX);
-        let laplacian_v =
-            (roll(&v, 1, false) + roll(&v, -1, false) + roll(&v, 1, true) + roll(&v, -1, true) - &*v * 4.0) / (DX * DX);
-
-        // Gray-Scottモデル方程式
-        let dudt: Matrix<f32> = (laplacian_u * DU) - (&*u * &*v * &*v) + f * (1.0 - &*u);
-        let dvdt = (laplacian_v * DV) + (&*u * &*v * &*v) - (f + k) * &*v;
-
-        *u = (DT as f32 * dudt) + &*u;
-        *v = (DT as f32 * dvdt) + &*v;
-    }
+    let mut uv = (mem::replace(u, Array2::zeros((0, 0))), mem::replace(v, Array2::zeros((0, 0))));
+    gray_scott(&mut uv, &GrayScottParams::new(f, k));
+    *u = uv.0;
+    *v = uv.1;
     u
     // use rand::distributions::IndependentSample;
     // use rand::thread_rng;
@@ -175,20 +884,3 @@ pub fn laplacian_ref<'a, 'b>(u: &'a mut Matrix<f32>, v: &'b mut Matrix<f32>, f:
     //     v
     // }
 }
-
-fn roll<A, T>(a: &Matrix<A>, shift: T, axis: bool) -> Matrix<A>
-where
-    A: Copy,
-    T: Integer + num_trait_cast::NumCast,
-{
-    let shift: i32 = num_cast(shift).unwrap();
-    let mut rotated = unsafe { Array2::uninitialized(a.dim()) };
-    if axis {
-        rotated.slice_mut(s![.., ..shift]).assign(&a.slice(s![.., -shift..]));
-        rotated.slice_mut(s![.., shift..]).assign(&a.slice(s![.., ..-shift]));
-    } else {
-        rotated.slice_mut(s![..shift, ..]).assign(&a.slice(s![-shift.., ..]));
-        rotated.slice_mut(s![shift.., ..]).assign(&a.slice(s![..-shift, ..]));
-    }
-    rotated
-}
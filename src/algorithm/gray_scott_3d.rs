@@ -0,0 +1,251 @@
+use algorithm::gray_scott::GrayScottParams;
+use algorithm::reaction_diffusion::ReactionDiffusion;
+use algorithm::stencil::{neighbor_index, standard_layout, Boundary};
+use failure;
+use ndarray::{Array, Array3, Axis};
+use ndarray_rand::RandomExt;
+use ndarray_rand::F32;
+use rand::distributions::Range;
+use rayon::prelude::*;
+use std::mem;
+use std::ops::AddAssign;
+use visualizer::matrix_visualizer::Matrix;
+
+// 初期状態に置く立方体の大きさ
+const CUBE_SIZE: usize = 20;
+
+/// 3次元の格子(層, 行, 列)で計算するGray-Scottモデル
+/// ラプラシアンは7点差分で、境界条件は`params.boundary`に従う。時間積分は常に陽的Euler法で、`params.integrator`は使わない。
+/// 層ごと・行ごとにrayonで並列に計算する
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::gray_scott::preset;
+/// use my_alife::algorithm::gray_scott_3d::{stable_params, GrayScott3d};
+/// use ndarray::Axis;
+///
+/// // 2次元のプリセットのままでは3次元の陽的Euler法で不安定になる
+/// let params = preset("mu").unwrap().params();
+/// assert!(GrayScott3d::new(params, (32, 32, 32)).check_stability().is_err());
+///
+/// let mut simulator = GrayScott3d::new(stable_params(params), (32, 32, 32));
+/// assert!(simulator.check_stability().is_ok());
+/// for _ in 0..5 {
+///     simulator.step();
+/// }
+/// assert!(simulator.state().0.iter().all(|u| u.is_finite()));
+///
+/// // 中央を通る断面はMatrixVisualizerでそのまま描画できる
+/// let (u, v) = simulator.slice(Axis(0), 16);
+/// assert_eq!(u.dim(), (32, 32));
+/// assert_eq!(v[[16, 16]], simulator.state().1[[16, 16, 16]]);
+/// ```
+pub struct GrayScott3d {
+    params: GrayScottParams,
+    state: (Array3<f32>, Array3<f32>),
+    next: (Array3<f32>, Array3<f32>),
+}
+
+impl GrayScott3d {
+    /// `initial_state`で作った初期状態から生成する
+    ///
+    /// # Arguments
+    /// * `params` - パラメーター
+    /// * `shape` - 格子の大きさ(層数, 行数, 列数)
+    pub fn new(params: GrayScottParams, shape: (usize, usize, usize)) -> GrayScott3d {
+        Self::from_state(params, initial_state(shape))
+    }
+
+    /// 初期状態を指定して生成する。uとvの大きさが違う場合はpanicする
+    pub fn from_state(params: GrayScottParams, state: (Array3<f32>, Array3<f32>)) -> GrayScott3d {
+        assert_eq!(state.0.dim(), state.1.dim(), "u and v must have the same shape");
+        let next = (Array3::zeros(state.0.dim()), Array3::zeros(state.1.dim()));
+        GrayScott3d {
+            params: params,
+            state: (standard_layout(state.0), standard_layout(state.1)),
+            next: next,
+        }
+    }
+
+    /// パラメーター
+    pub fn params(&self) -> &GrayScottParams {
+        &self.params
+    }
+
+    /// パラメーターを変更する
+    pub fn params_mut(&mut self) -> &mut GrayScottParams {
+        &mut self.params
+    }
+
+    /// 現在の状態(u, v)
+    pub fn state(&self) -> &(Array3<f32>, Array3<f32>) {
+        &self.state
+    }
+
+    /// 現在の状態(u, v)を取り出す
+    pub fn into_state(self) -> (Array3<f32>, Array3<f32>) {
+        self.state
+    }
+
+    /// `axis`に垂直で、その軸の添字が`index`の断面(u, v)を取り出す。`index`が格子の外側ならpanicする
+    pub fn slice(&self, axis: Axis, index: usize) -> (Matrix<f32>, Matrix<f32>) {
+        (slice(&self.state.0, axis, index), slice(&self.state.1, axis, index))
+    }
+
+    /// 陽的Euler法で拡散が安定に計算できるかを調べる
+    /// 7点差分では`D * dt / dx^2`が1/6以下である必要がある。反応項は考えない
+    pub fn check_stability(&self) -> Result<(), failure::Error> {
+        let ratio = diffusion_ratio(&self.params);
+        if ratio > 1.0 / 6.0 {
+            Err(failure::err_msg(format!(
+                "3D explicit Euler step is unstable: D * dt / dx^2 = {} exceeds 1/6 (see gray_scott_3d::stable_params)",
+                ratio
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// `params.substeps`ステップだけ時間を進める
+    pub fn step(&mut self) {
+        if self.state.0.is_empty() {
+            return;
+        }
+        let params = self.params;
+        let periodic = params.boundary == Boundary::Periodic;
+        // 格子の外側の値。Noneなら中心のセルと同じ値(出入りが0)とみなす
+        let outside = match params.boundary {
+            Boundary::Dirichlet(u, v) => Some((u, v)),
+            _ => None,
+        };
+        let inverse_dx2 = 1.0 / (params.dx * params.dx);
+        let (depth, rows, cols) = self.state.0.dim();
+
+        for _ in 0..params.substeps {
+            {
+                let u = self.state.0.as_slice().unwrap();
+                let v = self.state.1.as_slice().unwrap();
+                let next_u = self.next.0.as_slice_mut().unwrap();
+                let next_v = self.next.1.as_slice_mut().unwrap();
+                next_u
+                    .par_chunks_mut(cols)
+                    .zip(next_v.par_chunks_mut(cols))
+                    .enumerate()
+                    .for_each(|(line, (next_u_row, next_v_row))| {
+                        let (z, y) = (line / rows, line % rows);
+                        // 前後の層と上下の行の先頭の添字。格子の外側ならNone
+                        let lines = [
+                            neighbor_index(z, depth, -1, periodic).map(|z| (z * rows + y) * cols),
+                            neighbor_index(z, depth, 1, periodic).map(|z| (z * rows + y) * cols),
+                            neighbor_index(y, rows, -1, periodic).map(|y| (z * rows + y) * cols),
+                            neighbor_index(y, rows, 1, periodic).map(|y| (z * rows + y) * cols),
+                        ];
+                        let here = line * cols;
+                        for x in 0..cols {
+                            let (uc, vc) = (u[here + x], v[here + x]);
+                            let neighbors = [
+                                lines[0].map(|l| l + x),
+                                lines[1].map(|l| l + x),
+                                lines[2].map(|l| l + x),
+                                lines[3].map(|l| l + x),
+                                neighbor_index(x, cols, -1, periodic).map(|x| here + x),
+                                neighbor_index(x, cols, 1, periodic).map(|x| here + x),
+                            ];
+                            let (mut sum_u, mut sum_v) = (0.0, 0.0);
+                            for neighbor in &neighbors {
+                                let (nu, nv) = match *neighbor {
+                                    Some(n) => (u[n], v[n]),
+                                    None => outside.unwrap_or((uc, vc)),
+                                };
+                                sum_u += nu;
+                                sum_v += nv;
+                            }
+                            let laplacian_u = (sum_u - 6.0 * uc) * inverse_dx2;
+                            let laplacian_v = (sum_v - 6.0 * vc) * inverse_dx2;
+                            let (reaction_u, reaction_v) = params.reaction(uc, vc);
+                            next_u_row[x] = uc + params.dt * (params.du * laplacian_u + reaction_u);
+                            next_v_row[x] = vc + params.dt * (params.dv * laplacian_v + reaction_v);
+                        }
+                    });
+            }
+            mem::swap(&mut self.state, &mut self.next);
+        }
+    }
+}
+
+/// 3次元の陽的Euler法で安定に計算できるように、1回の更新で進む時間を変えずに`dt`を小さくしたパラメーター
+/// `dt`を半分にして`substeps`を倍にすることを、`D * dt / dx^2`が1/6以下になるまで繰り返す
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::gray_scott::GrayScottParams;
+/// use my_alife::algorithm::gray_scott_3d::stable_params;
+///
+/// let params = stable_params(GrayScottParams::new(0.04, 0.06));
+/// assert_eq!((params.dt, params.substeps), (0.5, 16));
+/// ```
+pub fn stable_params(params: GrayScottParams) -> GrayScottParams {
+    let mut params = params;
+    while diffusion_ratio(&params) > 1.0 / 6.0 && params.dt > 0.0 {
+        params.dt /= 2.0;
+        params.substeps *= 2;
+    }
+    params
+}
+
+/// 任意の大きさの3次元の初期状態を作る
+/// 中央に一辺20の立方体を置き、対称性を崩すため少しノイズを入れる
+///
+/// # Arguments
+/// * `shape` - 格子の大きさ(層数, 行数, 列数)
+pub fn initial_state(shape: (usize, usize, usize)) -> (Array3<f32>, Array3<f32>) {
+    let mut u = Array3::<f32>::ones(shape);
+    let mut v = Array3::<f32>::zeros(shape);
+
+    let (depth, rows, cols) = shape;
+    let range = |len: usize| (len / 2).saturating_sub(CUBE_SIZE / 2)..(len / 2 + CUBE_SIZE / 2).min(len);
+    let (z, y, x) = (range(depth), range(rows), range(cols));
+    u.slice_mut(s![z.clone(), y.clone(), x.clone()]).fill(0.5);
+    v.slice_mut(s![z, y, x]).fill(0.25);
+
+    // 対称性を崩すため少しノイズを入れる
+    let u_rand = Array::random(shape, F32(Range::new(0., 1.))) * 0.1;
+    let v_rand = Array::random(shape, F32(Range::new(0., 1.))) * 0.1;
+    u.add_assign(&u_rand);
+    v.add_assign(&v_rand);
+
+    (u, v)
+}
+
+/// `axis`に垂直で、その軸の添字が`index`の断面を取り出す。`index`が格子の外側ならpanicする
+/// `Axis(0)`なら(行, 列)、`Axis(1)`なら(層, 列)、`Axis(2)`なら(層, 行)の2次元配列になる
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::gray_scott_3d::slice;
+/// use ndarray::{Array3, Axis};
+///
+/// let volume = Array3::from_shape_fn((2, 3, 4), |(z, y, x)| (z * 100 + y * 10 + x) as f32);
+/// assert_eq!(slice(&volume, Axis(0), 1)[[2, 3]], 123.0);
+/// assert_eq!(slice(&volume, Axis(1), 2).dim(), (2, 4));
+/// assert_eq!(slice(&volume, Axis(2), 3).row(1).to_vec(), vec![103.0, 113.0, 123.0]);
+/// ```
+pub fn slice(volume: &Array3<f32>, axis: Axis, index: usize) -> Matrix<f32> {
+    let view = match axis.index() {
+        0 => volume.slice(s![index, .., ..]),
+        1 => volume.slice(s![.., index, ..]),
+        _ => volume.slice(s![.., .., index]),
+    };
+    standard_layout(view.to_owned())
+}
+
+/// 拡散係数の大きい方について`D * dt / dx^2`
+fn diffusion_ratio(params: &GrayScottParams) -> f32 {
+    params.du.max(params.dv) * params.dt / (params.dx * params.dx)
+}
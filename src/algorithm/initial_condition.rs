@@ -0,0 +1,368 @@
+use ndarray::{Array, Array2, Zip};
+use ndarray_rand::RandomExt;
+use ndarray_rand::F32;
+use rand::distributions::Range;
+use rand::{Rng, SeedableRng, StdRng};
+use visualizer::matrix_visualizer::Matrix;
+
+/// 初期状態で種を置く場所を表す模様
+/// `render`で各セルが0から1の値を持つMatrixになり、1のところに種を置く。
+/// `union`や`intersection`で組み合わせられる
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::initial_condition::Pattern;
+///
+/// // 市松模様のうち、円の中だけを使う
+/// let pattern = Pattern::Checkerboard(4).intersection(Pattern::Spots {
+///     count: 1,
+///     radius: 100.0,
+///     seed: 1,
+/// });
+/// let mask = pattern.render((16, 16));
+/// assert_eq!(mask.dim(), (16, 16));
+/// assert!(mask.iter().all(|&e| e == 0.0 || e == 1.0));
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// 指定したセル(行, 列)だけが1
+    Pixel(usize, usize),
+    /// 中心(行, 列)に一辺`size`の正方形を置く。はみ出した部分は切り捨てる
+    Square {
+        /// 中心(行, 列)
+        center: (usize, usize),
+        /// 一辺の長さ
+        size: usize,
+    },
+    /// ランダムな位置に半径`radius`の円を`count`個置く
+    Spots {
+        /// 円の数
+        count: usize,
+        /// 円の半径(セルの数)
+        radius: f32,
+        /// 乱数のseed
+        seed: usize,
+    },
+    /// 一辺の長さを指定した市松模様。左上のマスが1
+    Checkerboard(usize),
+    /// Perlinノイズを周波数を倍にしながら重ねたフラクタルノイズ。値は0から1の連続値になる
+    FractalNoise {
+        /// 一番大きなうねりの大きさ(セルの数)
+        scale: f32,
+        /// 重ねる数
+        octaves: usize,
+        /// 乱数のseed
+        seed: usize,
+    },
+    /// 文字列を5x7のビットマップフォントで描き、格子の中央に置く。
+    /// 英数字と一部の記号(` `, `!`, `-`, `.`, `?`)が使え、小文字は大文字として描く。改行で複数行にできる
+    Text {
+        /// 描く文字列
+        text: String,
+        /// フォントの1画素を何セルにするか
+        scale: usize,
+    },
+    /// 2つの模様の大きい方(和集合)
+    Union(Box<Pattern>, Box<Pattern>),
+    /// 2つの模様の小さい方(共通部分)
+    Intersection(Box<Pattern>, Box<Pattern>),
+    /// 1から引いたもの(反転)
+    Invert(Box<Pattern>),
+    /// しきい値以上を1、未満を0にしたもの
+    Threshold(Box<Pattern>, f32),
+}
+
+impl Pattern {
+    /// `self`と`other`の和集合
+    pub fn union(self, other: Pattern) -> Pattern {
+        Pattern::Union(Box::new(self), Box::new(other))
+    }
+
+    /// `self`と`other`の共通部分
+    pub fn intersection(self, other: Pattern) -> Pattern {
+        Pattern::Intersection(Box::new(self), Box::new(other))
+    }
+
+    /// 反転した模様
+    pub fn invert(self) -> Pattern {
+        Pattern::Invert(Box::new(self))
+    }
+
+    /// `level`以上を1、未満を0にした模様
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::algorithm::initial_condition::Pattern;
+    ///
+    /// let noise = Pattern::FractalNoise { scale: 16.0, octaves: 4, seed: 7 };
+    /// let values = noise.clone().render((32, 32));
+    /// assert!(values.iter().all(|&e| 0.0 <= e && e <= 1.0));
+    /// // 同じseedなら同じ模様になる
+    /// assert_eq!(values, noise.clone().render((32, 32)));
+    ///
+    /// let blobs = noise.threshold(0.5).render((32, 32));
+    /// assert!(blobs.iter().all(|&e| e == 0.0 || e == 1.0));
+    /// ```
+    pub fn threshold(self, level: f32) -> Pattern {
+        Pattern::Threshold(Box::new(self), level)
+    }
+
+    /// 大きさ`shape`(行数, 列数)の格子に模様を描く
+    pub fn render(&self, shape: (usize, usize)) -> Matrix<f32> {
+        let (rows, cols) = shape;
+        match *self {
+            Pattern::Pixel(row, col) => {
+                let mut m = Array2::zeros(shape);
+                if row < rows && col < cols {
+                    m[[row, col]] = 1.0;
+                }
+                m
+            }
+            Pattern::Square { center, size } => {
+                let (top, left) = (center.0.saturating_sub(size / 2), center.1.saturating_sub(size / 2));
+                Array2::from_shape_fn(shape, |(i, j)| {
+                    (i >= top && i < top + size && j >= left && j < left + size) as u8 as f32
+                })
+            }
+            Pattern::Spots { count, radius, seed } => {
+                let mut rng = StdRng::from_seed(&[seed][..]);
+                let centers = (0..count)
+                    .map(|_| (rng.gen_range(0, rows.max(1)) as f32, rng.gen_range(0, cols.max(1)) as f32))
+                    .collect::<Vec<_>>();
+                Array2::from_shape_fn(shape, |(i, j)| {
+                    let inside = centers.iter().any(|&(y, x)| {
+                        let (dy, dx) = (i as f32 - y, j as f32 - x);
+                        dy * dy + dx * dx <= radius * radius
+                    });
+                    inside as u8 as f32
+                })
+            }
+            Pattern::Checkerboard(size) => {
+                let size = size.max(1);
+                Array2::from_shape_fn(shape, |(i, j)| ((i / size + j / size) % 2 == 0) as u8 as f32)
+            }
+            Pattern::FractalNoise { scale, octaves, seed } => {
+                let perlin = Perlin::new(seed);
+                let octaves = octaves.max(1);
+                let total_amplitude = (0..octaves).map(|o| 0.5f32.powi(o as i32)).sum::<f32>();
+                Array2::from_shape_fn(shape, |(i, j)| {
+                    let mut sum = 0.0;
+                    for o in 0..octaves {
+                        let frequency = 2.0f32.powi(o as i32) / scale;
+                        sum += 0.5f32.powi(o as i32) * perlin.noise(j as f32 * frequency, i as f32 * frequency);
+                    }
+                    (0.5 + 0.5 * sum / total_amplitude).max(0.0).min(1.0)
+                })
+            }
+            Pattern::Text { ref text, scale } => render_text(text, scale.max(1), shape),
+            Pattern::Union(ref a, ref b) => combine(a, b, shape, f32::max),
+            Pattern::Intersection(ref a, ref b) => combine(a, b, shape, f32::min),
+            Pattern::Invert(ref a) => a.render(shape).mapv(|e| 1.0 - e),
+            Pattern::Threshold(ref a, level) => a.render(shape).mapv(|e| (e >= level) as u8 as f32),
+        }
+    }
+}
+
+fn combine<F: Fn(f32, f32) -> f32>(a: &Pattern, b: &Pattern, shape: (usize, usize), f: F) -> Matrix<f32> {
+    let mut m = a.render(shape);
+    Zip::from(&mut m).and(&b.render(shape)).apply(|x, &y| *x = f(*x, y));
+    m
+}
+
+/// 模様から反応拡散系の初期状態(u, v)を作る
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::initial_condition::{Pattern, StateGenerator};
+///
+/// let generator = StateGenerator::gray_scott(Pattern::Text { text: "ALife".to_string(), scale: 3 }, 42);
+/// let (u, v) = generator.state((64, 128));
+/// assert_eq!(u.dim(), (64, 128));
+/// // 文字のところに種(v > 0.25)が置かれる
+/// assert!(v.iter().any(|&e| e >= 0.25));
+/// assert!(v.iter().all(|&e| e < 0.35));
+/// // 同じseedなら同じ状態になる
+/// assert_eq!(generator.state((64, 128)).0, u);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateGenerator {
+    /// 種を置く模様
+    pub pattern: Pattern,
+    /// 模様が0のところの(u, v)
+    pub background: (f32, f32),
+    /// 模様が1のところの(u, v)。その間は線形に補間する
+    pub foreground: (f32, f32),
+    /// 対称性を崩すためのノイズの大きさ。[0, noise)の一様乱数をuとvに足す
+    pub noise: f32,
+    /// ノイズの乱数のseed
+    pub seed: usize,
+}
+
+impl StateGenerator {
+    /// Gray-Scottモデル向けの値(`gray_scott::initial_state`と同じ)のStateGeneratorインスタンスを生成する
+    /// 背景は(1, 0)、種は(0.5, 0.25)で、0.1のノイズを入れる
+    pub fn gray_scott(pattern: Pattern, seed: usize) -> StateGenerator {
+        StateGenerator {
+            pattern: pattern,
+            background: (1.0, 0.0),
+            foreground: (0.5, 0.25),
+            noise: 0.1,
+            seed: seed,
+        }
+    }
+
+    /// 大きさ`shape`(行数, 列数)の初期状態を作る
+    pub fn state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        let mask = self.pattern.render(shape);
+        let (background, foreground) = (self.background, self.foreground);
+        let mut u = mask.mapv(|p| background.0 + (foreground.0 - background.0) * p);
+        let mut v = mask.mapv(|p| background.1 + (foreground.1 - background.1) * p);
+        if self.noise > 0.0 {
+            let mut rng = StdRng::from_seed(&[self.seed][..]);
+            let range = F32(Range::new(0.0, self.noise as f64));
+            u += &Array::random_using(shape, range, &mut rng);
+            v += &Array::random_using(shape, range, &mut rng);
+        }
+        (u, v)
+    }
+}
+
+// 改良版Perlinノイズ(Perlin 2002)の2次元版
+struct Perlin {
+    // 0から255を並べ替えたものを2回繰り返した表
+    permutation: Vec<usize>,
+}
+
+impl Perlin {
+    fn new(seed: usize) -> Perlin {
+        let mut rng = StdRng::from_seed(&[seed][..]);
+        let mut permutation = (0..256).collect::<Vec<_>>();
+        rng.shuffle(&mut permutation);
+        let repeated = permutation.iter().chain(permutation.iter()).cloned().collect();
+        Perlin { permutation: repeated }
+    }
+
+    /// (x, y)でのノイズ。値はおよそ-1から1
+    fn noise(&self, x: f32, y: f32) -> f32 {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (xi, yi) = (x0 as i64 as usize & 255, y0 as i64 as usize & 255);
+        let p = &self.permutation;
+        let corner = |dx: usize, dy: usize| p[p[xi + dx] + yi + dy];
+        let (u, v) = (fade(fx), fade(fy));
+        lerp(
+            v,
+            lerp(u, gradient(corner(0, 0), fx, fy), gradient(corner(1, 0), fx - 1.0, fy)),
+            lerp(u, gradient(corner(0, 1), fx, fy - 1.0), gradient(corner(1, 1), fx - 1.0, fy - 1.0)),
+        )
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+// 格子点の勾配(8方向のどれか)と、格子点からの位置の内積
+fn gradient(hash: usize, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+// フォントの1文字の幅と高さ、文字と行の送り
+const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+const GLYPH_ADVANCE: usize = 6;
+const LINE_ADVANCE: usize = 8;
+
+fn render_text(text: &str, scale: usize, shape: (usize, usize)) -> Matrix<f32> {
+    let (rows, cols) = shape;
+    let lines = text.lines().collect::<Vec<_>>();
+    let text_width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) * GLYPH_ADVANCE;
+    let text_height = lines.len() * LINE_ADVANCE;
+    // 最後の文字の後ろと最後の行の下の余白は除いて中央に置く
+    let width = text_width.saturating_sub(GLYPH_ADVANCE - GLYPH_WIDTH) * scale;
+    let height = text_height.saturating_sub(LINE_ADVANCE - GLYPH_HEIGHT) * scale;
+    let top = rows as isize / 2 - height as isize / 2;
+    let left = cols as isize / 2 - width as isize / 2;
+
+    let mut m = Array2::zeros(shape);
+    for (line_index, line) in lines.iter().enumerate() {
+        for (char_index, c) in line.chars().enumerate() {
+            let bitmap = glyph(c);
+            for (y, bits) in bitmap.iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - x)) == 0 {
+                        continue;
+                    }
+                    let row = top + ((line_index * LINE_ADVANCE + y) * scale) as isize;
+                    let col = left + ((char_index * GLYPH_ADVANCE + x) * scale) as isize;
+                    for i in row.max(0)..(row + scale as isize).min(rows as isize) {
+                        for j in col.max(0)..(col + scale as isize).min(cols as isize) {
+                            m[[i as usize, j as usize]] = 1.0;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    m
+}
+
+/// 5x7のビットマップフォント。1行が1要素で、下位5ビットが左から右の画素
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        'A' => [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        '?' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+        _ => [0x00; GLYPH_HEIGHT],
+    }
+}
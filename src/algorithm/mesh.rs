@@ -0,0 +1,391 @@
+use algorithm::reaction_diffusion::{Discretization, ReactionDiffusion};
+use failure;
+use ndarray::{Array, Array1};
+use ndarray_rand::RandomExt;
+use ndarray_rand::F32;
+use rand::distributions::Range;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::mem;
+
+/// 頂点の座標と、頂点の添字3つで表した三角形からなるメッシュ
+/// 三角形の頂点は表から見て反時計回りに並べる
+#[derive(Clone, Debug, PartialEq)]
+pub struct TriangleMesh {
+    /// 頂点の座標(x, y, z)
+    pub vertices: Vec<[f32; 3]>,
+    /// 三角形の頂点の添字
+    pub triangles: Vec<[usize; 3]>,
+}
+
+impl TriangleMesh {
+    /// 三角形の頂点の添字がすべて`vertices`の範囲内かを調べる
+    pub fn validate(&self) -> Result<(), failure::Error> {
+        for (n, triangle) in self.triangles.iter().enumerate() {
+            if let Some(index) = triangle.iter().find(|&&i| i >= self.vertices.len()) {
+                return Err(failure::err_msg(format!(
+                    "triangle {} refers to vertex {}, but the mesh has only {} vertices",
+                    n,
+                    index,
+                    self.vertices.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 辺の長さの平均。同じ辺は三角形ごとに数える
+    pub fn mean_edge_length(&self) -> f32 {
+        let sum: f32 = self
+            .triangles
+            .iter()
+            .flat_map(|t| (0..3).map(move |k| (t[k], t[(k + 1) % 3])))
+            .map(|(a, b)| norm(sub(self.vertices[b], self.vertices[a])))
+            .sum();
+        sum / (self.triangles.len() * 3).max(1) as f32
+    }
+
+    /// すべての頂点の座標を`factor`倍する
+    pub fn scale(&mut self, factor: f32) {
+        for vertex in &mut self.vertices {
+            for x in vertex.iter_mut() {
+                *x *= factor;
+            }
+        }
+    }
+}
+
+/// 正二十面体の各三角形を`subdivisions`回4分割し、頂点を半径1の球面に移したメッシュ(icosphere)
+/// 頂点の数は`10 * 4^subdivisions + 2`、三角形の数は`20 * 4^subdivisions`
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::mesh::icosphere;
+///
+/// let sphere = icosphere(3);
+/// assert_eq!((sphere.vertices.len(), sphere.triangles.len()), (642, 1280));
+/// assert!(sphere.vertices.iter().all(|v| ((v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt() - 1.0).abs() < 1e-5));
+/// ```
+pub fn icosphere(subdivisions: usize) -> TriangleMesh {
+    let t = (1.0 + 5f32.sqrt()) / 2.0;
+    let mut vertices: Vec<[f32; 3]> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+        .iter()
+        .map(|&v| normalize(v))
+        .collect();
+    let mut triangles = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // 辺の中点の頂点は隣り合う三角形で共有する
+        let mut midpoints = HashMap::new();
+        let mut midpoint = |a: usize, b: usize, vertices: &mut Vec<[f32; 3]>| {
+            let key = (a.min(b), a.max(b));
+            *midpoints.entry(key).or_insert_with(|| {
+                let (p, q) = (vertices[a], vertices[b]);
+                vertices.push(normalize([p[0] + q[0], p[1] + q[1], p[2] + q[2]]));
+                vertices.len() - 1
+            })
+        };
+        let mut next = Vec::with_capacity(triangles.len() * 4);
+        for &[a, b, c] in &triangles {
+            let ab = midpoint(a, b, &mut vertices);
+            let bc = midpoint(b, c, &mut vertices);
+            let ca = midpoint(c, a, &mut vertices);
+            next.push([a, ab, ca]);
+            next.push([b, bc, ab]);
+            next.push([c, ca, bc]);
+            next.push([ab, bc, ca]);
+        }
+        triangles = next;
+    }
+    TriangleMesh {
+        vertices: vertices,
+        triangles: triangles,
+    }
+}
+
+/// 三角形メッシュの上で反応拡散系を解くソルバー
+/// 拡散はコタンジェント重みのラプラシアン(Laplace-Beltrami作用素の離散化)で、各頂点の面積は隣接する三角形の面積の1/3ずつとする。
+/// 時間積分は陽的Euler法で、頂点ごとにrayonで並列に計算する。境界のあるメッシュでは境界を通した出入りは0になる
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::mesh::{icosphere, MeshSolver};
+/// use ndarray::Array1;
+///
+/// // 拡散だけなら総量(面積で重みをつけた和)が保存され、一様な状態に近づく
+/// let sphere = icosphere(2);
+/// let u = Array1::from_shape_fn(sphere.vertices.len(), |i| sphere.vertices[i][2].max(0.0));
+/// let v = Array1::zeros(sphere.vertices.len());
+/// let mut solver = MeshSolver::new(&sphere, (u, v)).unwrap();
+/// let dt = solver.max_stable_dt(1.0) * 0.9;
+/// let total = solver.total().0;
+/// for _ in 0..500 {
+///     solver.step((1.0, 1.0), dt, |_, _| (0.0, 0.0));
+/// }
+/// assert!((solver.total().0 - total).abs() < 1e-3);
+/// let u = &solver.state().0;
+/// assert!(u.iter().all(|e| (e - u[0]).abs() < 1e-3));
+/// ```
+pub struct MeshSolver {
+    // 頂点iの隣の頂点とその重みは`neighbors[offsets[i]..offsets[i + 1]]`
+    offsets: Vec<usize>,
+    neighbors: Vec<(usize, f32)>,
+    // 頂点ごとの面積
+    areas: Vec<f32>,
+    // 三角形ごとのラプラシアンの最大固有値を、その三角形から各頂点に分けた面積で割ったものの最大値
+    element_bound: f32,
+    state: (Array1<f32>, Array1<f32>),
+    next: (Array1<f32>, Array1<f32>),
+}
+
+impl MeshSolver {
+    /// `mesh`の頂点ごとの値(u, v)を初期状態にしてソルバーを作る
+    /// 三角形の頂点の添字が範囲外の場合や、値の数が頂点の数と違う場合はエラーになる
+    pub fn new(mesh: &TriangleMesh, state: (Array1<f32>, Array1<f32>)) -> Result<MeshSolver, failure::Error> {
+        mesh.validate()?;
+        let n = mesh.vertices.len();
+        if state.0.len() != n || state.1.len() != n {
+            return Err(failure::err_msg(format!(
+                "state length mismatch: expected {}, found ({}, {})",
+                n,
+                state.0.len(),
+                state.1.len()
+            )));
+        }
+
+        let mut weights = HashMap::new();
+        let mut areas = vec![0.0; n];
+        let mut element_bound = 0.0f32;
+        for triangle in &mesh.triangles {
+            let p = [
+                mesh.vertices[triangle[0]],
+                mesh.vertices[triangle[1]],
+                mesh.vertices[triangle[2]],
+            ];
+            let area = norm(cross(sub(p[1], p[0]), sub(p[2], p[0]))) / 2.0;
+            // 頂点kの向かい側の辺の重み
+            let mut edges = [0.0f32; 3];
+            for k in 0..3 {
+                areas[triangle[k]] += area / 3.0;
+                // 頂点kの角のコタンジェントを、向かい側の辺の重みに足す
+                let (a, b) = (triangle[(k + 1) % 3], triangle[(k + 2) % 3]);
+                let (e1, e2) = (sub(p[(k + 1) % 3], p[k]), sub(p[(k + 2) % 3], p[k]));
+                let sine = norm(cross(e1, e2));
+                if sine > 0.0 {
+                    edges[k] = 0.5 * dot(e1, e2) / sine;
+                    *weights.entry((a.min(b), a.max(b))).or_insert(0.0) += edges[k];
+                }
+            }
+            // この三角形だけのラプラシアン(3x3)の固有値は0と、λ² - trace λ + minors = 0の2つの解
+            if area > 0.0 {
+                let trace = 2.0 * (edges[0] + edges[1] + edges[2]);
+                let minors = 3.0 * (edges[0] * edges[1] + edges[1] * edges[2] + edges[2] * edges[0]);
+                let largest = (trace + (trace * trace - 4.0 * minors).max(0.0).sqrt()) / 2.0;
+                element_bound = element_bound.max(largest / (area / 3.0));
+            }
+        }
+
+        let mut lists = vec![Vec::new(); n];
+        for (&(a, b), &w) in &weights {
+            lists[a].push((b, w));
+            lists[b].push((a, w));
+        }
+        let mut offsets = Vec::with_capacity(n + 1);
+        let mut neighbors = Vec::with_capacity(weights.len() * 2);
+        offsets.push(0);
+        for mut list in lists {
+            list.sort_by_key(|&(j, _)| j);
+            neighbors.extend(list);
+            offsets.push(neighbors.len());
+        }
+        Ok(MeshSolver {
+            offsets: offsets,
+            neighbors: neighbors,
+            areas: areas,
+            element_bound: element_bound,
+            next: (Array1::zeros(n), Array1::zeros(n)),
+            state: state,
+        })
+    }
+
+    /// 現在の状態(u, v)
+    pub fn state(&self) -> &(Array1<f32>, Array1<f32>) {
+        &self.state
+    }
+
+    /// 現在の状態(u, v)を取り出す
+    pub fn into_state(self) -> (Array1<f32>, Array1<f32>) {
+        self.state
+    }
+
+    /// 頂点ごとの面積
+    pub fn areas(&self) -> &[f32] {
+        &self.areas
+    }
+
+    /// u, vそれぞれの、頂点の面積で重みをつけた和(曲面上の積分)
+    pub fn total(&self) -> (f32, f32) {
+        let total = |x: &Array1<f32>| x.iter().zip(&self.areas).map(|(x, a)| x * a).sum();
+        (total(&self.state.0), total(&self.state.1))
+    }
+
+    /// 拡散係数`diffusion`の拡散を陽的Euler法で安定に解ける時間の刻み幅の上限
+    /// ラプラシアンの最大固有値の上界λから2 / (diffusion * λ)を求める。λにはGershgorinの定理による上界と、
+    /// 三角形ごとのラプラシアンの最大固有値を頂点の面積で割ったものの最大値(x^T L x = Σ x_T^T L_T x_T から
+    /// 上界になる)の小さい方を使うので、どちらも見積もりではなく必ず上界になる。頂点がなければ無限大になる
+    pub fn max_stable_dt(&self, diffusion: f32) -> f32 {
+        let gershgorin = (0..self.areas.len())
+            .filter(|&i| self.areas[i] > 0.0)
+            .map(|i| 2.0 * self.row(i).iter().map(|&(_, w)| w.abs()).sum::<f32>() / self.areas[i])
+            .fold(0.0, f32::max);
+        2.0 / (diffusion * gershgorin.min(self.element_bound))
+    }
+
+    // 頂点`i`の隣の頂点とその重み
+    fn row(&self, i: usize) -> &[(usize, f32)] {
+        &self.neighbors[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    /// 刻み幅`dt`で1ステップ進める
+    ///
+    /// # Arguments
+    /// * `diffusion` - uとvの拡散係数
+    /// * `dt` - 時間の刻み幅
+    /// * `reaction` - 頂点の値(u, v)での反応項(du/dt, dv/dt)
+    pub fn step<R>(&mut self, diffusion: (f32, f32), dt: f32, reaction: R)
+    where
+        R: Fn(f32, f32) -> (f32, f32) + Sync,
+    {
+        {
+            let (offsets, neighbors, areas) = (&self.offsets, &self.neighbors, &self.areas);
+            let u = self.state.0.as_slice().unwrap();
+            let v = self.state.1.as_slice().unwrap();
+            let next_u = self.next.0.as_slice_mut().unwrap();
+            let next_v = self.next.1.as_slice_mut().unwrap();
+            next_u
+                .par_iter_mut()
+                .zip(next_v.par_iter_mut())
+                .enumerate()
+                .for_each(|(i, (next_u, next_v))| {
+                    let (uc, vc) = (u[i], v[i]);
+                    let (mut laplacian_u, mut laplacian_v) = (0.0, 0.0);
+                    for &(j, w) in &neighbors[offsets[i]..offsets[i + 1]] {
+                        laplacian_u += w * (u[j] - uc);
+                        laplacian_v += w * (v[j] - vc);
+                    }
+                    // 三角形に含まれない頂点は面積が0なので、拡散しない
+                    if areas[i] > 0.0 {
+                        laplacian_u /= areas[i];
+                        laplacian_v /= areas[i];
+                    }
+                    let (reaction_u, reaction_v) = reaction(uc, vc);
+                    *next_u = uc + dt * (diffusion.0 * laplacian_u + reaction_u);
+                    *next_v = vc + dt * (diffusion.1 * laplacian_v + reaction_v);
+                });
+        }
+        mem::swap(&mut self.state, &mut self.next);
+    }
+}
+
+/// `model`に従って`solver`を`discretization.substeps`ステップだけ進める
+/// 長さの単位はメッシュの座標なので`discretization.dx`は使わない。平面の格子と同じパターンを得るには、
+/// メッシュの辺の長さが`dx`くらいになるように`TriangleMesh::scale`しておく。
+/// `discretization.boundary`と`discretization.integrator`も使わない
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::gray_scott::preset;
+/// use my_alife::algorithm::mesh::{icosphere, initial_state, step_model, MeshSolver};
+///
+/// let params = preset("spot").unwrap().params();
+/// let mut sphere = icosphere(3);
+/// let scale = params.dx / sphere.mean_edge_length();
+/// sphere.scale(scale);
+///
+/// let state = initial_state(&sphere, sphere.vertices[0], 5.0 * params.dx);
+/// let mut solver = MeshSolver::new(&sphere, state).unwrap();
+/// assert!(solver.max_stable_dt(params.du) > params.dt);
+/// for _ in 0..10 {
+///     step_model(&mut solver, &params, &params.discretization());
+/// }
+/// assert!(solver.state().0.iter().all(|u| u.is_finite()));
+/// ```
+pub fn step_model<M: ReactionDiffusion>(solver: &mut MeshSolver, model: &M, discretization: &Discretization) {
+    for _ in 0..discretization.substeps {
+        solver.step(model.diffusion(), discretization.dt, |u, v| model.reaction(u, v));
+    }
+}
+
+/// Gray-Scottモデル向けのメッシュの初期状態を作る
+/// 点`center`から距離`radius`以内の頂点を(0.5, 0.25)、それ以外を(1, 0)にして、対称性を崩すため少しノイズを入れる
+pub fn initial_state(mesh: &TriangleMesh, center: [f32; 3], radius: f32) -> (Array1<f32>, Array1<f32>) {
+    let n = mesh.vertices.len();
+    let inside = |i: usize| norm(sub(mesh.vertices[i], center)) <= radius;
+    let mut u = Array1::from_shape_fn(n, |i| if inside(i) { 0.5 } else { 1.0 });
+    let mut v = Array1::from_shape_fn(n, |i| if inside(i) { 0.25 } else { 0.0 });
+    u += &(Array::random(n, F32(Range::new(0., 1.))) * 0.1);
+    v += &(Array::random(n, F32(Range::new(0., 1.))) * 0.1);
+    (u, v)
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn norm(a: [f32; 3]) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let n = norm(a);
+    [a[0] / n, a[1] / n, a[2] / n]
+}
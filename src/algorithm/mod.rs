@@ -4,3 +4,37 @@ pub mod cellular_automata;
 pub mod game_of_life;
 /// GrayScottモデルのアルゴリズム
 pub mod gray_scott;
+/// 3次元の格子で計算するGrayScottモデル
+pub mod gray_scott_3d;
+/// セルラーオートマトンやLifeGameの非同期・確率的な更新方式
+pub mod update_scheme;
+/// セルラーオートマトンの状態遷移グラフとアトラクタの解析
+pub mod basins_of_attraction;
+/// de Bruijnグラフを用いたセルラーオートマトンのルールの解析
+pub mod de_bruijn;
+/// セルラーオートマトンの時空図から領域を取り除き粒子を追跡するフィルタ
+pub mod domain_filter;
+/// GrayScottモデルの(F, K)の相図
+pub mod phase_diagram;
+/// 反応拡散系を5点差分で解くソルバー
+pub mod stencil;
+/// 反応拡散系に加える流れ(移流)
+pub mod flow;
+/// 向きによって強さの違う拡散(拡散テンソル)
+pub mod diffusion_tensor;
+/// 反応拡散系の共通のトレイトとGray-Scott以外のモデル
+pub mod reaction_diffusion;
+/// 化学種と反応式で記述した、任意の数の化学種の反応拡散系
+pub mod reaction_network;
+/// 拡散をフーリエ空間で解く反応拡散系のソルバー
+pub mod spectral;
+/// 拡散を交互方向陰解法(ADI)で解く反応拡散系のソルバー
+pub mod adi;
+/// 反応拡散系のパラメーターを場所ごとに変えるためのマップ
+pub mod parameter_map;
+/// 反応拡散系の初期状態を作るための、組み合わせられる模様
+pub mod initial_condition;
+/// 反応拡散系の模様の特徴量(波長、斑点の数と大きさ、縞の向き、全変動、定常状態の判定)
+pub mod pattern_analysis;
+/// 三角形メッシュ(曲面)の上の反応拡散系
+pub mod mesh;
@@ -0,0 +1,89 @@
+use format::image::GrayImage;
+use ndarray::{Array2, Axis};
+use visualizer::matrix_visualizer::Matrix;
+
+/// `axis`の向きに`range.0`から`range.1`まで線形に変化するマップを作る
+///
+/// # Arguments
+/// * `shape` - 格子の大きさ(行数, 列数)
+/// * `axis` - 値が変化する向き。`Axis(0)`なら上から下、`Axis(1)`なら左から右に変化する
+/// * `range` - 端での値(始め, 終わり)
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::parameter_map::linear;
+/// use ndarray::Axis;
+///
+/// let map = linear((2, 5), Axis(1), (0.02, 0.06));
+/// assert_eq!(map[[1, 0]], 0.02);
+/// assert!((map[[0, 2]] - 0.04).abs() < 1e-6);
+/// assert_eq!(map[[1, 4]], 0.06);
+/// ```
+pub fn linear(shape: (usize, usize), axis: Axis, range: (f32, f32)) -> Matrix<f32> {
+    let len = if axis.index() == 0 { shape.0 } else { shape.1 };
+    let step = if len > 1 { (range.1 - range.0) / (len - 1) as f32 } else { 0.0 };
+    Array2::from_shape_fn(shape, |(i, j)| {
+        let position = if axis.index() == 0 { i } else { j };
+        range.0 + step * position as f32
+    })
+}
+
+/// 中心からの距離に応じて変化するマップを作る
+/// 中心で`range.0`、距離`radius`で`range.1`になり、その間は線形に変化する。`radius`より外側は`range.1`
+///
+/// # Arguments
+/// * `shape` - 格子の大きさ(行数, 列数)
+/// * `center` - 中心(行, 列)
+/// * `radius` - 半径(セルの数)
+/// * `range` - 値(中心, 外側)
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::parameter_map::radial;
+///
+/// let map = radial((9, 9), (4.0, 4.0), 4.0, (0.06, 0.02));
+/// assert_eq!(map[[4, 4]], 0.06);
+/// assert!((map[[4, 6]] - 0.04).abs() < 1e-6);
+/// assert_eq!(map[[0, 0]], 0.02);
+/// ```
+pub fn radial(shape: (usize, usize), center: (f32, f32), radius: f32, range: (f32, f32)) -> Matrix<f32> {
+    Array2::from_shape_fn(shape, |(i, j)| {
+        let (y, x) = (i as f32 - center.0, j as f32 - center.1);
+        let ratio = if radius > 0.0 {
+            ((x * x + y * y).sqrt() / radius).min(1.0)
+        } else {
+            1.0
+        };
+        range.0 + (range.1 - range.0) * ratio
+    })
+}
+
+/// 画像の明るさを値に割り当てたマップを作る
+/// 黒(0)が`range.0`、白(255)が`range.1`になる。画像の大きさが`shape`と違う場合は最近傍法で拡大縮小する
+///
+/// # Arguments
+/// * `image` - もとにする画像
+/// * `shape` - 格子の大きさ(行数, 列数)
+/// * `range` - 値(黒, 白)
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::parameter_map::from_image;
+/// use my_alife::format::image::GrayImage;
+///
+/// let mut image = GrayImage::new(2, 1, 0);
+/// image.set_pixel(1, 0, 255);
+/// let map = from_image(&image, (2, 4), (0.03, 0.05));
+/// assert_eq!(map.row(1).to_vec(), vec![0.03, 0.03, 0.05, 0.05]);
+/// ```
+pub fn from_image(image: &GrayImage, shape: (usize, usize), range: (f32, f32)) -> Matrix<f32> {
+    let (rows, cols) = shape;
+    Array2::from_shape_fn(shape, |(i, j)| {
+        let x = j * image.width() / cols;
+        let y = i * image.height() / rows;
+        range.0 + (range.1 - range.0) * image.pixel(x, y) as f32 / 255.0
+    })
+}
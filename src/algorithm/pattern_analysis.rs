@@ -0,0 +1,516 @@
+use algorithm::spectral::Fft;
+use algorithm::stencil::neighbor_index;
+use ndarray::Array2;
+use num::Complex;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use visualizer::matrix_visualizer::Matrix;
+
+/// 2次元のパワースペクトル|F(k)|^2
+/// 平均を引いてからフーリエ変換するので、(0, 0)成分は0になる。添字(i, j)は行方向にi周期、列方向にj周期の波に対応する
+/// (`rows`と`cols`の半分より大きい添字は負の周波数)
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::pattern_analysis::power_spectrum;
+/// use ndarray::Array2;
+/// use std::f32::consts::PI;
+///
+/// // 列方向に4周期の縞は(0, 4)と(0, 28)に集まる
+/// let stripes = Array2::from_shape_fn((32, 32), |(_, j)| (2.0 * PI * 4.0 * j as f32 / 32.0).cos());
+/// let power = power_spectrum(&stripes);
+/// let total = power.scalar_sum();
+/// assert!((power[[0, 4]] + power[[0, 28]]) / total > 0.999);
+/// ```
+pub fn power_spectrum(matrix: &Matrix<f32>) -> Matrix<f32> {
+    let (rows, cols) = matrix.dim();
+    if rows == 0 || cols == 0 {
+        return Array2::zeros((rows, cols));
+    }
+    let mean = matrix.scalar_sum() / matrix.len() as f32;
+    let mut data: Vec<Complex<f32>> = matrix.iter().map(|e| Complex::new(e - mean, 0.0)).collect();
+
+    let row_fft = Fft::new(cols);
+    for row in data.chunks_mut(cols) {
+        row_fft.forward(row);
+    }
+    let column_fft = Fft::new(rows);
+    let mut column = vec![Complex::new(0.0, 0.0); rows];
+    for j in 0..cols {
+        for i in 0..rows {
+            column[i] = data[i * cols + j];
+        }
+        column_fft.forward(&mut column);
+        for i in 0..rows {
+            data[i * cols + j] = column[i];
+        }
+    }
+    Array2::from_shape_vec((rows, cols), data.iter().map(|c| c.norm_sqr()).collect()).unwrap()
+}
+
+/// パワースペクトルを周波数の大きさ|k|(1セルあたりの周期数)ごとに平均したもの
+/// 幅`1 / max(rows, cols)`の区間ごとに(区間の中心の周波数, パワーの平均)を返す。0番目の区間は直流成分を含む
+pub fn radial_power_spectrum(matrix: &Matrix<f32>) -> Vec<(f32, f32)> {
+    let (rows, cols) = matrix.dim();
+    let power = power_spectrum(matrix);
+    let size = rows.max(cols);
+    // 周波数の大きさは最大で√2 / 2
+    let bins = size / 2 * 3 / 2 + 2;
+    let mut sums = vec![(0.0, 0usize); bins];
+    for ((i, j), p) in power.indexed_iter() {
+        let frequency = signed_frequency(i, rows).hypot(signed_frequency(j, cols));
+        let bin = ((frequency * size as f32).round() as usize).min(bins - 1);
+        sums[bin].0 += p;
+        sums[bin].1 += 1;
+    }
+    sums.iter()
+        .enumerate()
+        .filter(|&(_, &(_, count))| count > 0)
+        .map(|(bin, &(sum, count))| (bin as f32 / size as f32, sum / count as f32))
+        .collect()
+}
+
+/// 最も強い波の波長(セルの数)
+/// 直流成分を除いた`radial_power_spectrum`が最大になる周波数の逆数。模様がない(一様な)場合はNone
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::pattern_analysis::dominant_wavelength;
+/// use ndarray::Array2;
+/// use std::f32::consts::PI;
+///
+/// // 斜めに波長8の縞
+/// let stripes = Array2::from_shape_fn((64, 64), |(i, j)| {
+///     (2.0 * PI * (i as f32 + j as f32) / 8.0 / 2f32.sqrt()).cos()
+/// });
+/// assert!((dominant_wavelength(&stripes).unwrap() - 8.0).abs() < 0.5);
+/// assert_eq!(dominant_wavelength(&Array2::ones((16, 16))), None);
+/// ```
+pub fn dominant_wavelength(matrix: &Matrix<f32>) -> Option<f32> {
+    let spectrum = radial_power_spectrum(matrix);
+    let total: f32 = spectrum.iter().map(|&(_, p)| p).sum();
+    spectrum
+        .into_iter()
+        .filter(|&(frequency, _)| frequency > 0.0)
+        .fold(None, |best: Option<(f32, f32)>, (frequency, power)| match best {
+            Some((_, best_power)) if best_power >= power => best,
+            _ => Some((frequency, power)),
+        })
+        .and_then(|(frequency, power)| {
+            if power > total * ::std::f32::EPSILON {
+                Some(1.0 / frequency)
+            } else {
+                None
+            }
+        })
+}
+
+/// 斑点として数えるセルの条件
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Threshold {
+    /// 値がこれより大きいセル(Gray-Scottモデルの`v`など)
+    Above(f32),
+    /// 値がこれより小さいセル(Gray-Scottモデルの`u`など)
+    Below(f32),
+}
+
+impl Threshold {
+    fn contains(&self, value: f32) -> bool {
+        match *self {
+            Threshold::Above(threshold) => value > threshold,
+            Threshold::Below(threshold) => value < threshold,
+        }
+    }
+}
+
+/// 上下左右につながったセルの塊(斑点)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spot {
+    /// セルの数
+    pub area: usize,
+    /// 重心(行, 列)。周期境界で端をまたぐ斑点は、つながった形の重心を格子の内側に折り返したもの
+    pub centroid: (f32, f32),
+}
+
+impl Spot {
+    /// 面積が同じ円の直径(セルの数)
+    pub fn diameter(&self) -> f32 {
+        2.0 * (self.area as f32 / PI).sqrt()
+    }
+}
+
+/// 条件`threshold`を満たすセルの、上下左右につながった塊をすべて求める
+///
+/// # Arguments
+/// * `matrix` - 調べる場
+/// * `threshold` - 斑点として数えるセルの条件
+/// * `periodic` - 端と反対側の端がつながっているとみなすかどうか
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::pattern_analysis::{find_spots, Threshold};
+/// use ndarray::arr2;
+///
+/// let v = arr2(&[
+///     [0.3, 0.0, 0.0, 0.3],
+///     [0.0, 0.0, 0.0, 0.0],
+///     [0.0, 0.3, 0.3, 0.0],
+///     [0.0, 0.3, 0.0, 0.0],
+/// ]);
+/// let spots = find_spots(&v, Threshold::Above(0.2), false);
+/// assert_eq!(spots.iter().map(|s| s.area).collect::<Vec<_>>(), vec![1, 1, 3]);
+///
+/// // 周期境界では左上と右上の斑点がつながる
+/// let spots = find_spots(&v, Threshold::Above(0.2), true);
+/// assert_eq!(spots.len(), 2);
+/// assert_eq!((spots[0].area, spots[0].centroid), (2, (0.0, 3.5)));
+/// ```
+pub fn find_spots(matrix: &Matrix<f32>, threshold: Threshold, periodic: bool) -> Vec<Spot> {
+    let (rows, cols) = matrix.dim();
+    let mut visited = Array2::from_elem((rows, cols), false);
+    let mut spots = Vec::new();
+    let mut queue = VecDeque::new();
+    for ((i, j), value) in matrix.indexed_iter() {
+        if visited[[i, j]] || !threshold.contains(*value) {
+            continue;
+        }
+        visited[[i, j]] = true;
+        // 端をまたいでも重心がずれないよう、折り返す前の座標を持って幅優先探索する
+        queue.push_back((i, j, i as isize, j as isize));
+        let (mut area, mut sum_row, mut sum_col) = (0, 0.0, 0.0);
+        while let Some((i, j, y, x)) = queue.pop_front() {
+            area += 1;
+            sum_row += y as f64;
+            sum_col += x as f64;
+            let neighbors = [
+                (neighbor_index(i, rows, -1, periodic), Some(j), -1, 0),
+                (neighbor_index(i, rows, 1, periodic), Some(j), 1, 0),
+                (Some(i), neighbor_index(j, cols, -1, periodic), 0, -1),
+                (Some(i), neighbor_index(j, cols, 1, periodic), 0, 1),
+            ];
+            for &(ni, nj, dy, dx) in &neighbors {
+                if let (Some(ni), Some(nj)) = (ni, nj) {
+                    if !visited[[ni, nj]] && threshold.contains(matrix[[ni, nj]]) {
+                        visited[[ni, nj]] = true;
+                        queue.push_back((ni, nj, y + dy, x + dx));
+                    }
+                }
+            }
+        }
+        let wrap = |sum: f64, len: usize| {
+            let mean = (sum / area as f64) % len as f64;
+            (if mean < 0.0 { mean + len as f64 } else { mean }) as f32
+        };
+        spots.push(Spot {
+            area: area,
+            centroid: (wrap(sum_row, rows), wrap(sum_col, cols)),
+        });
+    }
+    spots
+}
+
+/// 斑点の数と大きさの分布
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpotStatistics {
+    /// 斑点の数
+    pub count: usize,
+    /// 面積の平均
+    pub mean_area: f32,
+    /// 面積の標準偏差
+    pub std_area: f32,
+    /// 最小の面積
+    pub min_area: usize,
+    /// 最大の面積
+    pub max_area: usize,
+    /// `histogram[n]`は面積が`n * bin_width`以上`(n + 1) * bin_width`未満の斑点の数
+    pub histogram: Vec<usize>,
+    /// ヒストグラムの区間の幅
+    pub bin_width: usize,
+}
+
+impl SpotStatistics {
+    /// `spots`の面積の統計をとる。ヒストグラムの区間の幅は`bin_width`(0なら1とみなす)
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::algorithm::pattern_analysis::{Spot, SpotStatistics};
+    ///
+    /// let spots: Vec<Spot> = [4, 5, 12]
+    ///     .iter()
+    ///     .map(|&area| Spot { area: area, centroid: (0.0, 0.0) })
+    ///     .collect();
+    /// let statistics = SpotStatistics::new(&spots, 5);
+    /// assert_eq!((statistics.count, statistics.mean_area), (3, 7.0));
+    /// assert_eq!(statistics.histogram, vec![1, 1, 1]);
+    /// ```
+    pub fn new(spots: &[Spot], bin_width: usize) -> SpotStatistics {
+        let bin_width = bin_width.max(1);
+        let n = spots.len().max(1) as f32;
+        let mean = spots.iter().map(|s| s.area as f32).sum::<f32>() / n;
+        let variance = spots.iter().map(|s| (s.area as f32 - mean).powi(2)).sum::<f32>() / n;
+        let max_area = spots.iter().map(|s| s.area).max().unwrap_or(0);
+        let mut histogram = vec![0; if spots.is_empty() { 0 } else { max_area / bin_width + 1 }];
+        for spot in spots {
+            histogram[spot.area / bin_width] += 1;
+        }
+        SpotStatistics {
+            count: spots.len(),
+            mean_area: mean,
+            std_area: variance.sqrt(),
+            min_area: spots.iter().map(|s| s.area).min().unwrap_or(0),
+            max_area: max_area,
+            histogram: histogram,
+            bin_width: bin_width,
+        }
+    }
+}
+
+/// 縞の向きのそろい具合
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orientation {
+    /// 縞の平均的な向き。列の増える向きから行の増える向きへ測った角度(ラジアン、0以上π未満)
+    pub angle: f32,
+    /// 向きのそろい具合。1ならすべての縞が平行で、0なら向きに偏りがない
+    pub order: f32,
+}
+
+/// 勾配の構造テンソルの平均から縞の向きとそろい具合を求める
+/// 勾配のない(一様な)場はorderが0になる
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::pattern_analysis::orientation;
+/// use ndarray::Array2;
+/// use std::f32::consts::PI;
+///
+/// // 行方向に並んだ縞(横縞)は列の向きと平行
+/// let stripes = Array2::from_shape_fn((32, 32), |(i, _)| (2.0 * PI * i as f32 / 8.0).sin());
+/// let result = orientation(&stripes, true);
+/// assert!(result.order > 0.99);
+/// assert!(result.angle.min(PI - result.angle) < 1e-3);
+///
+/// // 斑点は向きがそろわない
+/// let spots = Array2::from_shape_fn((32, 32), |(i, j)| {
+///     (2.0 * PI * i as f32 / 8.0).sin() * (2.0 * PI * j as f32 / 8.0).sin()
+/// });
+/// assert!(orientation(&spots, true).order < 0.01);
+/// ```
+pub fn orientation(matrix: &Matrix<f32>, periodic: bool) -> Orientation {
+    let (mut jxx, mut jyy, mut jxy) = (0.0f64, 0.0f64, 0.0f64);
+    for ((i, j), _) in matrix.indexed_iter() {
+        let (gy, gx) = gradient(matrix, i, j, periodic);
+        jxx += (gx * gx) as f64;
+        jyy += (gy * gy) as f64;
+        jxy += (gx * gy) as f64;
+    }
+    let trace = jxx + jyy;
+    if trace <= 0.0 {
+        return Orientation { angle: 0.0, order: 0.0 };
+    }
+    let order = ((jxx - jyy).powi(2) + 4.0 * jxy * jxy).sqrt() / trace;
+    // 勾配の向きは縞に垂直なので、90度回す
+    let gradient_angle = 0.5 * (2.0 * jxy).atan2(jxx - jyy);
+    let angle = (gradient_angle + ::std::f64::consts::FRAC_PI_2) % ::std::f64::consts::PI;
+    Orientation {
+        angle: (if angle < 0.0 { angle + ::std::f64::consts::PI } else { angle }) as f32,
+        order: order as f32,
+    }
+}
+
+/// 全変動。右と下の隣のセルとの差の絶対値の合計
+/// `periodic`なら端と反対側の端の差も含める
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::pattern_analysis::total_variation;
+/// use ndarray::arr2;
+///
+/// let matrix = arr2(&[[0.0, 1.0], [0.0, 1.0]]);
+/// assert_eq!(total_variation(&matrix, false), 2.0);
+/// assert_eq!(total_variation(&matrix, true), 4.0);
+/// ```
+pub fn total_variation(matrix: &Matrix<f32>, periodic: bool) -> f32 {
+    let (rows, cols) = matrix.dim();
+    let mut sum = 0.0f64;
+    for ((i, j), value) in matrix.indexed_iter() {
+        if let Some(down) = neighbor_index(i, rows, 1, periodic) {
+            sum += (matrix[[down, j]] - value).abs() as f64;
+        }
+        if let Some(right) = neighbor_index(j, cols, 1, periodic) {
+            sum += (matrix[[i, right]] - value).abs() as f64;
+        }
+    }
+    sum as f32
+}
+
+/// 2つの場の変化の大きさ。差の絶対値の平均
+pub fn change_norm(previous: &Matrix<f32>, current: &Matrix<f32>) -> f32 {
+    current.iter().zip(previous.iter()).map(|(a, b)| (a - b).abs()).sum::<f32>() / current.len().max(1) as f32
+}
+
+/// 更新ごとの変化の大きさから、定常状態に達したかを判定する
+/// 単位時間あたりの変化(`change_norm`を経過時間で割ったもの)が`window`回続けて`tolerance`を下回ったら定常とみなす
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::gray_scott::{GrayScott, GrayScottParams};
+/// use my_alife::algorithm::pattern_analysis::SteadyStateDetector;
+///
+/// // F=0.01, K=0.06では模様が消えて一様な状態に落ち着く
+/// let params = GrayScottParams::new(0.01, 0.06);
+/// let elapsed = params.dt * params.substeps as f32;
+/// let mut simulator = GrayScott::new(params, (32, 32));
+/// let mut detector = SteadyStateDetector::new(1e-6, 3);
+/// let mut steps = 0;
+/// while !detector.update(&simulator.state().0, elapsed) && steps < 5000 {
+///     simulator.step();
+///     steps += 1;
+/// }
+/// assert!(detector.is_steady());
+/// assert!(detector.last_change().unwrap() < 1e-6);
+///
+/// // 時間が経っていなければ変化の割合は求めない
+/// let last_change = detector.last_change();
+/// assert!(detector.update(&simulator.state().0, 0.0));
+/// assert_eq!(detector.last_change(), last_change);
+/// ```
+pub struct SteadyStateDetector {
+    /// 定常とみなす単位時間あたりの変化の上限
+    pub tolerance: f32,
+    /// 何回続けて`tolerance`を下回ったら定常とみなすか
+    pub window: usize,
+    previous: Option<Matrix<f32>>,
+    last_change: Option<f32>,
+    quiet_steps: usize,
+}
+
+impl SteadyStateDetector {
+    pub fn new(tolerance: f32, window: usize) -> SteadyStateDetector {
+        SteadyStateDetector {
+            tolerance: tolerance,
+            window: window.max(1),
+            previous: None,
+            last_change: None,
+            quiet_steps: 0,
+        }
+    }
+
+    /// 前回からの経過時間`elapsed`の後の場`current`を渡し、定常状態に達したかを返す
+    /// 初めて呼んだときは比べる相手がないので常に`false`。
+    /// `elapsed`が0以下(またはNaN)のときは単位時間あたりの変化を求められないので、比べずに`current`だけを覚える
+    pub fn update(&mut self, current: &Matrix<f32>, elapsed: f32) -> bool {
+        match self.previous {
+            Some(ref previous) if elapsed > 0.0 => {
+                let change = change_norm(previous, current) / elapsed;
+                self.last_change = Some(change);
+                if change < self.tolerance {
+                    self.quiet_steps += 1;
+                } else {
+                    self.quiet_steps = 0;
+                }
+            }
+            _ => {}
+        }
+        self.previous = Some(current.clone());
+        self.is_steady()
+    }
+
+    /// 定常状態に達しているか
+    pub fn is_steady(&self) -> bool {
+        self.quiet_steps >= self.window
+    }
+
+    /// 最後に`update`したときの単位時間あたりの変化
+    pub fn last_change(&self) -> Option<f32> {
+        self.last_change
+    }
+
+    /// 履歴を消して最初からやり直す
+    pub fn reset(&mut self) {
+        self.previous = None;
+        self.last_change = None;
+        self.quiet_steps = 0;
+    }
+}
+
+/// 模様の特徴量をまとめたもの
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternSummary {
+    /// 最も強い波の波長(セルの数)。一様な場合はNone
+    pub wavelength: Option<f32>,
+    /// 最小値と最大値の中間より小さいセルの塊(Gray-Scottモデルの`u`の斑点)の統計
+    pub spots: SpotStatistics,
+    /// 縞の向きのそろい具合
+    pub orientation: Orientation,
+    /// 1セルあたりの全変動
+    pub total_variation: f32,
+}
+
+/// Gray-Scottモデルの`u`のような場から、周期境界を仮定して特徴量をまとめて求める
+/// 斑点は最小値と最大値の中間より小さいセルの塊として数える
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::pattern_analysis::summarize;
+/// use ndarray::Array2;
+///
+/// // 16セルおきに3x3の穴があいた場
+/// let u = Array2::from_shape_fn((64, 64), |(i, j)| if i % 16 < 3 && j % 16 < 3 { 0.2 } else { 1.0 });
+/// let summary = summarize(&u);
+/// assert_eq!(summary.spots.count, 16);
+/// assert_eq!(summary.spots.mean_area, 9.0);
+/// assert!((summary.wavelength.unwrap() - 16.0).abs() < 1.0);
+/// ```
+pub fn summarize(matrix: &Matrix<f32>) -> PatternSummary {
+    let min = matrix.iter().cloned().fold(::std::f32::INFINITY, f32::min);
+    let max = matrix.iter().cloned().fold(::std::f32::NEG_INFINITY, f32::max);
+    let spots = find_spots(matrix, Threshold::Below((min + max) / 2.0), true);
+    PatternSummary {
+        wavelength: dominant_wavelength(matrix),
+        spots: SpotStatistics::new(&spots, 1),
+        orientation: orientation(matrix, true),
+        total_variation: total_variation(matrix, true) / matrix.len().max(1) as f32,
+    }
+}
+
+/// 長さ`len`の離散フーリエ変換の`index`番目の周波数(1セルあたりの周期数、符号つき)
+fn signed_frequency(index: usize, len: usize) -> f32 {
+    if index <= len / 2 {
+        index as f32 / len as f32
+    } else {
+        index as f32 / len as f32 - 1.0
+    }
+}
+
+/// (`i`, `j`)での中心差分の勾配(行方向, 列方向)。格子の外側は中心と同じ値とみなす
+fn gradient(matrix: &Matrix<f32>, i: usize, j: usize, periodic: bool) -> (f32, f32) {
+    let (rows, cols) = matrix.dim();
+    let center = matrix[[i, j]];
+    let at = |i: Option<usize>, j: Option<usize>| match (i, j) {
+        (Some(i), Some(j)) => matrix[[i, j]],
+        _ => center,
+    };
+    let up = at(neighbor_index(i, rows, -1, periodic), Some(j));
+    let down = at(neighbor_index(i, rows, 1, periodic), Some(j));
+    let left = at(Some(i), neighbor_index(j, cols, -1, periodic));
+    let right = at(Some(i), neighbor_index(j, cols, 1, periodic));
+    ((down - up) / 2.0, (right - left) / 2.0)
+}
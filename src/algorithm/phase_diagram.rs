@@ -0,0 +1,263 @@
+use algorithm::gray_scott::{GrayScott, GrayScottParams, InitialCondition};
+use algorithm::pattern_analysis::SteadyStateDetector;
+use failure;
+use format::image::GrayImage;
+use rayon::prelude::*;
+use std::fmt::Write as FmtWrite;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use visualizer::matrix_visualizer::Matrix;
+
+/// (F, K)の相図を作るときの設定
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SweepConfig {
+    /// Fの範囲(最小値, 最大値)
+    pub f_range: (f32, f32),
+    /// Kの範囲(最小値, 最大値)
+    pub k_range: (f32, f32),
+    /// Fを何通り調べるか
+    pub f_count: usize,
+    /// Kを何通り調べるか
+    pub k_count: usize,
+    /// 1回のシミュレーションの格子の大きさ(行数, 列数)
+    pub shape: (usize, usize),
+    /// `GrayScott::step`を呼ぶ回数の上限
+    pub max_steps: usize,
+    /// 1回の`step`での`u`の変化(絶対値の平均を時間で割ったもの)がこれを下回ったら定常状態とみなして止める
+    pub steady_tolerance: f32,
+    /// F, K以外のパラメーター
+    pub base_params: GrayScottParams,
+    /// 初期状態
+    pub initial_condition: InitialCondition,
+}
+
+impl Default for SweepConfig {
+    /// Pearson(1993)の相図とだいたい同じ範囲
+    fn default() -> SweepConfig {
+        SweepConfig {
+            f_range: (0.0, 0.08),
+            k_range: (0.03, 0.07),
+            f_count: 16,
+            k_count: 16,
+            shape: (64, 64),
+            max_steps: 1000,
+            steady_tolerance: 1e-7,
+            base_params: GrayScottParams::default(),
+            initial_condition: InitialCondition::RandomSquares { count: 3, seed: 1 },
+        }
+    }
+}
+
+impl SweepConfig {
+    /// `index`番目のFの値
+    pub fn f(&self, index: usize) -> f32 {
+        interpolate(self.f_range, index, self.f_count)
+    }
+
+    /// `index`番目のKの値
+    pub fn k(&self, index: usize) -> f32 {
+        interpolate(self.k_range, index, self.k_count)
+    }
+}
+
+fn interpolate(range: (f32, f32), index: usize, count: usize) -> f32 {
+    if count <= 1 {
+        range.0
+    } else {
+        range.0 + (range.1 - range.0) * index as f32 / (count - 1) as f32
+    }
+}
+
+/// 1組の(F, K)でのシミュレーション結果
+#[derive(Clone, Debug)]
+pub struct SweepCell {
+    /// F
+    pub f: f32,
+    /// K
+    pub k: f32,
+    /// 実際に`step`を呼んだ回数
+    pub steps: usize,
+    /// 定常状態に達して止まったかどうか
+    pub steady: bool,
+    /// 値がNaNや無限大になったかどうか
+    pub diverged: bool,
+    /// 最後の`step`での`u`の変化
+    pub change: f32,
+    /// `u`の平均
+    pub mean_u: f32,
+    /// `v`の平均
+    pub mean_v: f32,
+    /// `u`の標準偏差
+    pub std_u: f32,
+    /// `v`の標準偏差
+    pub std_v: f32,
+    /// `u`の最小値
+    pub min_u: f32,
+    /// `u`の最大値
+    pub max_u: f32,
+    /// 最終状態の`u`
+    pub u: Matrix<f32>,
+}
+
+/// (F, K)の相図
+pub struct PhaseDiagram {
+    /// 作ったときの設定
+    pub config: SweepConfig,
+    /// 結果。Fの添字`i`、Kの添字`j`の結果が`i * k_count + j`番目にある
+    pub cells: Vec<SweepCell>,
+}
+
+// モザイク画像のタイル同士の間隔
+const MOSAIC_MARGIN: usize = 2;
+
+impl PhaseDiagram {
+    /// Fの添字`i`、Kの添字`j`の結果
+    pub fn cell(&self, i: usize, j: usize) -> &SweepCell {
+        &self.cells[i * self.config.k_count + j]
+    }
+
+    /// 最終状態の`u`をタイル状に並べた画像を作る
+    /// 横軸がK(右ほど大きい)、縦軸がF(上ほど大きい)になる
+    pub fn mosaic(&self) -> GrayImage {
+        let (rows, cols) = self.config.shape;
+        let mut image = GrayImage::new(
+            MOSAIC_MARGIN + self.config.k_count * (cols + MOSAIC_MARGIN),
+            MOSAIC_MARGIN + self.config.f_count * (rows + MOSAIC_MARGIN),
+            255,
+        );
+        for i in 0..self.config.f_count {
+            for j in 0..self.config.k_count {
+                let tile = GrayImage::from_matrix(&self.cell(i, j).u);
+                let x = MOSAIC_MARGIN + j * (cols + MOSAIC_MARGIN);
+                let y = MOSAIC_MARGIN + (self.config.f_count - 1 - i) * (rows + MOSAIC_MARGIN);
+                image.paste(&tile, x, y);
+            }
+        }
+        image
+    }
+
+    /// 各(F, K)の集計値をCSVにする
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("f,k,steps,steady,diverged,change,mean_u,mean_v,std_u,std_v,min_u,max_u\n");
+        for c in &self.cells {
+            writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{},{},{},{}",
+                c.f,
+                c.k,
+                c.steps,
+                c.steady,
+                c.diverged,
+                c.change,
+                c.mean_u,
+                c.mean_v,
+                c.std_u,
+                c.std_v,
+                c.min_u,
+                c.max_u
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    /// モザイク画像とCSVを保存する
+    ///
+    /// # Arguments
+    /// * `image_path` - モザイク画像の保存先(拡張子で形式が決まる)
+    /// * `csv_path` - CSVの保存先
+    pub fn save<P: AsRef<Path>, Q: AsRef<Path>>(&self, image_path: P, csv_path: Q) -> Result<(), failure::Error> {
+        self.mosaic().save(image_path)?;
+        File::create(csv_path)?.write_all(self.to_csv().as_bytes())?;
+        Ok(())
+    }
+}
+
+/// (F, K)の格子点ごとにGray-Scottモデルをウィンドウを開かずに計算し、相図を作る
+/// 格子点ごとの計算はrayonで並列に行う
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::phase_diagram::{sweep, SweepConfig};
+///
+/// let config = SweepConfig {
+///     f_count: 2,
+///     k_count: 3,
+///     shape: (32, 32),
+///     max_steps: 5,
+///     ..Default::default()
+/// };
+/// let diagram = sweep(&config);
+/// assert_eq!(diagram.cells.len(), 6);
+/// assert_eq!(diagram.to_csv().lines().count(), 7);
+/// ```
+pub fn sweep(config: &SweepConfig) -> PhaseDiagram {
+    let indices = (0..config.f_count)
+        .flat_map(|i| (0..config.k_count).map(move |j| (i, j)))
+        .collect::<Vec<_>>();
+    let cells = indices
+        .into_par_iter()
+        .map(|(i, j)| run(config, config.f(i), config.k(j)))
+        .collect();
+    PhaseDiagram {
+        config: *config,
+        cells: cells,
+    }
+}
+
+fn run(config: &SweepConfig, f: f32, k: f32) -> SweepCell {
+    let params = GrayScottParams {
+        f: f,
+        k: k,
+        ..config.base_params
+    };
+    let mut simulator = GrayScott::from_state(params, config.initial_condition.state(config.shape));
+    let elapsed = params.dt * params.substeps.max(1) as f32;
+
+    let mut steps = 0;
+    let mut steady = false;
+    let mut diverged = false;
+    let mut detector = SteadyStateDetector::new(config.steady_tolerance, 1);
+    detector.update(&simulator.state().0, elapsed);
+    while steps < config.max_steps {
+        simulator.step();
+        steps += 1;
+        let u = &simulator.state().0;
+        if u.iter().any(|e| !e.is_finite()) {
+            diverged = true;
+            break;
+        }
+        if detector.update(u, elapsed) {
+            steady = true;
+            break;
+        }
+    }
+    let change = detector.last_change().unwrap_or(::std::f32::INFINITY);
+
+    let (u, v) = simulator.into_state();
+    let (mean_u, std_u) = mean_std(&u);
+    let (mean_v, std_v) = mean_std(&v);
+    SweepCell {
+        f: f,
+        k: k,
+        steps: steps,
+        steady: steady,
+        diverged: diverged,
+        change: change,
+        mean_u: mean_u,
+        mean_v: mean_v,
+        std_u: std_u,
+        std_v: std_v,
+        min_u: u.iter().cloned().fold(::std::f32::INFINITY, f32::min),
+        max_u: u.iter().cloned().fold(::std::f32::NEG_INFINITY, f32::max),
+        u: u,
+    }
+}
+
+fn mean_std(matrix: &Matrix<f32>) -> (f32, f32) {
+    let n = matrix.len().max(1) as f32;
+    let mean = matrix.scalar_sum() / n;
+    let variance = matrix.iter().map(|e| (e - mean) * (e - mean)).sum::<f32>() / n;
+    (mean, variance.sqrt())
+}
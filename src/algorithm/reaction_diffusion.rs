@@ -0,0 +1,756 @@
+use algorithm::gray_scott::{self, GrayScottParams};
+use algorithm::stencil::{cast, Boundary, Integrator, Real, StencilSolver};
+use failure;
+use ndarray::{Array, Array2};
+use ndarray_rand::RandomExt;
+use ndarray_rand::F32;
+use num::Complex;
+use rand::distributions::Range;
+use visualizer::matrix_visualizer::Matrix;
+
+/// 2変数(u, v)の反応拡散系
+///
+/// 拡散係数と各セルでの反応項さえ与えれば、`Simulator`で時間発展を計算でき、
+/// `MatrixVisualizer::draw_simulation`で描画できる。
+/// パラメーターは`f32`で持ち、反応項は状態と同じ型(`f32`か`f64`)で計算する
+pub trait ReactionDiffusion: Sync {
+    /// uとvの拡散係数
+    fn diffusion(&self) -> (f32, f32);
+
+    /// セルの値(u, v)での反応項(du/dt, dv/dt)
+    fn reaction<T: Real>(&self, u: T, v: T) -> (T, T);
+
+    /// このモデルに向いた初期状態
+    ///
+    /// # Arguments
+    /// * `shape` - 格子の大きさ(行数, 列数)
+    fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>);
+
+    /// 描画するときのuの範囲。この範囲を黒から白に割り当てる
+    fn display_range(&self) -> (f32, f32) {
+        (0.0, 1.0)
+    }
+}
+
+impl<'a, M: ReactionDiffusion + ?Sized> ReactionDiffusion for &'a M {
+    fn diffusion(&self) -> (f32, f32) {
+        (**self).diffusion()
+    }
+
+    fn reaction<T: Real>(&self, u: T, v: T) -> (T, T) {
+        (**self).reaction(u, v)
+    }
+
+    fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        (**self).initial_state(shape)
+    }
+
+    fn display_range(&self) -> (f32, f32) {
+        (**self).display_range()
+    }
+}
+
+/// 空間と時間の離散化の設定
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Discretization {
+    /// 格子の間隔
+    pub dx: f32,
+    /// 時間の刻み幅
+    pub dt: f32,
+    /// 1回の更新(描画)あたりに進める時間ステップ数
+    pub substeps: usize,
+    /// 格子の端の境界条件
+    pub boundary: Boundary,
+    /// 時間積分の方法
+    pub integrator: Integrator,
+}
+
+impl Discretization {
+    /// 周期境界で陽的Euler法を使うDiscretizationインスタンスを生成する
+    pub fn new(dx: f32, dt: f32, substeps: usize) -> Discretization {
+        Discretization {
+            dx: dx,
+            dt: dt,
+            substeps: substeps,
+            boundary: Boundary::Periodic,
+            integrator: Integrator::Euler,
+        }
+    }
+}
+
+/// `ReactionDiffusion`を実装した任意のモデルのシミュレーター
+/// 状態の値の型`T`は初期状態の型で決まる
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::reaction_diffusion::{Brusselator, Discretization, ReactionDiffusion, Simulator};
+/// use my_alife::algorithm::stencil::cast_state;
+///
+/// let model = Brusselator::default();
+/// let state = model.initial_state((32, 48));
+/// let discretization = Discretization::new(1.0, 0.01, 10);
+/// let mut simulator = Simulator::new(model, discretization, state.clone()).unwrap();
+/// simulator.step();
+/// assert_eq!(simulator.state().0.dim(), (32, 48));
+///
+/// // f64の初期状態から始めると倍精度で計算する
+/// let mut precise = Simulator::new(model, discretization, cast_state::<f32, f64>(&state)).unwrap();
+/// precise.step();
+/// let difference = (precise.state().0[[3, 5]] - simulator.state().0[[3, 5]] as f64).abs();
+/// assert!(difference > 0.0 && difference < 1e-4);
+/// ```
+pub struct Simulator<M: ReactionDiffusion, T: Real = f32> {
+    model: M,
+    discretization: Discretization,
+    solver: StencilSolver<T>,
+}
+
+impl<M: ReactionDiffusion, T: Real> Simulator<M, T> {
+    /// Simulatorインスタンスを生成する
+    ///
+    /// # Arguments
+    /// * `model` - モデル
+    /// * `discretization` - 離散化の設定
+    /// * `state` - 初期状態(u, v)
+    ///
+    /// 初期状態で時間の刻み幅が大きすぎて値が発散する場合はエラーになる(`check_stability`を参照)
+    pub fn new(
+        model: M,
+        discretization: Discretization,
+        state: (Matrix<T>, Matrix<T>),
+    ) -> Result<Simulator<M, T>, failure::Error> {
+        check_stability(&model, &discretization, &state)?;
+        Ok(Simulator {
+            model: model,
+            discretization: discretization,
+            solver: StencilSolver::new(state),
+        })
+    }
+
+    /// 現在の状態とパラメーターで、時間の刻み幅が安定な範囲にあるか調べる
+    /// パラメーターを変更したあとに呼ぶとよい
+    pub fn check_stability(&self) -> Result<(), failure::Error> {
+        check_stability(&self.model, &self.discretization, self.solver.state())
+    }
+
+    /// モデル
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// モデルのパラメーターを変更する
+    pub fn model_mut(&mut self) -> &mut M {
+        &mut self.model
+    }
+
+    /// 離散化の設定
+    pub fn discretization(&self) -> &Discretization {
+        &self.discretization
+    }
+
+    /// 離散化の設定を変更する
+    pub fn discretization_mut(&mut self) -> &mut Discretization {
+        &mut self.discretization
+    }
+
+    /// 障害物のマスクを変更する。`true`のセルが障害物になる
+    pub fn set_mask(&mut self, mask: Option<Array2<bool>>) -> Result<(), failure::Error> {
+        self.solver.set_mask(mask)
+    }
+
+    /// 現在の状態(u, v)
+    pub fn state(&self) -> &(Matrix<T>, Matrix<T>) {
+        self.solver.state()
+    }
+
+    /// 現在の状態を取り出す
+    pub fn into_state(self) -> (Matrix<T>, Matrix<T>) {
+        self.solver.into_state()
+    }
+
+    /// `discretization.substeps`ステップだけ時間を進める
+    pub fn step(&mut self) {
+        step_model(&mut self.solver, &self.model, &self.discretization);
+    }
+}
+
+/// `model`に従って`solver`を`discretization.substeps`ステップだけ進める
+pub fn step_model<M, T>(solver: &mut StencilSolver<T>, model: &M, discretization: &Discretization)
+where
+    M: ReactionDiffusion,
+    T: Real,
+{
+    solver.set_boundary(discretization.boundary);
+    solver.set_integrator(discretization.integrator);
+    let (du, dv) = model.diffusion();
+    let (dx, dt) = (cast(discretization.dx), cast(discretization.dt));
+    for _ in 0..discretization.substeps {
+        solver.step((cast(du), cast(dv)), dx, dt, |u, v| model.reaction(u, v));
+    }
+}
+
+/// 時間の刻み幅が、陽的な積分法で値が発散しない範囲にあるか調べる
+///
+/// 各セルで反応項のヤコビ行列Jを数値微分で求め、拡散による減衰σ(0から8/dx²まで)を引いた行列J - σDの固有値λについて、
+/// 線形の方程式 du/dt = λu を積分法で解いたときの増幅率|R(λdt)|が1を超えないかを調べる。
+/// 固有値の実部が正(モデル自体が不安定で、パターンができる向き)のときは、1ステップでの変化が小さい(|λdt| ≤ 1)ことを求める。
+/// 適応刻み幅は自分で刻み幅を調整するので調べない
+///
+/// 線形化した見積もりなので保守的で、波の先端のような急な変化の途中の状態ではエラーになっても発散しないことがある。
+/// 初期状態やパラメーターを変えた直後に調べるのに向いている
+///
+/// # Arguments
+/// * `model` - モデル
+/// * `discretization` - 離散化の設定
+/// * `state` - 調べる状態(u, v)
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::gray_scott::GrayScottParams;
+/// use my_alife::algorithm::reaction_diffusion::{check_stability, Discretization, ReactionDiffusion};
+/// use my_alife::algorithm::stencil::{cast_state, Integrator};
+///
+/// let model = GrayScottParams::default();
+/// let state = model.initial_state((16, 16));
+/// assert!(check_stability(&model, &model.discretization(), &state).is_ok());
+/// // f64の状態は丸めずにf64のまま調べる
+/// assert!(check_stability(&model, &model.discretization(), &cast_state::<f32, f64>(&state)).is_ok());
+///
+/// // 拡散数Du dt / dx²が1/4を超えるとEuler法は発散する
+/// let mut discretization = Discretization { dt: 2.0, ..model.discretization() };
+/// assert!(check_stability(&model, &discretization, &state).is_err());
+/// discretization.integrator = Integrator::Adaptive(1e-3);
+/// assert!(check_stability(&model, &discretization, &state).is_ok());
+/// ```
+pub fn check_stability<M: ReactionDiffusion + ?Sized, T: Real>(
+    model: &M,
+    discretization: &Discretization,
+    state: &(Matrix<T>, Matrix<T>),
+) -> Result<(), failure::Error> {
+    check_stability_varying(|_| model, discretization, state)
+}
+
+/// パラメーターがセルごとに異なる場合の`check_stability`
+///
+/// # Arguments
+/// * `model_at` - セルの添字(行優先で数えたもの)を受け取り、そのセルでのモデルを返す関数
+/// * `discretization` - 離散化の設定
+/// * `state` - 調べる状態(u, v)。反応項のヤコビ行列と固有値は状態と同じ型で求める
+pub fn check_stability_varying<M, F, T>(
+    model_at: F,
+    discretization: &Discretization,
+    state: &(Matrix<T>, Matrix<T>),
+) -> Result<(), failure::Error>
+where
+    M: ReactionDiffusion,
+    F: Fn(usize) -> M,
+    T: Real,
+{
+    let integrator = discretization.integrator;
+    if let Integrator::Adaptive(_) = integrator {
+        return Ok(());
+    }
+    let (dx, dt) = (discretization.dx, discretization.dt);
+    let (spacing, step) = (cast::<T>(dx), cast::<T>(dt));
+    let max_decay = cast::<T>(8.0) / (spacing * spacing);
+    let quarter = max_decay / cast(4.0);
+    let decays = [T::zero(), quarter, quarter * cast(2.0), quarter * cast(3.0), max_decay];
+    for (index, (((i, j), &u), &v)) in state.0.indexed_iter().zip(state.1.iter()).enumerate() {
+        if !u.is_finite() || !v.is_finite() {
+            return Err(failure::err_msg(format!("state is not finite at ({}, {})", i, j)));
+        }
+        let model = model_at(index);
+        let (du, dv) = model.diffusion();
+        let jacobian = reaction_jacobian(&model, u, v);
+        for &decay in &decays {
+            let matrix = [
+                [jacobian[0][0] - decay * cast(du), jacobian[0][1]],
+                [jacobian[1][0], jacobian[1][1] - decay * cast(dv)],
+            ];
+            for &eigenvalue in &eigenvalues(matrix) {
+                let z = eigenvalue * step;
+                let stable = if z.re <= T::zero() {
+                    integrator.amplification(z) <= cast(1.0 + 1e-4)
+                } else {
+                    z.norm() <= T::one()
+                };
+                if !stable {
+                    return Err(failure::err_msg(format!(
+                        "dt = {} is too large for {:?} at ({}, {}) where (u, v) = ({}, {}): \
+                         eigenvalue * dt = {}, diffusion numbers (Du, Dv) * dt / dx^2 = ({}, {})",
+                        dt,
+                        integrator,
+                        i,
+                        j,
+                        u,
+                        v,
+                        z,
+                        du * dt / (dx * dx),
+                        dv * dt / (dx * dx)
+                    )));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 反応項の(u, v)でのヤコビ行列を中心差分で求める
+fn reaction_jacobian<M: ReactionDiffusion + ?Sized, T: Real>(model: &M, u: T, v: T) -> [[T; 2]; 2] {
+    let (step, two) = (cast::<T>(1e-3), cast::<T>(2.0));
+    let hu = step * u.abs().max(T::one());
+    let hv = step * v.abs().max(T::one());
+    let (up, vp) = (model.reaction(u + hu, v), model.reaction(u, v + hv));
+    let (um, vm) = (model.reaction(u - hu, v), model.reaction(u, v - hv));
+    [
+        [(up.0 - um.0) / (two * hu), (vp.0 - vm.0) / (two * hv)],
+        [(up.1 - um.1) / (two * hu), (vp.1 - vm.1) / (two * hv)],
+    ]
+}
+
+/// 2x2行列の固有値
+fn eigenvalues<T: Real>(m: [[T; 2]; 2]) -> [Complex<T>; 2] {
+    let two = cast::<T>(2.0);
+    let half_trace = (m[0][0] + m[1][1]) / two;
+    let half_difference = (m[0][0] - m[1][1]) / two;
+    let discriminant = Complex::new(half_difference * half_difference + m[0][1] * m[1][0], T::zero()).sqrt();
+    let center = Complex::new(half_trace, T::zero());
+    [center + discriminant, center - discriminant]
+}
+
+/// 一様な状態(`u`, `v`)に、対称性を崩すため振幅`noise`のノイズを入れた状態を作る
+pub fn noisy_uniform_state(shape: (usize, usize), u: f32, v: f32, noise: f32) -> (Matrix<f32>, Matrix<f32>) {
+    let u = Array::random(shape, F32(Range::new(-noise as f64, noise as f64))) + u;
+    let v = Array::random(shape, F32(Range::new(-noise as f64, noise as f64))) + v;
+    (u, v)
+}
+
+/// 興奮性媒質で渦巻き波を作るための、途中で切れた波の状態を作る
+/// 上半分を興奮状態(`excited`)にし、右半分を不応状態(`refractory`)にする
+pub fn broken_wave_state(
+    shape: (usize, usize),
+    rest: (f32, f32),
+    excited: f32,
+    refractory: f32,
+) -> (Matrix<f32>, Matrix<f32>) {
+    let (rows, cols) = shape;
+    let u = Array2::from_shape_fn(shape, |(i, _)| if i < rows / 2 { excited } else { rest.0 });
+    let v = Array2::from_shape_fn(shape, |(_, j)| if j >= cols / 2 { refractory } else { rest.1 });
+    (u, v)
+}
+
+/// FitzHugh-Nagumoモデル
+/// du/dt = Du∇²u + u - u³ - v, dv/dt = Dv∇²v + ε(u - a1 v - a0)
+/// vの拡散がuより十分速いとチューリングパターン(迷路状の縞や斑点)ができる
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FitzHughNagumo {
+    /// uの拡散係数
+    pub du: f32,
+    /// vの拡散係数
+    pub dv: f32,
+    /// vのnullclineの切片
+    pub a0: f32,
+    /// vのnullclineの傾き
+    pub a1: f32,
+    /// uとvの時間スケールの比ε
+    pub epsilon: f32,
+}
+
+impl Default for FitzHughNagumo {
+    /// 迷路状の縞ができるパラメーター
+    fn default() -> FitzHughNagumo {
+        FitzHughNagumo {
+            du: 1.0,
+            dv: 10.0,
+            a0: 0.0,
+            a1: 0.8,
+            epsilon: 2.0,
+        }
+    }
+}
+
+impl ReactionDiffusion for FitzHughNagumo {
+    fn diffusion(&self) -> (f32, f32) {
+        (self.du, self.dv)
+    }
+
+    fn reaction<T: Real>(&self, u: T, v: T) -> (T, T) {
+        let (a0, a1, epsilon) = (cast::<T>(self.a0), cast::<T>(self.a1), cast::<T>(self.epsilon));
+        (u - u * u * u - v, epsilon * (u - a1 * v - a0))
+    }
+
+    fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        noisy_uniform_state(shape, 0.0, 0.0, 0.1)
+    }
+
+    fn display_range(&self) -> (f32, f32) {
+        (-1.0, 1.0)
+    }
+}
+
+/// Brusselator
+/// du/dt = Du∇²u + a - (b + 1)u + u²v, dv/dt = Dv∇²v + bu - u²v
+/// 一様な定常状態は(a, b/a)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Brusselator {
+    /// uの拡散係数
+    pub du: f32,
+    /// vの拡散係数
+    pub dv: f32,
+    /// uの供給率
+    pub a: f32,
+    /// uからvへの変換率
+    pub b: f32,
+}
+
+impl Default for Brusselator {
+    /// チューリング不安定になる(b > (1 + a√(Du/Dv))²)パラメーター
+    fn default() -> Brusselator {
+        Brusselator {
+            du: 2.0,
+            dv: 16.0,
+            a: 4.5,
+            b: 7.5,
+        }
+    }
+}
+
+impl ReactionDiffusion for Brusselator {
+    fn diffusion(&self) -> (f32, f32) {
+        (self.du, self.dv)
+    }
+
+    fn reaction<T: Real>(&self, u: T, v: T) -> (T, T) {
+        let (a, b) = (cast::<T>(self.a), cast::<T>(self.b));
+        let uuv = u * u * v;
+        (a - (b + T::one()) * u + uuv, b * u - uuv)
+    }
+
+    fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        noisy_uniform_state(shape, self.a, self.b / self.a, 0.1)
+    }
+
+    fn display_range(&self) -> (f32, f32) {
+        (0.0, 2.0 * self.a)
+    }
+}
+
+/// Schnakenbergモデル
+/// du/dt = Du∇²u + a - u + u²v, dv/dt = Dv∇²v + b - u²v
+/// 一様な定常状態は(a + b, b / (a + b)²)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Schnakenberg {
+    /// uの拡散係数
+    pub du: f32,
+    /// vの拡散係数
+    pub dv: f32,
+    /// uの供給率
+    pub a: f32,
+    /// vの供給率
+    pub b: f32,
+}
+
+impl Default for Schnakenberg {
+    /// 斑点ができるパラメーター
+    fn default() -> Schnakenberg {
+        Schnakenberg {
+            du: 1.0,
+            dv: 40.0,
+            a: 0.1,
+            b: 0.9,
+        }
+    }
+}
+
+impl ReactionDiffusion for Schnakenberg {
+    fn diffusion(&self) -> (f32, f32) {
+        (self.du, self.dv)
+    }
+
+    fn reaction<T: Real>(&self, u: T, v: T) -> (T, T) {
+        let (a, b) = (cast::<T>(self.a), cast::<T>(self.b));
+        let uuv = u * u * v;
+        (a - u + uuv, b - uuv)
+    }
+
+    fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        let u = self.a + self.b;
+        noisy_uniform_state(shape, u, self.b / (u * u), 0.01)
+    }
+
+    fn display_range(&self) -> (f32, f32) {
+        (0.0, 4.0 * (self.a + self.b))
+    }
+}
+
+/// Belousov-Zhabotinsky反応の2変数Oregonator(Tyson-Fifeの簡約版)
+/// du/dt = Du∇²u + (u - u² - f v (u - q) / (u + q)) / ε, dv/dt = Dv∇²v + u - v
+/// uはHBrO2、vは触媒の濃度にあたる。f > 1 + √2なら興奮性媒質になり、渦巻き波ができる
+/// (0.5 < f < 1 + √2では一様な状態が振動してしまう)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Oregonator {
+    /// uの拡散係数
+    pub du: f32,
+    /// vの拡散係数
+    pub dv: f32,
+    /// uとvの時間スケールの比ε
+    pub epsilon: f32,
+    /// 化学量論係数f
+    pub f: f32,
+    /// 速度定数の比q
+    pub q: f32,
+}
+
+impl Default for Oregonator {
+    /// 興奮性媒質として渦巻き波ができるパラメーター
+    fn default() -> Oregonator {
+        Oregonator {
+            du: 1.0,
+            dv: 0.0,
+            epsilon: 0.05,
+            f: 2.5,
+            q: 0.002,
+        }
+    }
+}
+
+impl ReactionDiffusion for Oregonator {
+    fn diffusion(&self) -> (f32, f32) {
+        (self.du, self.dv)
+    }
+
+    fn reaction<T: Real>(&self, u: T, v: T) -> (T, T) {
+        let (epsilon, f, q) = (cast::<T>(self.epsilon), cast::<T>(self.f), cast::<T>(self.q));
+        ((u - u * u - f * v * (u - q) / (u + q)) / epsilon, u - v)
+    }
+
+    fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        let rest = self.steady_state();
+        broken_wave_state(shape, (rest, rest), 0.8, 0.15)
+    }
+}
+
+impl Oregonator {
+    /// 一様な定常状態のu(vも同じ値になる)
+    /// u - u² - f u (u - q) / (u + q) = 0 の(q, 1)の範囲の解を二分法で求める
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::algorithm::reaction_diffusion::{Oregonator, ReactionDiffusion};
+    ///
+    /// let model = Oregonator::default();
+    /// let u = model.steady_state();
+    /// let (du, dv) = model.reaction(u, u);
+    /// assert!(du.abs() < 1e-3 && dv.abs() < 1e-6);
+    /// ```
+    pub fn steady_state(&self) -> f32 {
+        let g = |u: f32| u - u * u - self.f * u * (u - self.q) / (u + self.q);
+        let (mut low, mut high) = (self.q * 1.0001, 1.0);
+        for _ in 0..50 {
+            let middle = (low + high) / 2.0;
+            if g(low) * g(middle) <= 0.0 {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        low
+    }
+}
+
+/// Barkleyの興奮性媒質モデル
+/// du/dt = Du∇²u + u(1 - u)(u - (v + b)/a) / ε, dv/dt = Dv∇²v + u - v
+/// 計算が軽く、渦巻き波の研究によく使われる
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Barkley {
+    /// uの拡散係数
+    pub du: f32,
+    /// vの拡散係数
+    pub dv: f32,
+    /// パラメーターa
+    pub a: f32,
+    /// 興奮の閾値を決めるパラメーターb
+    pub b: f32,
+    /// uとvの時間スケールの比ε
+    pub epsilon: f32,
+}
+
+impl Default for Barkley {
+    /// 安定した渦巻き波ができるパラメーター
+    fn default() -> Barkley {
+        Barkley {
+            du: 1.0,
+            dv: 0.0,
+            a: 0.75,
+            b: 0.02,
+            epsilon: 0.02,
+        }
+    }
+}
+
+impl ReactionDiffusion for Barkley {
+    fn diffusion(&self) -> (f32, f32) {
+        (self.du, self.dv)
+    }
+
+    fn reaction<T: Real>(&self, u: T, v: T) -> (T, T) {
+        let (a, b, epsilon) = (cast::<T>(self.a), cast::<T>(self.b), cast::<T>(self.epsilon));
+        (u * (T::one() - u) * (u - (v + b) / a) / epsilon, u - v)
+    }
+
+    fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        broken_wave_state(shape, (0.0, 0.0), 1.0, self.a / 2.0)
+    }
+}
+
+/// このモジュールで使えるモデルのどれか
+/// プリセットのように、異なるモデルを同じ型で扱いたいときに使う
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Model {
+    /// Gray-Scottモデル
+    GrayScott(GrayScottParams),
+    /// FitzHugh-Nagumoモデル
+    FitzHughNagumo(FitzHughNagumo),
+    /// Brusselator
+    Brusselator(Brusselator),
+    /// Schnakenbergモデル
+    Schnakenberg(Schnakenberg),
+    /// Oregonator
+    Oregonator(Oregonator),
+    /// Barkleyモデル
+    Barkley(Barkley),
+}
+
+// `ReactionDiffusion::reaction`は型パラメーターを持ちトレイトオブジェクトにできないので、モデルごとに振り分ける
+impl ReactionDiffusion for Model {
+    fn diffusion(&self) -> (f32, f32) {
+        match *self {
+            Model::GrayScott(ref m) => m.diffusion(),
+            Model::FitzHughNagumo(ref m) => m.diffusion(),
+            Model::Brusselator(ref m) => m.diffusion(),
+            Model::Schnakenberg(ref m) => m.diffusion(),
+            Model::Oregonator(ref m) => m.diffusion(),
+            Model::Barkley(ref m) => m.diffusion(),
+        }
+    }
+
+    fn reaction<T: Real>(&self, u: T, v: T) -> (T, T) {
+        match *self {
+            Model::GrayScott(ref m) => m.reaction(u, v),
+            Model::FitzHughNagumo(ref m) => m.reaction(u, v),
+            Model::Brusselator(ref m) => m.reaction(u, v),
+            Model::Schnakenberg(ref m) => m.reaction(u, v),
+            Model::Oregonator(ref m) => m.reaction(u, v),
+            Model::Barkley(ref m) => m.reaction(u, v),
+        }
+    }
+
+    fn initial_state(&self, shape: (usize, usize)) -> (Matrix<f32>, Matrix<f32>) {
+        match *self {
+            Model::GrayScott(ref m) => m.initial_state(shape),
+            Model::FitzHughNagumo(ref m) => m.initial_state(shape),
+            Model::Brusselator(ref m) => m.initial_state(shape),
+            Model::Schnakenberg(ref m) => m.initial_state(shape),
+            Model::Oregonator(ref m) => m.initial_state(shape),
+            Model::Barkley(ref m) => m.initial_state(shape),
+        }
+    }
+
+    fn display_range(&self) -> (f32, f32) {
+        match *self {
+            Model::GrayScott(ref m) => m.display_range(),
+            Model::FitzHughNagumo(ref m) => m.display_range(),
+            Model::Brusselator(ref m) => m.display_range(),
+            Model::Schnakenberg(ref m) => m.display_range(),
+            Model::Oregonator(ref m) => m.display_range(),
+            Model::Barkley(ref m) => m.display_range(),
+        }
+    }
+}
+
+/// 名前のついたモデルと離散化の設定の組
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ModelPreset {
+    /// 名前
+    pub name: &'static str,
+    /// どんなパターンになるか
+    pub description: &'static str,
+    /// モデル
+    pub model: Model,
+    /// 離散化の設定
+    pub discretization: Discretization,
+}
+
+impl ModelPreset {
+    /// モデルのおすすめの初期状態から始まるシミュレーターを作る
+    ///
+    /// # Arguments
+    /// * `shape` - 格子の大きさ(行数, 列数)
+    pub fn simulator(&self, shape: (usize, usize)) -> Result<Simulator<Model>, failure::Error> {
+        Simulator::new(self.model, self.discretization, self.model.initial_state(shape))
+    }
+}
+
+/// 全てのモデルのプリセット
+/// Gray-Scottモデルのプリセット(`gray_scott::PRESETS`)も同じ名前で含む
+pub fn model_presets() -> Vec<ModelPreset> {
+    let mut presets = vec![
+        ModelPreset {
+            name: "fitzhugh-nagumo",
+            description: "FitzHugh-Nagumoモデルの迷路状の縞",
+            model: Model::FitzHughNagumo(FitzHughNagumo::default()),
+            discretization: Discretization::new(1.0, 0.02, 10),
+        },
+        ModelPreset {
+            name: "brusselator",
+            description: "Brusselatorの斑点",
+            model: Model::Brusselator(Brusselator::default()),
+            discretization: Discretization::new(1.0, 0.01, 20),
+        },
+        ModelPreset {
+            name: "schnakenberg",
+            description: "Schnakenbergモデルの斑点",
+            model: Model::Schnakenberg(Schnakenberg::default()),
+            discretization: Discretization::new(1.0, 0.005, 40),
+        },
+        ModelPreset {
+            name: "oregonator",
+            description: "BZ反応(Oregonator)の渦巻き波",
+            model: Model::Oregonator(Oregonator::default()),
+            discretization: Discretization::new(0.25, 0.001, 50),
+        },
+        ModelPreset {
+            name: "barkley",
+            description: "Barkleyモデルの渦巻き波",
+            model: Model::Barkley(Barkley::default()),
+            discretization: Discretization::new(0.5, 0.01, 20),
+        },
+    ];
+    presets.extend(gray_scott::PRESETS.iter().map(|p| {
+        let params = p.params();
+        ModelPreset {
+            name: p.name,
+            description: p.description,
+            model: Model::GrayScott(params),
+            discretization: params.discretization(),
+        }
+    }));
+    presets
+}
+
+/// 名前からモデルのプリセットを探す。大文字と小文字は区別しない
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::reaction_diffusion::{model_preset, Model};
+///
+/// assert!(model_preset("Barkley").is_some());
+/// match model_preset("spot").unwrap().model {
+///     Model::GrayScott(params) => assert_eq!((params.f, params.k), (0.035, 0.065)),
+///     _ => unreachable!(),
+/// }
+/// ```
+pub fn model_preset(name: &str) -> Option<ModelPreset> {
+    let name = name.to_lowercase();
+    model_presets().into_iter().find(|p| p.name == name)
+}
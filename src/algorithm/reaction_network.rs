@@ -0,0 +1,426 @@
+use algorithm::flow::Flow;
+use algorithm::reaction_diffusion::Discretization;
+use algorithm::stencil::{standard_layout, Integrator, StencilSolver};
+use failure;
+use ndarray::{Array, Array2, Array3};
+use ndarray_rand::RandomExt;
+use ndarray_rand::F32;
+use num::Complex;
+use rand::distributions::Range;
+use visualizer::matrix_visualizer::Matrix;
+
+// 初期状態に置く正方形の大きさ
+const SQUARE_SIZE: usize = 20;
+
+/// 化学種
+#[derive(Clone, Debug, PartialEq)]
+pub struct Species {
+    /// 名前。反応式の中で使うので、英字か`_`で始まり、英数字と`_`だけからなる
+    pub name: String,
+    /// 拡散係数
+    pub diffusion: f32,
+    /// 初期状態での一様な濃度
+    pub initial: f32,
+    /// 初期状態で中央の正方形に置く濃度
+    pub seed: f32,
+    /// 初期状態に入れるノイズの振幅
+    pub noise: f32,
+}
+
+impl Species {
+    /// 初期状態が一様に0の化学種を生成する
+    pub fn new(name: &str, diffusion: f32) -> Species {
+        Species {
+            name: name.to_string(),
+            diffusion: diffusion,
+            initial: 0.0,
+            seed: 0.0,
+            noise: 0.0,
+        }
+    }
+}
+
+/// 質量作用の法則に従う反応
+/// 反応の速さは、速度定数と各反応物の濃度を係数で累乗したものの積になる
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reaction {
+    /// 反応物(化学種の添字, 係数)
+    pub reactants: Vec<(usize, u32)>,
+    /// 生成物(化学種の添字, 係数)
+    pub products: Vec<(usize, u32)>,
+    /// 速度定数
+    pub rate: f32,
+}
+
+impl Reaction {
+    /// 濃度`concentrations`での反応の速さ
+    fn velocity(&self, concentrations: &[f32]) -> f32 {
+        self.reactants
+            .iter()
+            .fold(self.rate, |velocity, &(i, n)| velocity * concentrations[i].powi(n as i32))
+    }
+}
+
+/// 化学種と反応の一覧で記述した、任意の数の化学種の反応拡散系
+/// 拡散係数と反応式だけから反応項を組み立てるので、Rustのコードを書かずに新しい反応系を試せる。
+/// ファイルからは`format::reaction_network::open_network`で読み込める
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::gray_scott::GrayScottParams;
+/// use my_alife::algorithm::reaction_diffusion::ReactionDiffusion;
+/// use my_alife::algorithm::reaction_network::{ReactionNetwork, Species};
+///
+/// // Gray-Scottモデルを反応式で書く。供給は`-> U`、除去は`U ->`のように片側を空にする
+/// let (f, k) = (0.04, 0.06);
+/// let mut network = ReactionNetwork::new();
+/// network.add_species(Species::new("U", 2e-5)).unwrap();
+/// network.add_species(Species::new("V", 1e-5)).unwrap();
+/// network.add_reaction("U + 2V -> 3V", 1.0).unwrap();
+/// network.add_reaction("-> U", f).unwrap();
+/// network.add_reaction("U ->", f).unwrap();
+/// network.add_reaction("V ->", f + k).unwrap();
+///
+/// let mut rates = [0.0; 2];
+/// network.reaction(&[0.5, 0.25], &mut rates);
+/// let (du, dv) = GrayScottParams::new(f, k).reaction(0.5, 0.25);
+/// assert!((rates[0] - du).abs() < 1e-6 && (rates[1] - dv).abs() < 1e-6);
+///
+/// // 知らない化学種を含む反応式はエラーになる
+/// assert!(network.add_reaction("U + W -> 2W", 1.0).is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReactionNetwork {
+    /// 化学種。添字が状態の3番目の軸の添字になる
+    pub species: Vec<Species>,
+    /// 反応
+    pub reactions: Vec<Reaction>,
+}
+
+impl ReactionNetwork {
+    /// 化学種も反応もないReactionNetworkインスタンスを生成する
+    pub fn new() -> ReactionNetwork {
+        Default::default()
+    }
+
+    /// 化学種を追加し、その添字を返す。名前が正しくないか、すでにある場合はエラーになる
+    pub fn add_species(&mut self, species: Species) -> Result<usize, failure::Error> {
+        if !is_valid_name(&species.name) {
+            return Err(failure::err_msg(format!("invalid species name: {:?}", species.name)));
+        }
+        if self.index(&species.name).is_some() {
+            return Err(failure::err_msg(format!("duplicate species: {}", species.name)));
+        }
+        self.species.push(species);
+        Ok(self.species.len() - 1)
+    }
+
+    /// `U + 2V -> 3V`のような反応式の反応を追加する
+    /// 係数は化学種の名前の前に書き、省略すると1になる。何もない側は空にするか`0`と書く
+    ///
+    /// # Arguments
+    /// * `equation` - 反応式
+    /// * `rate` - 速度定数
+    pub fn add_reaction(&mut self, equation: &str, rate: f32) -> Result<(), failure::Error> {
+        let error = |message: &str| failure::err_msg(format!("{}: {:?}", message, equation));
+        let mut sides = equation.split("->");
+        let (left, right) = match (sides.next(), sides.next(), sides.next()) {
+            (Some(left), Some(right), None) => (left, right),
+            _ => return Err(error("reaction needs exactly one '->'")),
+        };
+        if !(rate >= 0.0 && rate.is_finite()) {
+            return Err(error("rate must be a finite non-negative number"));
+        }
+        let reaction = Reaction {
+            reactants: self.parse_side(left).map_err(|e| error(&e))?,
+            products: self.parse_side(right).map_err(|e| error(&e))?,
+            rate: rate,
+        };
+        self.reactions.push(reaction);
+        Ok(())
+    }
+
+    /// 反応式の片側を(化学種の添字, 係数)のリストにする。同じ化学種は係数をまとめる
+    fn parse_side(&self, side: &str) -> Result<Vec<(usize, u32)>, String> {
+        let side = side.trim();
+        let mut terms: Vec<(usize, u32)> = Vec::new();
+        if side.is_empty() || side == "0" {
+            return Ok(terms);
+        }
+        for term in side.split('+') {
+            let term = term.trim();
+            let digits = term.chars().take_while(|c| c.is_ascii_digit()).count();
+            let coefficient = if digits == 0 {
+                1
+            } else {
+                term[..digits].parse::<u32>().map_err(|_| format!("invalid coefficient {:?}", term))?
+            };
+            let name = term[digits..].trim();
+            let index = self
+                .index(name)
+                .ok_or_else(|| format!("unknown species {:?}", name))?;
+            if coefficient == 0 {
+                continue;
+            }
+            match terms.iter().position(|&(i, _)| i == index) {
+                Some(p) => terms[p].1 += coefficient,
+                None => terms.push((index, coefficient)),
+            }
+        }
+        Ok(terms)
+    }
+
+    /// 名前が`name`の化学種の添字
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.species.iter().position(|s| s.name == name)
+    }
+
+    /// 化学種の名前が正しく重複していないか、拡散係数と速度定数が有限で負でないか、反応の添字が範囲内かを調べる
+    /// フィールドを直接書き換えた後に使う
+    pub fn validate(&self) -> Result<(), failure::Error> {
+        for (i, species) in self.species.iter().enumerate() {
+            if !is_valid_name(&species.name) {
+                return Err(failure::err_msg(format!("invalid species name: {:?}", species.name)));
+            }
+            if self.index(&species.name) != Some(i) {
+                return Err(failure::err_msg(format!("duplicate species: {}", species.name)));
+            }
+            if !(species.diffusion >= 0.0 && species.diffusion.is_finite()) {
+                return Err(failure::err_msg(format!(
+                    "diffusion of {} must be a finite non-negative number, found {}",
+                    species.name, species.diffusion
+                )));
+            }
+        }
+        for (r, reaction) in self.reactions.iter().enumerate() {
+            let mut terms = reaction.reactants.iter().chain(&reaction.products);
+            if let Some(&(i, _)) = terms.find(|&&(i, _)| i >= self.species.len()) {
+                return Err(failure::err_msg(format!(
+                    "reaction {} refers to species {}, but there are only {}",
+                    r,
+                    i,
+                    self.species.len()
+                )));
+            }
+            if !(reaction.rate >= 0.0 && reaction.rate.is_finite()) {
+                return Err(failure::err_msg(format!(
+                    "rate of reaction {} must be a finite non-negative number, found {}",
+                    r, reaction.rate
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// 各化学種の濃度`concentrations`での反応項(dc/dt)を`rates`に書き込む
+    /// どちらも長さは化学種の数で、違う場合はpanicする
+    pub fn reaction(&self, concentrations: &[f32], rates: &mut [f32]) {
+        assert_eq!(concentrations.len(), self.species.len(), "concentrations length mismatch");
+        assert_eq!(rates.len(), self.species.len(), "rates length mismatch");
+        for rate in rates.iter_mut() {
+            *rate = 0.0;
+        }
+        for reaction in &self.reactions {
+            let velocity = reaction.velocity(concentrations);
+            for &(i, n) in &reaction.reactants {
+                rates[i] -= n as f32 * velocity;
+            }
+            for &(i, n) in &reaction.products {
+                rates[i] += n as f32 * velocity;
+            }
+        }
+    }
+
+    /// 各化学種の`initial`で埋め、中央に`seed`の正方形を置き、`noise`の振幅のノイズを入れた初期状態
+    /// 形は(行数, 列数, 化学種の数)になる
+    ///
+    /// # Arguments
+    /// * `shape` - 格子の大きさ(行数, 列数)
+    pub fn initial_state(&self, shape: (usize, usize)) -> Array3<f32> {
+        let (rows, cols) = shape;
+        let n = self.species.len();
+        let mut state = Array3::<f32>::zeros((rows, cols, n));
+        let row_range = (rows / 2).saturating_sub(SQUARE_SIZE / 2)..(rows / 2 + SQUARE_SIZE / 2).min(rows);
+        let col_range = (cols / 2).saturating_sub(SQUARE_SIZE / 2)..(cols / 2 + SQUARE_SIZE / 2).min(cols);
+        for (s, species) in self.species.iter().enumerate() {
+            state.slice_mut(s![.., .., s]).fill(species.initial);
+            state
+                .slice_mut(s![row_range.clone(), col_range.clone(), s])
+                .fill(species.seed);
+            if species.noise != 0.0 {
+                let noise = Array::random(shape, F32(Range::new(0., 1.))) * species.noise;
+                let mut layer = state.slice_mut(s![.., .., s]);
+                layer += &noise;
+            }
+        }
+        state
+    }
+}
+
+/// 化学種の名前として使えるか。英字か`_`で始まり、英数字と`_`だけからなる
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false,
+    }
+}
+
+/// `ReactionNetwork`のシミュレーター
+/// 状態は(行, 列, 化学種)の3次元配列で、`StencilSolver`で時間発展させる。
+/// 時間積分の方法は`discretization.integrator`に従い、障害物のマスクや流れも`GrayScott`と同じように使える。
+/// ディリクレ境界では、格子の外側の濃度を各化学種の`initial`に固定する(`Boundary::Dirichlet`の値は使わない)
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::reaction_diffusion::Discretization;
+/// use my_alife::algorithm::reaction_network::{NetworkSimulator, ReactionNetwork, Species};
+/// use my_alife::algorithm::stencil::Integrator;
+///
+/// // A + B -> C で、AとBが同じだけ減りCが増える
+/// let mut network = ReactionNetwork::new();
+/// for name in &["A", "B", "C"] {
+///     network.add_species(Species { initial: 1.0, seed: 1.0, ..Species::new(name, 0.1) }).unwrap();
+/// }
+/// network.add_reaction("A + B -> C", 0.5).unwrap();
+///
+/// let state = network.initial_state((16, 16));
+/// let mut discretization = Discretization::new(1.0, 0.1, 10);
+/// discretization.integrator = Integrator::RungeKutta4;
+/// let mut simulator = NetworkSimulator::new(network, discretization, state).unwrap();
+/// assert!(simulator.check_stability().is_ok());
+/// simulator.step();
+/// let a = simulator.species("A").unwrap();
+/// let c = simulator.species("C").unwrap();
+/// assert!(a[[8, 8]] < 1.0 && (a[[8, 8]] + c[[8, 8]] - 2.0).abs() < 1e-5);
+/// assert!(simulator.species("D").is_none());
+/// ```
+pub struct NetworkSimulator {
+    network: ReactionNetwork,
+    discretization: Discretization,
+    // 各化学種の拡散係数
+    diffusions: Vec<f32>,
+    solver: StencilSolver<f32, Array3<f32>>,
+}
+
+impl NetworkSimulator {
+    /// 初期状態を指定して生成する
+    /// ネットワークが正しくない場合や、状態の3番目の軸の長さが化学種の数と違う場合はエラーになる
+    ///
+    /// # Arguments
+    /// * `network` - 反応ネットワーク
+    /// * `discretization` - 離散化の設定
+    /// * `state` - 初期状態(行数, 列数, 化学種の数)
+    pub fn new(
+        network: ReactionNetwork,
+        discretization: Discretization,
+        state: Array3<f32>,
+    ) -> Result<NetworkSimulator, failure::Error> {
+        network.validate()?;
+        if state.dim().2 != network.species.len() {
+            return Err(failure::err_msg(format!(
+                "state has {} species, but the network has {}",
+                state.dim().2,
+                network.species.len()
+            )));
+        }
+        let mut solver = StencilSolver::new(state);
+        solver.set_dirichlet_values(Some(network.species.iter().map(|s| s.initial).collect()))?;
+        Ok(NetworkSimulator {
+            diffusions: network.species.iter().map(|s| s.diffusion).collect(),
+            network: network,
+            discretization: discretization,
+            solver: solver,
+        })
+    }
+
+    /// 反応ネットワーク
+    pub fn network(&self) -> &ReactionNetwork {
+        &self.network
+    }
+
+    /// 離散化の設定
+    pub fn discretization(&self) -> &Discretization {
+        &self.discretization
+    }
+
+    /// 離散化の設定を変更する
+    pub fn discretization_mut(&mut self) -> &mut Discretization {
+        &mut self.discretization
+    }
+
+    /// 現在の状態(行, 列, 化学種)
+    pub fn state(&self) -> &Array3<f32> {
+        self.solver.state()
+    }
+
+    /// 現在の状態(行, 列, 化学種)を取り出す
+    pub fn into_state(self) -> Array3<f32> {
+        self.solver.into_state()
+    }
+
+    /// 名前が`name`の化学種の濃度。`MatrixVisualizer`でそのまま描画できる
+    pub fn species(&self, name: &str) -> Option<Matrix<f32>> {
+        self.network
+            .index(name)
+            .map(|s| standard_layout(self.state().slice(s![.., .., s]).to_owned()))
+    }
+
+    /// 障害物のマスク
+    pub fn mask(&self) -> Option<&Array2<bool>> {
+        self.solver.mask()
+    }
+
+    /// 障害物のマスクを変更する。`None`なら障害物はない。マスクの大きさが格子と違う場合はエラーになる
+    pub fn set_mask(&mut self, mask: Option<Array2<bool>>) -> Result<(), failure::Error> {
+        self.solver.set_mask(mask)
+    }
+
+    /// 流れ(移流)
+    pub fn flow(&self) -> Option<&Flow> {
+        self.solver.flow()
+    }
+
+    /// 流れを変更する。`None`なら流れはない。速度場の大きさが格子と違う場合はエラーになる
+    pub fn set_flow(&mut self, flow: Option<Flow>) -> Result<(), failure::Error> {
+        self.solver.set_flow(flow)
+    }
+
+    /// 拡散と流れが安定に計算できるかを調べる。反応項は考えない
+    /// 5点差分のラプラシアンの固有値は-8 / dx^2以上なので、各化学種で-8 * D * dt / dx^2が
+    /// 時間積分の方法の安定領域に入っている必要がある。適応刻み幅では拡散の条件は調べない
+    pub fn check_stability(&self) -> Result<(), failure::Error> {
+        let d = &self.discretization;
+        let adaptive = match d.integrator {
+            Integrator::Adaptive(_) => true,
+            _ => false,
+        };
+        for species in self.network.species.iter().filter(|_| !adaptive) {
+            let ratio = species.diffusion * d.dt / (d.dx * d.dx);
+            // NaNも不安定とみなす
+            if !(d.integrator.amplification(Complex::new(-8.0 * ratio, 0.0)) <= 1.0 + 1e-4) {
+                return Err(failure::err_msg(format!(
+                    "dt = {} is too large for {:?}: D * dt / dx^2 = {} of {}",
+                    d.dt, d.integrator, ratio, species.name
+                )));
+            }
+        }
+        let diffusion = self.diffusions.iter().fold(0.0f32, |m, &d| m.max(d));
+        self.solver.check_flow(diffusion, d.dx, d.dt)
+    }
+
+    /// `discretization.substeps`ステップだけ時間を進める
+    /// 書き込み先のバッファは使い回すので、新しい配列は確保しない
+    pub fn step(&mut self) {
+        let d = self.discretization;
+        self.solver.set_boundary(d.boundary);
+        self.solver.set_integrator(d.integrator);
+        let (network, diffusions) = (&self.network, &self.diffusions);
+        for _ in 0..d.substeps {
+            self.solver.step_fields(d.dx, d.dt, |_, concentrations, diffusion, reaction| {
+                diffusion.copy_from_slice(diffusions);
+                network.reaction(concentrations, reaction);
+            });
+        }
+    }
+}
@@ -0,0 +1,502 @@
+use algorithm::gray_scott::GrayScottParams;
+use algorithm::reaction_diffusion::{Discretization, ReactionDiffusion};
+use algorithm::stencil::{standard_layout, Boundary};
+use failure;
+use ndarray::Array2;
+use num::Complex;
+use rayon::prelude::*;
+use std::f64::consts::PI;
+use std::mem;
+use visualizer::matrix_visualizer::Matrix;
+
+/// 1次元の高速フーリエ変換(FFT)
+///
+/// 長さが2の累乗ならCooley-Tukey法、それ以外はBluestein法で計算する。どちらもO(n log n)で、
+/// 回転因子は生成するときに計算しておく
+///
+/// # Example
+/// ```
+/// extern crate num;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::spectral::Fft;
+/// use num::Complex;
+///
+/// // 長さ6(2の累乗でない)の定数列は0番目の成分だけが残る
+/// let fft = Fft::new(6);
+/// let mut data = vec![Complex::new(1.0, 0.0); 6];
+/// fft.forward(&mut data);
+/// assert!((data[0].re - 6.0).abs() < 1e-5);
+/// assert!(data[1..].iter().all(|c| c.norm() < 1e-5));
+///
+/// // 逆変換は1/nを掛けないので、元に戻すにはnで割る
+/// fft.inverse(&mut data);
+/// assert!(data.iter().all(|c| (c.re / 6.0 - 1.0).abs() < 1e-5));
+/// ```
+pub struct Fft {
+    len: usize,
+    // 長さが2の累乗のときの回転因子exp(-2πik/len) (k < len/2)
+    twiddles: Vec<Complex<f32>>,
+    bluestein: Option<Box<Bluestein>>,
+}
+
+// Bluestein法で任意の長さのDFTを2の累乗の長さの畳み込みに直すためのデータ
+struct Bluestein {
+    // チャープexp(-πik²/len)
+    chirp: Vec<Complex<f32>>,
+    // 畳み込みの相手(チャープの複素共役)をFFTしたもの
+    kernel: Vec<Complex<f32>>,
+    fft: Fft,
+}
+
+impl Fft {
+    /// 長さ`len`のFftインスタンスを生成する
+    pub fn new(len: usize) -> Fft {
+        if len.is_power_of_two() || len <= 1 {
+            let twiddles = (0..len / 2)
+                .map(|k| {
+                    let angle = -2.0 * PI * k as f64 / len as f64;
+                    Complex::new(angle.cos() as f32, angle.sin() as f32)
+                })
+                .collect();
+            return Fft {
+                len: len,
+                twiddles: twiddles,
+                bluestein: None,
+            };
+        }
+
+        let padded = (2 * len - 1).next_power_of_two();
+        let fft = Fft::new(padded);
+        let chirp = (0..len)
+            .map(|k| {
+                // k²は大きくなるので、2lenで割った余りにしてから角度にする
+                let k2 = (k as u64 * k as u64) % (2 * len as u64);
+                let angle = -PI * k2 as f64 / len as f64;
+                Complex::new(angle.cos() as f32, angle.sin() as f32)
+            })
+            .collect::<Vec<_>>();
+        let mut kernel = vec![Complex::new(0.0, 0.0); padded];
+        kernel[0] = chirp[0].conj();
+        for k in 1..len {
+            kernel[k] = chirp[k].conj();
+            kernel[padded - k] = chirp[k].conj();
+        }
+        fft.forward(&mut kernel);
+        Fft {
+            len: len,
+            twiddles: Vec::new(),
+            bluestein: Some(Box::new(Bluestein {
+                chirp: chirp,
+                kernel: kernel,
+                fft: fft,
+            })),
+        }
+    }
+
+    /// 変換する列の長さ
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 長さが0かどうか
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// `data`をその場で離散フーリエ変換する
+    /// X_k = Σ x_n exp(-2πikn/len)
+    pub fn forward(&self, data: &mut [Complex<f32>]) {
+        assert_eq!(data.len(), self.len, "data length must match the FFT length");
+        match self.bluestein {
+            None => self.radix2(data, false),
+            Some(ref bluestein) => bluestein.forward(data),
+        }
+    }
+
+    /// `data`をその場で逆離散フーリエ変換する。1/lenは掛けない
+    /// x_n = Σ X_k exp(2πikn/len)
+    pub fn inverse(&self, data: &mut [Complex<f32>]) {
+        assert_eq!(data.len(), self.len, "data length must match the FFT length");
+        match self.bluestein {
+            None => self.radix2(data, true),
+            Some(ref bluestein) => {
+                // 逆変換は複素共役をとって順変換したものの複素共役になる
+                for e in data.iter_mut() {
+                    *e = e.conj();
+                }
+                bluestein.forward(data);
+                for e in data.iter_mut() {
+                    *e = e.conj();
+                }
+            }
+        }
+    }
+
+    fn radix2(&self, data: &mut [Complex<f32>], inverse: bool) {
+        let n = data.len();
+        // ビット反転の順に並べ替える
+        let mut j = 0;
+        for i in 1..n {
+            let mut bit = n >> 1;
+            while j & bit != 0 {
+                j ^= bit;
+                bit >>= 1;
+            }
+            j |= bit;
+            if i < j {
+                data.swap(i, j);
+            }
+        }
+        let mut size = 2;
+        while size <= n {
+            let half = size / 2;
+            let stride = n / size;
+            for block in data.chunks_mut(size) {
+                for k in 0..half {
+                    let w = self.twiddles[k * stride];
+                    let w = if inverse { w.conj() } else { w };
+                    let a = block[k];
+                    let b = block[k + half] * w;
+                    block[k] = a + b;
+                    block[k + half] = a - b;
+                }
+            }
+            size *= 2;
+        }
+    }
+}
+
+impl Bluestein {
+    fn forward(&self, data: &mut [Complex<f32>]) {
+        let padded = self.fft.len();
+        let mut work = vec![Complex::new(0.0, 0.0); padded];
+        for ((w, &x), &c) in work.iter_mut().zip(data.iter()).zip(&self.chirp) {
+            *w = x * c;
+        }
+        self.fft.forward(&mut work);
+        for (w, &k) in work.iter_mut().zip(&self.kernel) {
+            *w = *w * k;
+        }
+        self.fft.inverse(&mut work);
+        let scale = 1.0 / padded as f32;
+        for ((x, &w), &c) in data.iter_mut().zip(&work).zip(&self.chirp) {
+            *x = w * c * scale;
+        }
+    }
+}
+
+/// フーリエ空間で拡散を厳密に解き、反応項は各セルで別に解く(Strangの作用素分割)反応拡散系のソルバー
+///
+/// 拡散は波数kの成分にexp(-D|k|²t)を掛けるだけなので、時間の刻み幅に拡散による安定性の制限がない。
+/// 大きな格子(2048x2048など)で`StencilSolver`より大きな刻み幅を使いたいときに向いている。
+/// 周期境界のみ対応し、障害物のマスクは使えない。状態は`f32`だけ
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::spectral::SpectralSolver;
+/// use my_alife::algorithm::stencil::{Integrator, StencilSolver};
+/// use ndarray::Array2;
+///
+/// // 滑らかな山なら、5点差分(時間は4次のRunge-Kutta法)の結果とほぼ一致する
+/// let bump = Array2::from_shape_fn((32, 24), |(i, j)| {
+///     let (y, x) = (i as f32 - 16.0, j as f32 - 12.0);
+///     (-(x * x + y * y) / 32.0).exp()
+/// });
+/// let state = (bump.clone(), bump.mapv(|e| 1.0 - e));
+/// let reaction = |u: f32, v: f32| (-u * v, u * v);
+///
+/// let mut spectral = SpectralSolver::new(state.clone());
+/// spectral.step((1.0, 0.5), 1.0, 0.05, 100, reaction);
+/// let mut stencil = StencilSolver::new(state);
+/// stencil.set_integrator(Integrator::RungeKutta4);
+/// for _ in 0..100 {
+///     stencil.step((1.0, 0.5), 1.0, 0.05, reaction);
+/// }
+///
+/// let difference = |a: &Array2<f32>, b: &Array2<f32>| {
+///     a.iter().zip(b.iter()).map(|(p, q)| (p - q).abs()).fold(0.0, f32::max)
+/// };
+/// assert!(difference(&spectral.state().0, &stencil.state().0) < 5e-3);
+/// assert!(difference(&spectral.state().1, &stencil.state().1) < 5e-3);
+/// ```
+pub struct SpectralSolver {
+    state: (Matrix<f32>, Matrix<f32>),
+    workspace: Workspace,
+}
+
+// FFTの設定と作業用の配列
+struct Workspace {
+    row_fft: Fft,
+    column_fft: Fft,
+    // 行ごとにFFTした値(行数 x 列数)。途中で(列数 x 行数)の配列としても使う
+    spectrum: Vec<Complex<f32>>,
+    // `spectrum`を転置したもの(列数 x 行数)。途中で(行数 x 列数)の配列としても使う
+    transposed: Vec<Complex<f32>>,
+}
+
+impl SpectralSolver {
+    /// SpectralSolverインスタンスを生成する
+    ///
+    /// # Arguments
+    /// * `state` - 初期状態(u, v)。uとvは同じ大きさでなければならない。任意の大きさで良いが、
+    ///   行数と列数が2の累乗のときが一番速い
+    pub fn new(state: (Matrix<f32>, Matrix<f32>)) -> SpectralSolver {
+        assert_eq!(state.0.dim(), state.1.dim(), "u and v must have the same shape");
+        let (rows, cols) = state.0.dim();
+        SpectralSolver {
+            state: (standard_layout(state.0), standard_layout(state.1)),
+            workspace: Workspace {
+                row_fft: Fft::new(cols),
+                column_fft: Fft::new(rows),
+                spectrum: vec![Complex::new(0.0, 0.0); rows * cols],
+                transposed: vec![Complex::new(0.0, 0.0); rows * cols],
+            },
+        }
+    }
+
+    /// 現在の状態(u, v)
+    pub fn state(&self) -> &(Matrix<f32>, Matrix<f32>) {
+        &self.state
+    }
+
+    /// 現在の状態を取り出す
+    pub fn into_state(self) -> (Matrix<f32>, Matrix<f32>) {
+        self.state
+    }
+
+    /// Strangの作用素分割で、時間を`dt`ずつ`steps`回進める
+    /// 拡散(dt/2)→反応(dt)→拡散(dt/2)を繰り返すが、続けて現れる拡散はまとめて1回で計算する。
+    /// 反応項は各セルで4次のRunge-Kutta法で解く
+    ///
+    /// # Arguments
+    /// * `diffusion` - uとvの拡散係数
+    /// * `dx` - 格子の間隔
+    /// * `dt` - 時間の刻み幅
+    /// * `steps` - 進めるステップ数
+    /// * `reaction` - (u, v)を受け取り、反応項(du/dt, dv/dt)を返す関数
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ndarray;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::algorithm::spectral::SpectralSolver;
+    /// use ndarray::Array2;
+    /// use std::f32::consts::PI;
+    ///
+    /// // 波数kの正弦波は拡散でexp(-Dk²t)倍になる。刻み幅を大きくしても厳密に一致する
+    /// let (rows, cols, dx) = (16, 32, 0.5);
+    /// let k = 2.0 * PI * 3.0 / (cols as f32 * dx);
+    /// let wave = Array2::from_shape_fn((rows, cols), |(_, j)| (k * j as f32 * dx).sin());
+    /// let mut solver = SpectralSolver::new((wave.clone(), wave.clone()));
+    /// solver.step((0.1, 0.0), dx, 2.0, 5, |_, _| (0.0, 0.0));
+    /// let expected = wave.mapv(|e| e * (-0.1 * k * k * 10.0).exp());
+    /// for (a, b) in solver.state().0.iter().zip(expected.iter()) {
+    ///     assert!((a - b).abs() < 1e-5);
+    /// }
+    /// // 拡散係数が0なら変化しない
+    /// for (a, b) in solver.state().1.iter().zip(wave.iter()) {
+    ///     assert!((a - b).abs() < 1e-5);
+    /// }
+    /// ```
+    pub fn step<R>(&mut self, diffusion: (f32, f32), dx: f32, dt: f32, steps: usize, reaction: R)
+    where
+        R: Fn(f32, f32) -> (f32, f32) + Sync,
+    {
+        if steps == 0 || self.state.0.is_empty() {
+            return;
+        }
+        self.diffuse(diffusion, dx, dt / 2.0);
+        for step in 0..steps {
+            react(&mut self.state, dt, &reaction);
+            self.diffuse(diffusion, dx, if step + 1 == steps { dt / 2.0 } else { dt });
+        }
+    }
+
+    fn diffuse(&mut self, diffusion: (f32, f32), dx: f32, time: f32) {
+        self.workspace.diffuse(&mut self.state, diffusion, dx, time);
+    }
+
+}
+
+/// 各セルで反応項だけの方程式を4次のRunge-Kutta法で`dt`だけ進める。作用素分割をするソルバーで使う
+pub(crate) fn react<R>(state: &mut (Matrix<f32>, Matrix<f32>), dt: f32, reaction: &R)
+where
+    R: Fn(f32, f32) -> (f32, f32) + Sync,
+{
+    let u = state.0.as_slice_mut().unwrap();
+    let v = state.1.as_slice_mut().unwrap();
+    u.par_iter_mut().zip(v.par_iter_mut()).for_each(|(u, v)| {
+        let (u0, v0) = (*u, *v);
+        let k1 = reaction(u0, v0);
+        let k2 = reaction(u0 + dt / 2.0 * k1.0, v0 + dt / 2.0 * k1.1);
+        let k3 = reaction(u0 + dt / 2.0 * k2.0, v0 + dt / 2.0 * k2.1);
+        let k4 = reaction(u0 + dt * k3.0, v0 + dt * k3.1);
+        *u = u0 + dt / 6.0 * (k1.0 + 2.0 * k2.0 + 2.0 * k3.0 + k4.0);
+        *v = v0 + dt / 6.0 * (k1.1 + 2.0 * k2.1 + 2.0 * k3.1 + k4.1);
+    });
+}
+
+impl Workspace {
+    /// 拡散方程式 du/dt = Du∇²u, dv/dt = Dv∇²v を時間`time`だけフーリエ空間で厳密に解く
+    ///
+    /// uとvはどちらも実数なので、z = u + ivとして1回の複素FFTでまとめて変換する。
+    /// Zの波数kの成分とZ(-k)の複素共役から、UとVを別々に取り出せる
+    fn diffuse(&mut self, state: &mut (Matrix<f32>, Matrix<f32>), diffusion: (f32, f32), dx: f32, time: f32) {
+        if diffusion.0 * time == 0.0 && diffusion.1 * time == 0.0 {
+            return;
+        }
+        let (rows, cols) = state.0.dim();
+        let u = state.0.as_slice_mut().unwrap();
+        let v = state.1.as_slice_mut().unwrap();
+        let row_fft = &self.row_fft;
+        let column_fft = &self.column_fft;
+
+        self.spectrum
+            .par_chunks_mut(cols)
+            .zip(u.par_chunks(cols).zip(v.par_chunks(cols)))
+            .for_each(|(spectrum, (u, v))| {
+                for ((s, &u), &v) in spectrum.iter_mut().zip(u).zip(v) {
+                    *s = Complex::new(u, v);
+                }
+                row_fft.forward(spectrum);
+            });
+        transpose(&self.spectrum, &mut self.transposed, rows, cols);
+        self.transposed
+            .par_chunks_mut(rows)
+            .for_each(|column| column_fft.forward(column));
+
+        // exp(-D(kx² + ky²)t) = exp(-D kx² t) exp(-D ky² t) なので軸ごとに計算しておく
+        let decay_u = (decay(cols, dx, diffusion.0 * time), decay(rows, dx, diffusion.0 * time));
+        let decay_v = (decay(cols, dx, diffusion.1 * time), decay(rows, dx, diffusion.1 * time));
+        let scale = 1.0 / (rows * cols) as f32;
+        // U(k) = (Z(k) + Z(-k)*) / 2, V(k) = (Z(k) - Z(-k)*) / 2i に倍率を掛けてから u + iv の形に戻す
+        // `spectrum`は(列数 x 行数)の配列として使う
+        {
+            let transposed = &self.transposed;
+            self.spectrum
+                .par_chunks_mut(rows)
+                .enumerate()
+                .for_each(|(j, column)| {
+                    let negative_j = (cols - j) % cols;
+                    for (i, c) in column.iter_mut().enumerate() {
+                        let negative_i = (rows - i) % rows;
+                        let z = transposed[j * rows + i];
+                        let conjugate = transposed[negative_j * rows + negative_i].conj();
+                        let mu = decay_u.0[j] * decay_u.1[i];
+                        let mv = decay_v.0[j] * decay_v.1[i];
+                        *c = (z * (mu + mv) + conjugate * (mu - mv)) * (scale / 2.0);
+                    }
+                    column_fft.inverse(column);
+                });
+        }
+
+        transpose(&self.spectrum, &mut self.transposed, cols, rows);
+        self.transposed
+            .par_chunks_mut(cols)
+            .zip(u.par_chunks_mut(cols).zip(v.par_chunks_mut(cols)))
+            .for_each(|(spectrum, (u, v))| {
+                row_fft.inverse(spectrum);
+                for ((u, v), s) in u.iter_mut().zip(v.iter_mut()).zip(spectrum.iter()) {
+                    *u = s.re;
+                    *v = s.im;
+                }
+            });
+    }
+}
+
+/// 長さ`len`、間隔`dx`の周期的な軸で、各波数の成分が拡散で減衰する倍率exp(-k² D t)
+fn decay(len: usize, dx: f32, diffusion_time: f32) -> Vec<f32> {
+    let length = len as f64 * dx as f64;
+    (0..len)
+        .map(|m| {
+            // len/2より大きい添字は負の波数
+            let m = if m <= len / 2 { m as f64 } else { m as f64 - len as f64 };
+            let k = 2.0 * PI * m / length;
+            (-k * k * diffusion_time as f64).exp() as f32
+        })
+        .collect()
+}
+
+/// 行数`rows`、列数`cols`の`from`を転置して`to`に書き込む
+fn transpose(from: &[Complex<f32>], to: &mut [Complex<f32>], rows: usize, cols: usize) {
+    to.par_chunks_mut(rows).enumerate().for_each(|(j, column)| {
+        for (i, c) in column.iter_mut().enumerate() {
+            *c = from[i * cols + j];
+        }
+    });
+}
+
+/// `model`に従って`solver`を`discretization.substeps`ステップだけ進める
+/// `discretization.integrator`は使わず、反応項は常に4次のRunge-Kutta法で解く
+///
+/// 周期境界以外ではエラーになる
+pub fn step_model<M: ReactionDiffusion>(
+    solver: &mut SpectralSolver,
+    model: &M,
+    discretization: &Discretization,
+) -> Result<(), failure::Error> {
+    if discretization.boundary != Boundary::Periodic {
+        return Err(failure::err_msg(format!(
+            "spectral solver supports only periodic boundaries: {:?}",
+            discretization.boundary
+        )));
+    }
+    solver.step(
+        model.diffusion(),
+        discretization.dx,
+        discretization.dt,
+        discretization.substeps,
+        |u, v| model.reaction(u, v),
+    );
+    Ok(())
+}
+
+/// `gray_scott::gray_scott`と同じように、`params`に従ってGray-Scottモデルの時間発展を`params.substeps`ステップ計算する
+/// 呼び出すたびにFFTの準備をするので、繰り返し呼ぶ場合は`SpectralSolver`と`step_model`を使う方が良い
+///
+/// # Arguments
+/// * `uv` - 現在の状態(u, v)。任意の大きさの長方形で良い
+/// * `params` - パラメーター。`boundary`は`Boundary::Periodic`でなければならない
+pub fn gray_scott(uv: &mut (Matrix<f32>, Matrix<f32>), params: &GrayScottParams) -> Result<(), failure::Error> {
+    let empty = (Array2::zeros((0, 0)), Array2::zeros((0, 0)));
+    let mut solver = SpectralSolver::new(mem::replace(uv, empty));
+    let result = step_model(&mut solver, params, &params.discretization());
+    *uv = solver.into_state();
+    result
+}
+
+/// `gray_scott::laplacian`の代わりに使える、拡散をフーリエ空間で解くバージョン
+///
+/// # Arguments
+/// * `uv` - 拡散するもとのやつ
+/// * `f` - uの供給率
+/// * `k` - vの除去率
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::gray_scott::{GrayScott, GrayScottParams};
+/// use my_alife::algorithm::spectral;
+///
+/// // 5点差分のラプラシアンで計算した結果と近い値になる
+/// // (初期状態の正方形の角のような鋭い部分は、差分の誤差で少しずれる)
+/// let mut stencil = GrayScott::new(GrayScottParams::new(0.04, 0.06), (64, 64));
+/// let mut spectral = stencil.state().clone();
+/// for _ in 0..10 {
+///     stencil.step();
+///     spectral::laplacian(&mut spectral, 0.04, 0.06);
+/// }
+/// let mean = |m: &ndarray::Array2<f32>| m.scalar_sum() / m.len() as f32;
+/// assert!((mean(&stencil.state().0) - mean(&spectral.0)).abs() < 1e-2);
+/// assert!((mean(&stencil.state().1) - mean(&spectral.1)).abs() < 1e-2);
+/// ```
+pub fn laplacian(uv: &mut (Matrix<f32>, Matrix<f32>), f: f32, k: f32) {
+    gray_scott(uv, &GrayScottParams::new(f, k)).unwrap();
+}
@@ -0,0 +1,1254 @@
+use algorithm::diffusion_tensor::{anisotropic_laplacian, DiffusionTensor};
+use algorithm::flow::{Advection, Flow, SemiLagrangian, Velocity};
+use failure;
+use ndarray::{Array, Array2, Array3, Dimension};
+use num::Complex;
+use num_traits::Float;
+use rayon::prelude::*;
+use std::fmt;
+use std::mem;
+use visualizer::matrix_visualizer::Matrix;
+
+/// ソルバーが状態の値に使う浮動小数点数の型。`f32`と`f64`が当てはまる
+/// 長い時間の計算で単精度の丸め誤差が溜まるのが気になる場合は`f64`を使う。
+/// `f64`で計算できるのは`StencilSolver`と、それを使う`GrayScott`・`gray_scott`・`Simulator`(`ReactionDiffusion`の全てのモデル)で、
+/// `SpectralSolver`、`AdiSolver`、`MeshSolver`、`NetworkSimulator`は`f32`だけ
+pub trait Real: Float + fmt::Display + fmt::Debug + Send + Sync + 'static {}
+
+impl<T: Float + fmt::Display + fmt::Debug + Send + Sync + 'static> Real for T {}
+
+/// 状態(u, v)の値の型を変換する。`f32`の初期状態から`f64`で計算するときなどに使う
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::stencil::cast_state;
+/// use ndarray::arr2;
+///
+/// let state = (arr2(&[[1.0f32, 0.5]]), arr2(&[[0.0f32, 0.25]]));
+/// let precise = cast_state::<f32, f64>(&state);
+/// assert_eq!(precise.1[[0, 1]], 0.25f64);
+/// assert_eq!(cast_state::<f64, f32>(&precise), state);
+/// ```
+pub fn cast_state<A: Real, B: Real>(state: &(Matrix<A>, Matrix<A>)) -> (Matrix<B>, Matrix<B>) {
+    let convert = |matrix: &Matrix<A>| matrix.mapv(|e| B::from(e).unwrap_or_else(B::nan));
+    (convert(&state.0), convert(&state.1))
+}
+
+/// `f32`で持っているパラメーターの値を`T`に変換する
+pub(crate) fn cast<T: Real>(value: f32) -> T {
+    T::from(value).unwrap()
+}
+
+/// 格子の端の境界条件
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::stencil::{Boundary, StencilSolver};
+/// use ndarray::Array2;
+///
+/// let u = Array2::from_shape_fn((6, 6), |(i, j)| if i < 3 && j < 3 { 1.0f32 } else { 0.0 });
+/// let v = Array2::zeros((6, 6));
+///
+/// // ノイマン境界では拡散だけなら総量が保存される
+/// let mut solver = StencilSolver::new((u.clone(), v.clone()));
+/// solver.set_boundary(Boundary::Neumann);
+/// for _ in 0..20 {
+///     solver.step((1e-5, 1e-5), 0.01, 1.0, |_, _| (0.0, 0.0));
+/// }
+/// assert!((solver.state().0.scalar_sum() - u.scalar_sum()).abs() < 1e-4);
+///
+/// // ディリクレ境界では境界の値に近づいていく
+/// let mut solver = StencilSolver::new((u, v));
+/// solver.set_boundary(Boundary::Dirichlet(0.5, 0.0));
+/// for _ in 0..2000 {
+///     solver.step((1e-5, 1e-5), 0.01, 1.0, |_, _| (0.0, 0.0));
+/// }
+/// assert!(solver.state().0.iter().all(|e| (e - 0.5).abs() < 1e-2));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Boundary {
+    /// 周期境界。端は反対側の端とつながっている
+    Periodic,
+    /// ノイマン境界。端を通して出入りする量が0になる
+    Neumann,
+    /// ディリクレ境界。格子の外側の(u, v)が指定した値に固定されている
+    Dirichlet(f32, f32),
+}
+
+impl Default for Boundary {
+    fn default() -> Boundary {
+        Boundary::Periodic
+    }
+}
+
+/// 時間積分の方法
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Integrator {
+    /// 陽的Euler法(1次精度)
+    Euler,
+    /// Heun法(2次精度のRunge-Kutta法)
+    Heun,
+    /// 古典的な4次のRunge-Kutta法
+    RungeKutta4,
+    /// Heun法とEuler法の結果の差で誤差を見積もり、1ステップの誤差が指定した値以下になるように刻み幅を自動で調整する
+    /// `step`に渡す`dt`は刻み幅ではなく、進める時間の長さになる
+    Adaptive(f32),
+}
+
+impl Default for Integrator {
+    fn default() -> Integrator {
+        Integrator::Euler
+    }
+}
+
+impl Integrator {
+    /// 線形の方程式 du/dt = λu を刻み幅dtで1ステップ進めたときの増幅率|R(λdt)|
+    /// 1以下なら誤差が増幅されず安定である
+    ///
+    /// # Example
+    /// ```
+    /// extern crate num;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::algorithm::stencil::Integrator;
+    /// use num::Complex;
+    ///
+    /// // 実軸上の安定領域はEuler法とHeun法が[-2, 0]、4次のRunge-Kutta法が[-2.78, 0]
+    /// assert!(Integrator::Euler.amplification(Complex::new(-2.5, 0.0)) > 1.0);
+    /// assert!(Integrator::RungeKutta4.amplification(Complex::new(-2.5, 0.0)) <= 1.0);
+    /// ```
+    pub fn amplification<T: Real>(&self, z: Complex<T>) -> T {
+        let one = Complex::new(T::one(), T::zero());
+        let (two, six, twenty_four) = (cast::<T>(2.0), cast::<T>(6.0), cast::<T>(24.0));
+        let r = match *self {
+            Integrator::Euler => one + z,
+            Integrator::Heun | Integrator::Adaptive(_) => one + z + z * z / two,
+            Integrator::RungeKutta4 => one + z + z * z / two + z * z * z / six + z * z * z * z / twenty_four,
+        };
+        r.norm()
+    }
+}
+
+// 適応刻み幅で、刻み幅をこれ(進める時間に対する比)より小さくしない
+const MIN_ADAPTIVE_STEP_RATIO: f32 = 1e-6;
+
+/// `StencilSolver`で解く状態。同じ大きさの2次元の場(成分)をいくつか束ねたもの
+///
+/// 2変数の`(Matrix<T>, Matrix<T>)`と、成分を3番目の軸に並べた`Array3<T>`(行数, 列数, 成分の数)に実装してある
+pub trait Fields<T: Real>: Send + Sync + Sized {
+    /// 格子の大きさ(行数, 列数)
+    fn shape(&self) -> (usize, usize);
+
+    /// 成分の数
+    fn components(&self) -> usize;
+
+    /// 同じ形で、値がすべて0の状態
+    fn zeros_like(&self) -> Self;
+
+    /// 行ごとに連続したメモリ配置(C order)にしたもの。成分ごとに大きさが違う場合はpanicする
+    fn standard_layout(self) -> Self;
+
+    /// 値を並べたスライス
+    fn slices<'a>(&'a self) -> Slices<'a, T>;
+
+    /// 値を並べた書き換えられるスライス
+    fn slices_mut<'a>(&'a mut self) -> SlicesMut<'a, T>;
+}
+
+impl<T: Real> Fields<T> for (Matrix<T>, Matrix<T>) {
+    fn shape(&self) -> (usize, usize) {
+        self.0.dim()
+    }
+
+    fn components(&self) -> usize {
+        2
+    }
+
+    fn zeros_like(&self) -> (Matrix<T>, Matrix<T>) {
+        (Array2::zeros(self.0.dim()), Array2::zeros(self.1.dim()))
+    }
+
+    fn standard_layout(self) -> (Matrix<T>, Matrix<T>) {
+        assert_eq!(self.0.dim(), self.1.dim(), "u and v must have the same shape");
+        (standard_layout(self.0), standard_layout(self.1))
+    }
+
+    fn slices<'a>(&'a self) -> Slices<'a, T> {
+        Slices::Pair(self.0.as_slice().unwrap(), self.1.as_slice().unwrap())
+    }
+
+    fn slices_mut<'a>(&'a mut self) -> SlicesMut<'a, T> {
+        SlicesMut::Pair(self.0.as_slice_mut().unwrap(), self.1.as_slice_mut().unwrap())
+    }
+}
+
+impl<T: Real> Fields<T> for Array3<T> {
+    fn shape(&self) -> (usize, usize) {
+        let (rows, cols, _) = self.dim();
+        (rows, cols)
+    }
+
+    fn components(&self) -> usize {
+        self.dim().2
+    }
+
+    fn zeros_like(&self) -> Array3<T> {
+        Array3::zeros(self.dim())
+    }
+
+    fn standard_layout(self) -> Array3<T> {
+        standard_layout(self)
+    }
+
+    fn slices<'a>(&'a self) -> Slices<'a, T> {
+        Slices::Interleaved(self.as_slice().unwrap(), self.dim().2)
+    }
+
+    fn slices_mut<'a>(&'a mut self) -> SlicesMut<'a, T> {
+        let components = self.dim().2;
+        SlicesMut::Interleaved(self.as_slice_mut().unwrap(), components)
+    }
+}
+
+/// `Fields`の値を行優先で並べたスライス
+#[derive(Clone, Copy, Debug)]
+pub enum Slices<'a, T: 'a> {
+    /// 成分ごとに別々のスライス(u, v)
+    Pair(&'a [T], &'a [T]),
+    /// セルごとに成分を並べた1つのスライスと、成分の数
+    Interleaved(&'a [T], usize),
+}
+
+impl<'a, T: Real> Slices<'a, T> {
+    /// スライスの数
+    pub(crate) fn parts(self) -> usize {
+        match self {
+            Slices::Pair(_, _) => 2,
+            Slices::Interleaved(_, _) => 1,
+        }
+    }
+
+    /// 1つのスライスでセルごとに並べている成分の数
+    pub(crate) fn stride(self) -> usize {
+        match self {
+            Slices::Pair(_, _) => 1,
+            Slices::Interleaved(_, components) => components,
+        }
+    }
+
+    /// `part`番目のスライス
+    pub(crate) fn part(self, part: usize) -> &'a [T] {
+        match self {
+            Slices::Pair(u, v) => {
+                if part == 0 {
+                    u
+                } else {
+                    v
+                }
+            }
+            Slices::Interleaved(values, _) => values,
+        }
+    }
+}
+
+/// `Fields`の値を行優先で並べた書き換えられるスライス
+#[derive(Debug)]
+pub enum SlicesMut<'a, T: 'a> {
+    /// 成分ごとに別々のスライス(u, v)
+    Pair(&'a mut [T], &'a mut [T]),
+    /// セルごとに成分を並べた1つのスライスと、成分の数
+    Interleaved(&'a mut [T], usize),
+}
+
+impl<'a, T: Real> SlicesMut<'a, T> {
+    /// `part`番目のスライス
+    pub(crate) fn part_mut(&mut self, part: usize) -> &mut [T] {
+        match *self {
+            SlicesMut::Pair(ref mut u, ref mut v) => {
+                if part == 0 {
+                    u
+                } else {
+                    v
+                }
+            }
+            SlicesMut::Interleaved(ref mut values, _) => values,
+        }
+    }
+}
+
+/// セルの値の読み方。並べ方ごとに別々にコンパイルされるので、内側のループで並べ方を調べずに済む
+trait Values<T>: Copy + Send + Sync {
+    /// 成分の数
+    fn components(self) -> usize;
+
+    /// 行優先で数えたセル`index`の、`component`番目の成分の値
+    fn get(self, index: usize, component: usize) -> T;
+}
+
+#[derive(Clone, Copy)]
+struct PairValues<'a, T: 'a>(&'a [T], &'a [T]);
+
+impl<'a, T: Real> Values<T> for PairValues<'a, T> {
+    #[inline]
+    fn components(self) -> usize {
+        2
+    }
+
+    #[inline]
+    fn get(self, index: usize, component: usize) -> T {
+        if component == 0 {
+            self.0[index]
+        } else {
+            self.1[index]
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct InterleavedValues<'a, T: 'a>(&'a [T], usize);
+
+impl<'a, T: Real> Values<T> for InterleavedValues<'a, T> {
+    #[inline]
+    fn components(self) -> usize {
+        self.1
+    }
+
+    #[inline]
+    fn get(self, index: usize, component: usize) -> T {
+        self.0[index * self.1 + component]
+    }
+}
+
+/// 1行分の書き込み先
+trait Row<T> {
+    /// `col`列目のセルの`component`番目の成分に書き込む
+    fn set(&mut self, col: usize, component: usize, value: T);
+}
+
+struct PairRow<'a, T: 'a>(&'a mut [T], &'a mut [T]);
+
+impl<'a, T> Row<T> for PairRow<'a, T> {
+    #[inline]
+    fn set(&mut self, col: usize, component: usize, value: T) {
+        if component == 0 {
+            self.0[col] = value;
+        } else {
+            self.1[col] = value;
+        }
+    }
+}
+
+struct InterleavedRow<'a, T: 'a>(&'a mut [T], usize);
+
+impl<'a, T> Row<T> for InterleavedRow<'a, T> {
+    #[inline]
+    fn set(&mut self, col: usize, component: usize, value: T) {
+        self.0[col * self.1 + component] = value;
+    }
+}
+
+/// 5点差分ステンシルで反応拡散系を解くソルバー
+/// 状態は2変数の(u, v)か、任意の数の成分を並べた`Array3`(`Fields`)で、どちらも同じステンシルと積分法で計算する
+///
+/// 現在の状態と次の状態を書き込むバッファを1組ずつ持ち、1ステップごとに入れ替える(ダブルバッファ)。
+/// Runge-Kutta法の途中の値を置くバッファも使い回すので、`step`では新しい配列を確保しない
+/// (積分法を変えた直後の1回だけ、足りないバッファを確保する)。
+/// 陽的Euler法では時間微分をバッファに置かず、1回の走査で次の状態を書き込む。
+/// 隣のセルの添字は境界条件に従ってその場で求め、行ごとにrayonで並列に計算する。
+/// セルごとの値や反応項を置く作業領域も、行ごとに1度だけ確保したものを使い回す
+///
+/// マスクで障害物に指定したセルでは拡散も反応も起こらず、値は変わらない。
+/// 障害物との境目はノイマン境界と同じく、出入りする量が0になる
+///
+/// `set_flow`で流れによる移流を、`set_anisotropy`で向きによって強さの違う拡散を加えられる
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::stencil::StencilSolver;
+/// use ndarray::Array2;
+///
+/// let (rows, cols) = (5, 7);
+/// let u = Array2::from_shape_fn((rows, cols), |(i, j)| ((i * 3 + j * 5) % 7) as f32 / 7.0);
+/// let v = Array2::from_shape_fn((rows, cols), |(i, j)| ((i * 2 + j) % 5) as f32 / 5.0);
+/// let (du, dv, dx, dt) = (2e-5, 1e-5, 0.01, 1.0);
+/// let (f, k) = (0.04, 0.06);
+///
+/// let mut solver = StencilSolver::new((u.clone(), v.clone()));
+/// solver.step((du, dv), dx, dt, |u, v| (-u * v * v + f * (1.0 - u), u * v * v - (f + k) * v));
+///
+/// // 素朴に添字を折り返して計算したものと一致する
+/// let laplacian = |a: &Array2<f32>, i: usize, j: usize| {
+///     a[[(i + rows - 1) % rows, j]] + a[[(i + 1) % rows, j]] + a[[i, (j + cols - 1) % cols]]
+///         + a[[i, (j + 1) % cols]] - 4.0 * a[[i, j]]
+/// };
+/// for i in 0..rows {
+///     for j in 0..cols {
+///         let (uc, vc) = (u[[i, j]], v[[i, j]]);
+///         let expected_u = uc + dt * (du * laplacian(&u, i, j) / (dx * dx) - uc * vc * vc + f * (1.0 - uc));
+///         let expected_v = vc + dt * (dv * laplacian(&v, i, j) / (dx * dx) + uc * vc * vc - (f + k) * vc);
+///         assert!((solver.state().0[[i, j]] - expected_u).abs() < 1e-5);
+///         assert!((solver.state().1[[i, j]] - expected_v).abs() < 1e-5);
+///     }
+/// }
+///
+/// // 配列を丸ごと巡回シフト(roll)してラプラシアンを求める、元の実装のやり方で何ステップか進めたものとも一致する
+/// let roll = |a: &Array2<f32>, shift: usize, axis: usize| {
+///     Array2::from_shape_fn((rows, cols), |(i, j)| {
+///         if axis == 0 { a[[(i + rows - shift) % rows, j]] } else { a[[i, (j + cols - shift) % cols]] }
+///     })
+/// };
+/// let rolled_laplacian = |a: &Array2<f32>| {
+///     (roll(a, 1, 0) + roll(a, rows - 1, 0) + roll(a, 1, 1) + roll(a, cols - 1, 1) - a * 4.0) / (dx * dx)
+/// };
+/// let (mut u, mut v) = (u, v);
+/// let mut solver = StencilSolver::new((u.clone(), v.clone()));
+/// for _ in 0..30 {
+///     let uvv = &u * &v * &v;
+///     let dudt = rolled_laplacian(&u) * du - &uvv + (1.0 - &u) * f;
+///     let dvdt = rolled_laplacian(&v) * dv + &uvv - &v * (f + k);
+///     u = u + dudt * dt;
+///     v = v + dvdt * dt;
+///     solver.step((du, dv), dx, dt, |u, v| (-u * v * v + f * (1.0 - u), u * v * v - (f + k) * v));
+/// }
+/// let difference = |a: &Array2<f32>, b: &Array2<f32>| a.iter().zip(b).fold(0.0f32, |m, (p, q)| m.max((p - q).abs()));
+/// assert!(difference(&solver.state().0, &u) < 1e-5);
+/// assert!(difference(&solver.state().1, &v) < 1e-5);
+/// ```
+pub struct StencilSolver<T = f32, S = (Matrix<T>, Matrix<T>)> {
+    front: S,
+    back: S,
+    // Runge-Kutta法の各段での時間微分
+    rates: Vec<S>,
+    // Runge-Kutta法の途中の状態
+    scratch: Option<S>,
+    // 行ごとの作業領域。1行につき成分の数の3倍の長さ
+    workspace: Vec<T>,
+    boundary: Boundary,
+    // ディリクレ境界で格子の外側に置く各成分の値。Noneなら`Boundary::Dirichlet`の値を使う
+    dirichlet: Option<Vec<T>>,
+    mask: Option<Array2<bool>>,
+    flow: Option<Flow>,
+    anisotropy: DiffusionTensor,
+    integrator: Integrator,
+    adaptive_step: Option<T>,
+}
+
+impl<T: Real, S: Fields<T>> StencilSolver<T, S> {
+    /// 初期状態からStencilSolverインスタンスを生成する
+    /// 書き込み先のバッファと作業領域はここで1度だけ確保する
+    ///
+    /// # Arguments
+    /// * `state` - 初期状態。2変数なら(u, v)で、uとvは同じ大きさでなければならない
+    pub fn new(state: S) -> StencilSolver<T, S> {
+        let front = state.standard_layout();
+        let workspace = vec![T::zero(); front.shape().0 * 3 * front.components()];
+        StencilSolver {
+            back: front.zeros_like(),
+            front: front,
+            rates: Vec::new(),
+            scratch: None,
+            workspace: workspace,
+            boundary: Boundary::Periodic,
+            dirichlet: None,
+            mask: None,
+            flow: None,
+            anisotropy: DiffusionTensor::isotropic(),
+            integrator: Integrator::Euler,
+            adaptive_step: None,
+        }
+    }
+
+    /// 時間積分の方法
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    /// 時間積分の方法を変更する
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        if integrator != self.integrator {
+            self.adaptive_step = None;
+        }
+        self.integrator = integrator;
+    }
+
+    /// 適応刻み幅で最後に選んだ(次のステップで最初に試す)刻み幅
+    pub fn adaptive_step(&self) -> Option<T> {
+        self.adaptive_step
+    }
+
+    /// 境界条件
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    /// 境界条件を変更する
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.boundary = boundary;
+    }
+
+    /// ディリクレ境界で格子の外側に置く各成分の値を変更する。長さが成分の数と違う場合はエラーになる
+    /// `None`なら`Boundary::Dirichlet(u, v)`の値を使う(3番目以降の成分はvと同じになる)
+    pub fn set_dirichlet_values(&mut self, values: Option<Vec<T>>) -> Result<(), failure::Error> {
+        if let Some(ref values) = values {
+            if values.len() != self.front.components() {
+                return Err(failure::err_msg(format!(
+                    "{} Dirichlet values are given for {} components",
+                    values.len(),
+                    self.front.components()
+                )));
+            }
+        }
+        self.dirichlet = values;
+        Ok(())
+    }
+
+    /// 障害物のマスク
+    pub fn mask(&self) -> Option<&Array2<bool>> {
+        self.mask.as_ref()
+    }
+
+    /// 障害物のマスクを変更する。`true`のセルが障害物になる。`None`なら障害物はない
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ndarray;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::algorithm::stencil::StencilSolver;
+    /// use ndarray::Array2;
+    ///
+    /// let u = Array2::from_shape_fn((8, 8), |(i, j)| (i * 8 + j) as f32 / 64.0);
+    /// let v = Array2::zeros((8, 8));
+    /// let mask = Array2::from_shape_fn((8, 8), |(i, _)| i < 2);
+    /// let mut solver = StencilSolver::new((u.clone(), v));
+    /// solver.set_mask(Some(mask)).unwrap();
+    /// for _ in 0..10 {
+    ///     solver.step((1e-5, 1e-5), 0.01, 1.0, |_, _| (0.0, 0.0));
+    /// }
+    /// // 障害物のセルの値は変わらない
+    /// assert_eq!(solver.state().0.row(0), u.row(0));
+    /// assert!(solver.state().0.row(4) != u.row(4));
+    /// ```
+    pub fn set_mask(&mut self, mask: Option<Array2<bool>>) -> Result<(), failure::Error> {
+        if let Some(ref mask) = mask {
+            if mask.dim() != self.front.shape() {
+                return Err(failure::err_msg(format!(
+                    "mask shape {:?} does not match state shape {:?}",
+                    mask.dim(),
+                    self.front.shape()
+                )));
+            }
+        }
+        self.mask = mask.map(standard_layout);
+        Ok(())
+    }
+
+    /// 流れ(移流)
+    pub fn flow(&self) -> Option<&Flow> {
+        self.flow.as_ref()
+    }
+
+    /// 流れを変更する。`None`なら流れはない。速度場の大きさが格子と違う場合はエラーになる
+    /// 障害物のセルは流れがあっても値が変わらない
+    pub fn set_flow(&mut self, flow: Option<Flow>) -> Result<(), failure::Error> {
+        if let Some(Flow {
+            velocity: Velocity::Field(ref vx, ref vy),
+            ..
+        }) = flow
+        {
+            let dim = self.front.shape();
+            if vx.dim() != dim || vy.dim() != dim {
+                return Err(failure::err_msg(format!(
+                    "velocity field shape ({:?}, {:?}) does not match state shape {:?}",
+                    vx.dim(),
+                    vy.dim(),
+                    dim
+                )));
+            }
+        }
+        self.flow = flow;
+        Ok(())
+    }
+
+    /// 流れの最大のCourant数(|vx| + |vy|) * dt / dx。流れがなければ0
+    pub fn courant_number(&self, dx: f32, dt: f32) -> f32 {
+        self.flow
+            .as_ref()
+            .map_or(0.0, |flow| flow.velocity.max_speed(self.front.shape().0, dx) * dt / dx)
+    }
+
+    /// 移流が安定に計算できるかを調べる
+    /// 風上差分は拡散と同時に陽的に計算するので、Courant数と拡散数の和
+    /// `(|vx| + |vy|) * dt / dx + 4 * D * dt / dx^2`(拡散テンソルの分も含める)が1を超えるとエラーになる。
+    /// セミラグランジュ法は刻み幅によらず安定なので、常に`Ok`になる
+    ///
+    /// # Arguments
+    /// * `diffusion` - 拡散係数の最大値
+    /// * `dx` - 格子の間隔
+    /// * `dt` - 時間の刻み幅
+    pub fn check_flow(&self, diffusion: f32, dx: f32, dt: f32) -> Result<(), failure::Error> {
+        let courant = self.courant_number(dx, dt);
+        let diffusion_number = 4.0 * diffusion * self.anisotropy.spectral_factor() * dt / (dx * dx);
+        match self.flow {
+            Some(Flow {
+                advection: Advection::Upwind,
+                ..
+            }) if !(courant + diffusion_number <= 1.0) => Err(failure::err_msg(format!(
+                "dt = {} is too large for upwind advection: Courant number {} plus diffusion number {} exceeds 1 \
+                 (use Advection::SemiLagrangian or a smaller dt)",
+                dt, courant, diffusion_number
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// 拡散テンソル
+    pub fn anisotropy(&self) -> DiffusionTensor {
+        self.anisotropy
+    }
+
+    /// 拡散テンソルを変更する。半正定値でない場合はエラーになる
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ndarray;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::algorithm::diffusion_tensor::DiffusionTensor;
+    /// use my_alife::algorithm::stencil::StencilSolver;
+    /// use ndarray::Array2;
+    ///
+    /// // 中央の1点から、x方向にだけ広がる
+    /// let u = Array2::from_shape_fn((9, 9), |(i, j)| if (i, j) == (4, 4) { 1.0 } else { 0.0 });
+    /// let mut solver = StencilSolver::new((u, Array2::zeros((9, 9))));
+    /// solver.set_anisotropy(DiffusionTensor { xx: 1.0, xy: 0.0, yy: 0.0 }).unwrap();
+    /// for _ in 0..10 {
+    ///     solver.step((1e-5, 1e-5), 0.01, 1.0, |_, _| (0.0, 0.0));
+    /// }
+    /// assert!(solver.state().0[[4, 6]] > 0.0);
+    /// assert_eq!(solver.state().0[[2, 4]], 0.0);
+    ///
+    /// assert!(solver.set_anisotropy(DiffusionTensor { xx: -1.0, xy: 0.0, yy: 1.0 }).is_err());
+    /// ```
+    pub fn set_anisotropy(&mut self, anisotropy: DiffusionTensor) -> Result<(), failure::Error> {
+        if !anisotropy.is_positive_semidefinite() {
+            return Err(failure::err_msg(format!(
+                "diffusion tensor {:?} is not positive semidefinite",
+                anisotropy
+            )));
+        }
+        self.anisotropy = anisotropy;
+        Ok(())
+    }
+
+    /// 現在の状態
+    pub fn state(&self) -> &S {
+        &self.front
+    }
+
+    /// 現在の状態を書き換える。大きさや成分の数は変えてはいけない(変えると次の時間発展でpanicする)
+    pub fn state_mut(&mut self) -> &mut S {
+        &mut self.front
+    }
+
+    /// 現在の状態を取り出す
+    pub fn into_state(self) -> S {
+        self.front
+    }
+
+    /// 成分の数によらない`step`。時間積分の方法(`integrator`)に従って、時間を`dt`だけ進める
+    /// 拡散はD(x)∇²uとして計算する(Dの勾配による流れは考えない)
+    ///
+    /// # Arguments
+    /// * `dx` - 格子の間隔
+    /// * `dt` - 時間の刻み幅(適応刻み幅の場合は進める時間の長さ)
+    /// * `local` - セルの添字(行優先で数えたもの)と各成分の値を受け取り、そのセルでの各成分の拡散係数と
+    ///   反応項(時間微分)を2番目と3番目の引数に書き込む関数。1つのセルにつき各段で1回だけ呼ぶ
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ndarray;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::algorithm::stencil::{Boundary, Integrator, StencilSolver};
+    /// use ndarray::{Array2, Array3};
+    ///
+    /// let u = Array2::from_shape_fn((6, 5), |(i, j)| ((i * 3 + j * 5) % 7) as f32 / 7.0);
+    /// let v = Array2::from_shape_fn((6, 5), |(i, j)| ((i * 2 + j) % 5) as f32 / 5.0);
+    /// let (f, k) = (0.04, 0.06);
+    /// let reaction = |u: f32, v: f32| (-u * v * v + f * (1.0 - u), u * v * v - (f + k) * v);
+    ///
+    /// // 成分を3番目の軸に並べても、(u, v)と同じ結果になる
+    /// let mut pair = StencilSolver::new((u.clone(), v.clone()));
+    /// let mut fields = StencilSolver::new(Array3::from_shape_fn((6, 5, 2), |(i, j, s)| {
+    ///     if s == 0 { u[[i, j]] } else { v[[i, j]] }
+    /// }));
+    /// pair.set_integrator(Integrator::RungeKutta4);
+    /// pair.set_boundary(Boundary::Dirichlet(1.0, 0.0));
+    /// fields.set_integrator(Integrator::RungeKutta4);
+    /// fields.set_boundary(Boundary::Dirichlet(1.0, 0.0));
+    /// for _ in 0..10 {
+    ///     pair.step((0.2, 0.1), 1.0, 1.0, |u, v| reaction(u, v));
+    ///     fields.step_fields(1.0, 1.0, |_, c, diffusion, rates| {
+    ///         diffusion.copy_from_slice(&[0.2, 0.1]);
+    ///         let (du, dv) = reaction(c[0], c[1]);
+    ///         rates.copy_from_slice(&[du, dv]);
+    ///     });
+    /// }
+    /// for ((i, j), &u) in pair.state().0.indexed_iter() {
+    ///     assert!((fields.state()[[i, j, 0]] - u).abs() < 1e-6);
+    ///     assert!((fields.state()[[i, j, 1]] - pair.state().1[[i, j]]).abs() < 1e-6);
+    /// }
+    /// ```
+    pub fn step_fields<L>(&mut self, dx: T, dt: T, local: L)
+    where
+        L: Fn(usize, &[T], &mut [T], &mut [T]) + Sync,
+    {
+        let (rows, cols) = self.front.shape();
+        if rows == 0 || cols == 0 || self.front.components() == 0 {
+            return;
+        }
+        assert!(
+            self.back.shape() == (rows, cols) && self.back.components() == self.front.components(),
+            "the shape of the state must not be changed"
+        );
+        self.reserve();
+        // 移流は`self`全体を借りるので、ステンシルと作業領域の借用はこのブロックの中で終える
+        {
+            let mut pair = [T::zero(); 2];
+            let stencil = Stencil {
+                rows: rows,
+                cols: cols,
+                periodic: self.boundary == Boundary::Periodic,
+                outside: outside_values(self.boundary, &self.dirichlet, &mut pair),
+                inverse_dx2: T::one() / (dx * dx),
+                dx: dx,
+                mask: self.mask.as_ref().map(|m| m.as_slice().unwrap()),
+                upwind: match self.flow {
+                    Some(Flow {
+                        ref velocity,
+                        advection: Advection::Upwind,
+                    }) => Some(velocity),
+                    _ => None,
+                },
+                anisotropy: self.anisotropy.stencil(),
+            };
+            let (two, three, six) = (cast::<T>(2.0), cast::<T>(3.0), cast::<T>(6.0));
+            let rates = &mut self.rates;
+            let workspace = &mut self.workspace;
+            match self.integrator {
+                Integrator::Euler => {
+                    stencil.euler(&self.front, &mut self.back, workspace, dt, &local);
+                    mem::swap(&mut self.front, &mut self.back);
+                }
+                Integrator::Heun => {
+                    let scratch = self.scratch.as_mut().unwrap();
+                    stencil.rates(&self.front, &mut rates[0], workspace, &local);
+                    combine(scratch, &self.front, &[(dt, &rates[0])]);
+                    stencil.rates(scratch, &mut rates[1], workspace, &local);
+                    combine(
+                        &mut self.back,
+                        &self.front,
+                        &[(dt / two, &rates[0]), (dt / two, &rates[1])],
+                    );
+                    mem::swap(&mut self.front, &mut self.back);
+                }
+                Integrator::RungeKutta4 => {
+                    let scratch = self.scratch.as_mut().unwrap();
+                    stencil.rates(&self.front, &mut rates[0], workspace, &local);
+                    combine(scratch, &self.front, &[(dt / two, &rates[0])]);
+                    stencil.rates(scratch, &mut rates[1], workspace, &local);
+                    combine(scratch, &self.front, &[(dt / two, &rates[1])]);
+                    stencil.rates(scratch, &mut rates[2], workspace, &local);
+                    combine(scratch, &self.front, &[(dt, &rates[2])]);
+                    stencil.rates(scratch, &mut rates[3], workspace, &local);
+                    combine(
+                        &mut self.back,
+                        &self.front,
+                        &[
+                            (dt / six, &rates[0]),
+                            (dt / three, &rates[1]),
+                            (dt / three, &rates[2]),
+                            (dt / six, &rates[3]),
+                        ],
+                    );
+                    mem::swap(&mut self.front, &mut self.back);
+                }
+                Integrator::Adaptive(tolerance) => {
+                    let scratch = self.scratch.as_mut().unwrap();
+                    let tolerance = cast::<T>(tolerance);
+                    let min_step = dt * cast(MIN_ADAPTIVE_STEP_RATIO);
+                    let mut remaining = dt;
+                    while remaining > min_step {
+                        stencil.rates(&self.front, &mut rates[0], workspace, &local);
+                        loop {
+                            let proposal = self.adaptive_step.unwrap_or(dt);
+                            let h = proposal.min(remaining);
+                            combine(scratch, &self.front, &[(h, &rates[0])]);
+                            stencil.rates(scratch, &mut rates[1], workspace, &local);
+                            // Euler法(1次)とHeun法(2次)の差が1ステップの誤差の見積もりになる
+                            let error = h / two * max_difference(&rates[0], &rates[1]);
+                            let factor = if error.is_nan() {
+                                cast(0.2)
+                            } else if error > T::zero() {
+                                (cast::<T>(0.9) * (tolerance / error).sqrt()).max(cast(0.2)).min(two)
+                            } else {
+                                two
+                            };
+                            if error <= tolerance || h <= min_step {
+                                combine(
+                                    &mut self.back,
+                                    &self.front,
+                                    &[(h / two, &rates[0]), (h / two, &rates[1])],
+                                );
+                                mem::swap(&mut self.front, &mut self.back);
+                                remaining = remaining - h;
+                                // 残り時間に合わせて縮めた刻み幅は次のステップに持ち越さない
+                                self.adaptive_step = Some(if h < proposal && factor >= T::one() {
+                                    proposal
+                                } else {
+                                    (h * factor).max(min_step)
+                                });
+                                break;
+                            }
+                            self.adaptive_step = Some((h * factor).max(min_step));
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(Flow {
+            advection: Advection::SemiLagrangian,
+            ..
+        }) = self.flow
+        {
+            self.advect(dx, dt);
+        }
+    }
+
+    /// セミラグランジュ法で時間`dt`だけ移流する
+    fn advect(&mut self, dx: T, dt: T) {
+        let velocity = match self.flow {
+            Some(ref flow) => &flow.velocity,
+            None => return,
+        };
+        let (rows, cols) = self.front.shape();
+        let mut pair = [T::zero(); 2];
+        SemiLagrangian {
+            velocity: velocity,
+            rows: rows,
+            cols: cols,
+            periodic: self.boundary == Boundary::Periodic,
+            outside: outside_values(self.boundary, &self.dirichlet, &mut pair),
+            mask: self.mask.as_ref().map(|m| m.as_slice().unwrap()),
+            dx: dx,
+            dt: dt,
+        }
+        .advect(&self.front, &mut self.back);
+        mem::swap(&mut self.front, &mut self.back);
+    }
+
+    /// 積分法に必要なバッファを確保する
+    fn reserve(&mut self) {
+        let (stages, scratch) = match self.integrator {
+            Integrator::Euler => (0, false),
+            Integrator::Heun | Integrator::Adaptive(_) => (2, true),
+            Integrator::RungeKutta4 => (4, true),
+        };
+        while self.rates.len() < stages {
+            let zeros = self.front.zeros_like();
+            self.rates.push(zeros);
+        }
+        if scratch && self.scratch.is_none() {
+            self.scratch = Some(self.front.zeros_like());
+        }
+    }
+}
+
+/// 2変数(u, v)の場合の時間発展
+impl<T: Real> StencilSolver<T> {
+    /// 時間積分の方法(`integrator`)に従って、時間を`dt`だけ進める
+    ///
+    /// # Arguments
+    /// * `diffusion` - uとvの拡散係数
+    /// * `dx` - 格子の間隔
+    /// * `dt` - 時間の刻み幅(適応刻み幅の場合は進める時間の長さ)
+    /// * `reaction` - (u, v)を受け取り、反応項(du/dt, dv/dt)を返す関数
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ndarray;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::algorithm::stencil::{Integrator, StencilSolver};
+    /// use ndarray::Array2;
+    ///
+    /// // du/dt = -u の厳密解はexp(-t)。高次の積分法ほど誤差が小さい
+    /// let error = |integrator| {
+    ///     let mut solver = StencilSolver::new((Array2::ones((2, 2)), Array2::ones((2, 2))));
+    ///     solver.set_integrator(integrator);
+    ///     for _ in 0..10 {
+    ///         solver.step((0.0, 0.0), 1.0, 0.1, |u, v| (-u, -v));
+    ///     }
+    ///     (solver.state().0[[0, 0]] - (-1.0f32).exp()).abs()
+    /// };
+    /// assert!(error(Integrator::Euler) > error(Integrator::Heun));
+    /// assert!(error(Integrator::Heun) > error(Integrator::RungeKutta4));
+    /// assert!(error(Integrator::RungeKutta4) < 1e-6);
+    /// assert!(error(Integrator::Adaptive(1e-6)) < 1e-4);
+    /// ```
+    pub fn step<R>(&mut self, diffusion: (T, T), dx: T, dt: T, reaction: R)
+    where
+        R: Fn(T, T) -> (T, T) + Sync,
+    {
+        self.step_varying(dx, dt, |_, u, v| (diffusion, reaction(u, v)));
+    }
+
+    /// 拡散係数と反応項がセルごとに異なる場合の`step`
+    /// 拡散はD(x)∇²uとして計算する(Dの勾配による流れは考えない)
+    ///
+    /// # Arguments
+    /// * `dx` - 格子の間隔
+    /// * `dt` - 時間の刻み幅(適応刻み幅の場合は進める時間の長さ)
+    /// * `local` - セルの添字(行優先で数えたもの)と(u, v)を受け取り、そのセルでのuとvの拡散係数と
+    ///   反応項(du/dt, dv/dt)の組を返す関数。1つのセルにつき各段で1回だけ呼ぶ
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ndarray;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::algorithm::stencil::StencilSolver;
+    /// use ndarray::Array2;
+    ///
+    /// // 左半分だけuが減衰する
+    /// let mut solver = StencilSolver::new((Array2::ones((4, 4)), Array2::zeros((4, 4))));
+    /// solver.step_varying(1.0, 0.1, |i, u, _| ((0.0, 0.0), (if i % 4 < 2 { -u } else { 0.0 }, 0.0)));
+    /// assert!(solver.state().0[[0, 0]] < 1.0);
+    /// assert_eq!(solver.state().0[[0, 3]], 1.0);
+    /// ```
+    pub fn step_varying<L>(&mut self, dx: T, dt: T, local: L)
+    where
+        L: Fn(usize, T, T) -> ((T, T), (T, T)) + Sync,
+    {
+        self.step_fields(dx, dt, |index, values, diffusion, reaction| {
+            let ((du, dv), (ru, rv)) = local(index, values[0], values[1]);
+            diffusion[0] = du;
+            diffusion[1] = dv;
+            reaction[0] = ru;
+            reaction[1] = rv;
+        });
+    }
+}
+
+/// 5点差分ステンシルで時間微分を計算するための設定
+struct Stencil<'a, T: 'a> {
+    rows: usize,
+    cols: usize,
+    periodic: bool,
+    // 格子の外側の各成分の値。Noneなら中心のセルと同じ値(出入りが0)とみなす
+    outside: Option<&'a [T]>,
+    inverse_dx2: T,
+    dx: T,
+    mask: Option<&'a [bool]>,
+    // 風上差分で移流を計算する場合の速度場
+    upwind: Option<&'a Velocity>,
+    // 等方的でない場合の拡散テンソルの成分(xx, xy, yy)。斜めのセルも使う9点差分になる
+    anisotropy: Option<(T, T, T)>,
+}
+
+impl<'a, T: Real> Stencil<'a, T> {
+    fn is_obstacle(&self, index: usize) -> bool {
+        self.mask.map_or(false, |m| m[index])
+    }
+
+    /// 隣のセルの値をどこから取るか。格子の外側や障害物なら境界条件に従って置き換える
+    fn neighbor(&self, neighbor: Option<usize>) -> Neighbor {
+        match neighbor {
+            Some(n) if !self.is_obstacle(n) => Neighbor::Cell(n),
+            Some(_) => Neighbor::Center,
+            None if self.outside.is_some() => Neighbor::Outside,
+            None => Neighbor::Center,
+        }
+    }
+
+    /// 状態`state`での時間微分を`rates`に書き込む。障害物のセルは0になる
+    fn rates<S, L>(&self, state: &S, rates: &mut S, workspace: &mut [T], local: &L)
+    where
+        S: Fields<T>,
+        L: Fn(usize, &[T], &mut [T], &mut [T]) + Sync,
+    {
+        self.apply(state, rates, workspace, local, |_, rate| rate);
+    }
+
+    /// 陽的Euler法で`dt`だけ進めた状態を`next`に書き込む
+    /// 時間微分をバッファに置かず、1回の走査で次の状態まで計算する
+    fn euler<S, L>(&self, state: &S, next: &mut S, workspace: &mut [T], dt: T, local: &L)
+    where
+        S: Fields<T>,
+        L: Fn(usize, &[T], &mut [T], &mut [T]) + Sync,
+    {
+        self.apply(state, next, workspace, local, |value, rate| value + dt * rate);
+    }
+
+    /// 各セルで時間微分を計算し、`output(セルの値, 時間微分)`を`out`に書き込む
+    /// 行ごとに並列に計算する。`workspace`は行数で等分し、1行につき成分の数の3倍の長さを使う
+    fn apply<S, L, O>(&self, state: &S, out: &mut S, workspace: &mut [T], local: &L, output: O)
+    where
+        S: Fields<T>,
+        L: Fn(usize, &[T], &mut [T], &mut [T]) + Sync,
+        O: Fn(T, T) -> T + Sync,
+    {
+        let (rows, cols) = (self.rows, self.cols);
+        let width = workspace.len() / rows;
+        let output = &output;
+        match (state.slices(), out.slices_mut()) {
+            (Slices::Pair(u, v), SlicesMut::Pair(next_u, next_v)) => {
+                let values = PairValues(u, v);
+                next_u
+                    .par_chunks_mut(cols)
+                    .zip(next_v.par_chunks_mut(cols))
+                    .zip(workspace.par_chunks_mut(width))
+                    .enumerate()
+                    .for_each(|(i, ((next_u, next_v), work))| {
+                        self.row(values, PairRow(next_u, next_v), i, work, local, output)
+                    });
+            }
+            (Slices::Interleaved(values, components), SlicesMut::Interleaved(next, _)) => {
+                let values = InterleavedValues(values, components);
+                next.par_chunks_mut(cols * components)
+                    .zip(workspace.par_chunks_mut(width))
+                    .enumerate()
+                    .for_each(|(i, (next, work))| {
+                        self.row(values, InterleavedRow(next, components), i, work, local, output)
+                    });
+            }
+            _ => panic!("states have different layouts"),
+        }
+    }
+
+    /// `values`を読んで、`i`行目の`output(セルの値, 時間微分)`を`row`に書き込む。`work`はこの行だけで使える作業領域
+    fn row<V, W, L, O>(&self, values: V, mut row: W, i: usize, work: &mut [T], local: &L, output: &O)
+    where
+        V: Values<T>,
+        W: Row<T>,
+        L: Fn(usize, &[T], &mut [T], &mut [T]) + Sync,
+        O: Fn(T, T) -> T + Sync,
+    {
+        let (rows, cols) = (self.rows, self.cols);
+        let n = values.components();
+        let four = cast::<T>(4.0);
+        // セルの値、拡散係数、反応項
+        let (cell, rest) = work[..3 * n].split_at_mut(n);
+        let (diffusion, reaction) = rest.split_at_mut(n);
+        // 上下の行の先頭の添字。格子の外側ならNone
+        let up = neighbor_index(i, rows, -1, self.periodic).map(|i| i * cols);
+        let down = neighbor_index(i, rows, 1, self.periodic).map(|i| i * cols);
+        let here = i * cols;
+        for j in 0..cols {
+            let center = here + j;
+            for (s, value) in cell.iter_mut().enumerate() {
+                *value = values.get(center, s);
+            }
+            if self.is_obstacle(center) {
+                for (s, &value) in cell.iter().enumerate() {
+                    row.set(j, s, output(value, T::zero()));
+                }
+                continue;
+            }
+            let left = neighbor_index(j, cols, -1, self.periodic);
+            let right = neighbor_index(j, cols, 1, self.periodic);
+            // 上, 下, 左, 右
+            let neighbors = [
+                self.neighbor(up.map(|up| up + j)),
+                self.neighbor(down.map(|down| down + j)),
+                self.neighbor(left.map(|j| here + j)),
+                self.neighbor(right.map(|j| here + j)),
+            ];
+            // 右下, 左下, 右上, 左上。異方的な拡散でだけ使う
+            let corner = |row: Option<usize>, col: Option<usize>| match (row, col) {
+                (Some(row), Some(col)) => self.neighbor(Some(row + col)),
+                _ => self.neighbor(None),
+            };
+            let diagonals = self.anisotropy.map(|_| {
+                [
+                    corner(down, right),
+                    corner(down, left),
+                    corner(up, right),
+                    corner(up, left),
+                ]
+            });
+            // 風上差分の移流で、上, 下, 左, 右の隣のセルとの差に掛ける重み
+            let upwind = self.upwind.map(|velocity| velocity.upwind_weights(i, j, rows, self.dx));
+            local(center, cell, diffusion, reaction);
+            // 等方的で移流もない、よく使う場合は分岐の少ないループで済ませる
+            if diagonals.is_none() && upwind.is_none() {
+                for s in 0..n {
+                    let c = cell[s];
+                    let mut sum = T::zero();
+                    for &neighbor in &neighbors {
+                        sum = sum + neighbor.value(values, s, c, self.outside);
+                    }
+                    let rate = diffusion[s] * ((sum - four * c) * self.inverse_dx2) + reaction[s];
+                    row.set(j, s, output(c, rate));
+                }
+                continue;
+            }
+            for (s, &c) in cell.iter().enumerate() {
+                let value = |neighbor: Neighbor| neighbor.value(values, s, c, self.outside);
+                let around = [
+                    value(neighbors[0]),
+                    value(neighbors[1]),
+                    value(neighbors[2]),
+                    value(neighbors[3]),
+                ];
+                let laplacian = match (self.anisotropy, diagonals) {
+                    (Some(tensor), Some(d)) => {
+                        let corners = [value(d[0]), value(d[1]), value(d[2]), value(d[3])];
+                        anisotropic_laplacian(tensor, c, around, corners, self.inverse_dx2)
+                    }
+                    _ => (around[0] + around[1] + around[2] + around[3] - four * c) * self.inverse_dx2,
+                };
+                let mut rate = diffusion[s] * laplacian + reaction[s];
+                if let Some(weights) = upwind {
+                    for (&weight, &neighbor) in weights.iter().zip(&around) {
+                        rate = rate + weight * (neighbor - c);
+                    }
+                }
+                row.set(j, s, output(c, rate));
+            }
+        }
+    }
+}
+
+/// 隣のセルの値をどこから取るか
+#[derive(Clone, Copy)]
+pub(crate) enum Neighbor {
+    /// 添字のセルの値
+    Cell(usize),
+    /// 中心のセルと同じ値(出入りが0)
+    Center,
+    /// 格子の外側の値(ディリクレ境界)
+    Outside,
+}
+
+impl Neighbor {
+    /// `component`番目の成分の値
+    ///
+    /// # Arguments
+    /// * `state` - 状態
+    /// * `component` - 成分の番号
+    /// * `center` - 中心のセルの値
+    /// * `outside` - 格子の外側の値。成分の数より短ければ、足りない成分には最後の値を使う
+    fn value<T: Real, V: Values<T>>(self, state: V, component: usize, center: T, outside: Option<&[T]>) -> T {
+        self.value_with(|n| state.get(n, component), component, center, outside)
+    }
+
+    /// `value`と同じだが、添字のセルの値を`cell`で読む
+    #[inline]
+    pub(crate) fn value_with<T, F>(self, cell: F, component: usize, center: T, outside: Option<&[T]>) -> T
+    where
+        T: Real,
+        F: Fn(usize) -> T,
+    {
+        match (self, outside) {
+            (Neighbor::Cell(n), _) => cell(n),
+            (Neighbor::Outside, Some(outside)) => outside[component.min(outside.len() - 1)],
+            _ => center,
+        }
+    }
+}
+
+/// ディリクレ境界で格子の外側に置く値。`values`がなければ(u, v)を`pair`に書いて使う
+fn outside_values<'a, T: Real>(
+    boundary: Boundary,
+    values: &'a Option<Vec<T>>,
+    pair: &'a mut [T; 2],
+) -> Option<&'a [T]> {
+    match (boundary, values) {
+        (Boundary::Dirichlet(_, _), &Some(ref values)) => Some(values),
+        (Boundary::Dirichlet(u, v), &None) => {
+            *pair = [cast(u), cast(v)];
+            Some(pair)
+        }
+        _ => None,
+    }
+}
+
+/// `out = base + Σ coefficient * rate`
+fn combine<T: Real, S: Fields<T>>(out: &mut S, base: &S, terms: &[(T, &S)]) {
+    let base = base.slices();
+    let mut out = out.slices_mut();
+    for part in 0..base.parts() {
+        combine_slice(out.part_mut(part), base.part(part), terms, |rate| rate.slices().part(part));
+    }
+}
+
+fn combine_slice<'a, T, S, F>(out: &mut [T], base: &[T], terms: &[(T, &'a S)], part: F)
+where
+    T: Real,
+    F: Fn(&'a S) -> &'a [T],
+{
+    // 項は高々4つなので、スライスはスタック上の配列に置く
+    let mut slices: [(T, &[T]); 4] = [(T::zero(), &[]); 4];
+    for (slot, &(coefficient, rate)) in slices.iter_mut().zip(terms) {
+        *slot = (coefficient, part(rate));
+    }
+    let slices = &slices[..terms.len()];
+    out.par_iter_mut().enumerate().for_each(|(i, o)| {
+        let mut value = base[i];
+        for &(coefficient, rate) in slices {
+            value = value + coefficient * rate[i];
+        }
+        *o = value;
+    });
+}
+
+/// 2つの状態の要素ごとの差の絶対値の最大値。どちらかにNaNがあればNaN
+fn max_difference<T: Real, S: Fields<T>>(a: &S, b: &S) -> T {
+    let (a, b) = (a.slices(), b.slices());
+    let mut max = T::zero();
+    for part in 0..a.parts() {
+        let difference = a
+            .part(part)
+            .par_iter()
+            .zip(b.part(part).par_iter())
+            .map(|(&p, &q)| (p - q).abs())
+            .reduce(T::zero, |m, d| if m.is_nan() || d.is_nan() { T::nan() } else { m.max(d) });
+        if difference.is_nan() {
+            return T::nan();
+        }
+        max = max.max(difference);
+    }
+    max
+}
+
+/// 長さ`len`の軸で`index`から`offset`(±1)だけ隣の添字。周期境界でなければ外側はNone
+pub(crate) fn neighbor_index(index: usize, len: usize, offset: isize, periodic: bool) -> Option<usize> {
+    let neighbor = index as isize + offset;
+    if neighbor >= 0 && neighbor < len as isize {
+        Some(neighbor as usize)
+    } else if periodic {
+        Some(((neighbor + len as isize) % len as isize) as usize)
+    } else {
+        None
+    }
+}
+
+/// 行ごとに連続したメモリ配置(C order)にする
+pub(crate) fn standard_layout<T: Clone, D: Dimension>(array: Array<T, D>) -> Array<T, D> {
+    if array.is_standard_layout() {
+        array
+    } else {
+        Array::from_shape_vec(array.raw_dim(), array.iter().cloned().collect()).unwrap()
+    }
+}
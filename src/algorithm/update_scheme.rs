@@ -0,0 +1,168 @@
+use algorithm::cellular_automata::next_cell_state as next_ca_cell_state;
+use algorithm::game_of_life::next_cell_state as next_life_cell_state;
+use ndarray::Array1;
+use rand::{Rng, SeedableRng, StdRng};
+use std::mem;
+
+/// セルの更新方式
+/// `cellular_automata`や`game_of_life`は全セルを同時に(同期的に)、決定論的に更新するが、
+/// 更新のタイミングやルールの適用にゆらぎを入れたときにパターンがどう変わるか(頑健性)を調べるために使う
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UpdateScheme {
+    /// 全セルを同時に更新する(`cellular_automata`や`game_of_life`と同じ)
+    Synchronous,
+    /// ランダムに選んだセルを1つずつその場で更新する。1ステップでセルの数だけ(重複を許して)選ぶ
+    RandomSequential,
+    /// 先頭のセルから順番に1つずつその場で更新する
+    FixedSweep,
+    /// 全セルの次の状態を同時に計算するが、各セルは確率alphaでしか更新されない
+    AlphaAsynchronous(f64),
+    /// 全セルを同時に更新するが、ルールの出力が確率pで反転する
+    Noisy(f64),
+}
+
+/// 更新方式と乱数生成器をまとめたもの
+/// 乱数のseedを指定するので、同じseedからは同じ結果が得られる
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::update_scheme::{UpdateScheme, Updater};
+/// use ndarray::Array1;
+///
+/// let initial = Array1::<u32>::from_vec(vec![0, 0, 0, 1, 0, 0, 1, 1, 0, 1]);
+/// let mut state1 = (initial.clone(), Array1::<u32>::zeros(10));
+/// let mut state2 = (initial.clone(), Array1::<u32>::zeros(10));
+/// let mut updater1 = Updater::new(UpdateScheme::AlphaAsynchronous(0.5), 42);
+/// let mut updater2 = Updater::new(UpdateScheme::AlphaAsynchronous(0.5), 42);
+/// for _ in 0..10 {
+///     updater1.cellular_automata(&mut state1, 110, 10);
+///     updater2.cellular_automata(&mut state2, 110, 10);
+/// }
+/// assert_eq!(state1.0, state2.0);
+/// ```
+pub struct Updater {
+    scheme: UpdateScheme,
+    rng: StdRng,
+}
+
+impl Updater {
+    /// Updaterインスタンスを生成する
+    ///
+    /// # Arguments
+    /// * `scheme` - 更新方式
+    /// * `seed` - 乱数のseed
+    pub fn new(scheme: UpdateScheme, seed: usize) -> Updater {
+        Updater {
+            scheme: scheme,
+            rng: StdRng::from_seed(&[seed][..]),
+        }
+    }
+
+    /// 更新方式
+    pub fn scheme(&self) -> UpdateScheme {
+        self.scheme
+    }
+
+    /// `cellular_automata`を更新方式に従って1ステップ分計算する
+    /// 引数は`cellular_automata`と同じなので`ArrayVisualizer::draw_loop`にclosureとして渡せる
+    ///
+    /// # Arguments
+    /// * `state` - 現在の状態と次の状態
+    /// * `rule` - ウルフラムのルールコーディングの数字
+    /// * `space_size` - セルの数
+    pub fn cellular_automata(&mut self, state: &mut (Array1<u32>, Array1<u32>), rule: u8, space_size: usize) {
+        let current_state = &mut state.0;
+        let next_state = &mut state.1;
+        let neighbor =
+            |s: &Array1<u32>, i: usize| (s[(i + space_size - 1) % space_size], s[i], s[(i + 1) % space_size]);
+        match self.scheme {
+            UpdateScheme::RandomSequential => {
+                for _ in 0..space_size {
+                    let i = self.rng.gen_range(0, space_size);
+                    let (l, c, r) = neighbor(current_state, i);
+                    current_state[i] = next_ca_cell_state(l, c, r, rule);
+                }
+            }
+            UpdateScheme::FixedSweep => {
+                for i in 0..space_size {
+                    let (l, c, r) = neighbor(current_state, i);
+                    current_state[i] = next_ca_cell_state(l, c, r, rule);
+                }
+            }
+            _ => {
+                for i in 0..space_size {
+                    let (l, c, r) = neighbor(current_state, i);
+                    let next = next_ca_cell_state(l, c, r, rule);
+                    next_state[i] = self.apply(c == 1, next == 1) as u32;
+                }
+                mem::swap(current_state, next_state);
+            }
+        }
+    }
+
+    /// `game_of_life`を更新方式に従って1ステップ分計算する
+    /// 引数は`game_of_life`と同じなので`GameOfLifeVisualizer::draw_loop`にclosureとして渡せる
+    ///
+    /// # Arguments
+    /// * `state` - 現在の状態
+    /// * `next_state` - 次の瞬間の状態
+    /// * `height` - セルの縦の数
+    /// * `width` - セルの横の数
+    pub fn game_of_life(
+        &mut self,
+        state: &mut Vec<Vec<u8>>,
+        next_state: &mut Vec<Vec<u8>>,
+        height: usize,
+        width: usize,
+    ) {
+        match self.scheme {
+            UpdateScheme::RandomSequential => {
+                for _ in 0..height * width {
+                    let i = self.rng.gen_range(0, height);
+                    let j = self.rng.gen_range(0, width);
+                    state[i][j] = next_life_cell_state(state, i, j, height, width);
+                }
+            }
+            UpdateScheme::FixedSweep => {
+                for i in 0..height {
+                    for j in 0..width {
+                        state[i][j] = next_life_cell_state(state, i, j, height, width);
+                    }
+                }
+            }
+            _ => {
+                for i in 0..height {
+                    for j in 0..width {
+                        let next = next_life_cell_state(state, i, j, height, width);
+                        next_state[i][j] = self.apply(state[i][j] == 1, next == 1) as u8;
+                    }
+                }
+                mem::swap(state, next_state);
+            }
+        }
+    }
+
+    /// 同時に更新する方式において、現在の生死`current`とルールの出力`next`から実際に採用する生死を決める
+    fn apply(&mut self, current: bool, next: bool) -> bool {
+        match self.scheme {
+            UpdateScheme::AlphaAsynchronous(alpha) => {
+                if self.rng.gen::<f64>() < alpha {
+                    next
+                } else {
+                    current
+                }
+            }
+            UpdateScheme::Noisy(p) => {
+                if self.rng.gen::<f64>() < p {
+                    !next
+                } else {
+                    next
+                }
+            }
+            _ => next,
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use failure;
+use format::checksum::{adler32, crc32};
+use ndarray::Array2;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use visualizer::matrix_visualizer::Matrix;
+
+/// 8bitグレースケールの画像
+/// 画素は左上から行ごとに並んでいて、0が黒、255が白
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrayImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    /// 全画素が`value`の画像を生成する
+    pub fn new(width: usize, height: usize, value: u8) -> GrayImage {
+        GrayImage {
+            width: width,
+            height: height,
+            pixels: vec![value; width * height],
+        }
+    }
+
+    /// セルの生死から画像を生成する。生きている「1」のセルが黒、死んでいる「0」のセルが白になる
+    ///
+    /// # Example
+    /// ```
+    /// extern crate ndarray;
+    /// extern crate my_alife;
+    ///
+    /// use my_alife::format::image::GrayImage;
+    /// use ndarray::arr2;
+    ///
+    /// let image = GrayImage::from_cells(&arr2(&[[0, 1], [1, 0]]));
+    /// assert_eq!(image.pixel(1, 0), 0);
+    /// assert_eq!(image.pixel(0, 0), 255);
+    /// ```
+    pub fn from_cells(cells: &Array2<u32>) -> GrayImage {
+        let (height, width) = cells.dim();
+        let pixels = cells.iter().map(|c| if *c == 0 { 255 } else { 0 }).collect();
+        GrayImage {
+            width: width,
+            height: height,
+            pixels: pixels,
+        }
+    }
+
+    /// 各要素が画素値(0.0が黒、1.0が白)を意味する2次元配列から画像を生成する
+    /// 0.0未満と1.0より大きい値は丸められる
+    pub fn from_matrix(matrix: &Matrix<f32>) -> GrayImage {
+        let (height, width) = matrix.dim();
+        let pixels = matrix
+            .iter()
+            .map(|e| {
+                (if *e < 0.0 {
+                    0.0
+                } else if *e > 1.0 {
+                    1.0
+                } else {
+                    *e
+                } * 255.0) as u8
+            })
+            .collect();
+        GrayImage {
+            width: width,
+            height: height,
+            pixels: pixels,
+        }
+    }
+
+    /// 拡張子(`pbm`, `pgm`)に応じた形式で読み込む
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<GrayImage, failure::Error> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "pbm" | "pgm" => Self::read_pnm(&mut BufReader::new(File::open(path)?)),
+            _ => Err(failure::err_msg(format!(
+                "unsupported image format: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// PBM(P1, P4)かPGM(P2, P5)を読み込む。PBMの黒は0、白は255になる
+    ///
+    /// # Example
+    /// ```
+    /// use my_alife::format::image::GrayImage;
+    ///
+    /// let mut pgm = b"P2\n# comment\n3 2\n15\n0 15 3\n15 0 15\n".as_ref();
+    /// let image = GrayImage::read_pnm(&mut pgm).unwrap();
+    /// assert_eq!((image.width(), image.height()), (3, 2));
+    /// assert_eq!(image.pixels(), &[0, 255, 51, 255, 0, 255]);
+    ///
+    /// let mut bytes = Vec::new();
+    /// image.write_pbm(&mut bytes).unwrap();
+    /// let pbm = GrayImage::read_pnm(&mut bytes.as_slice()).unwrap();
+    /// assert_eq!(pbm.pixels(), &[0, 255, 0, 255, 0, 255]);
+    /// ```
+    pub fn read_pnm<R: Read>(reader: &mut R) -> Result<GrayImage, failure::Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut position = 0;
+        let magic = pnm_token(&data, &mut position)?;
+        let width = pnm_number(&data, &mut position)?;
+        let height = pnm_number(&data, &mut position)?;
+        let max_value = match magic.as_str() {
+            "P1" | "P4" => 1,
+            "P2" | "P5" => pnm_number(&data, &mut position)?,
+            _ => return Err(failure::err_msg(format!("unsupported PNM type: {}", magic))),
+        };
+        if max_value == 0 || max_value > 255 {
+            return Err(failure::err_msg(format!("unsupported max value: {}", max_value)));
+        }
+        let scale = |value: usize| (value.min(max_value) * 255 / max_value) as u8;
+
+        let pixels = match magic.as_str() {
+            "P1" => (0..width * height)
+                .map(|_| pnm_bit(&data, &mut position).map(|bit| if bit { 0 } else { 255 }))
+                .collect::<Result<Vec<_>, _>>()?,
+            "P2" => (0..width * height)
+                .map(|_| pnm_number(&data, &mut position).map(&scale))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => {
+                // バイナリ形式はヘッダーの後の空白1文字の次から画素が始まる
+                let body = data.get(position + 1..).unwrap_or(&[]);
+                if magic == "P4" {
+                    let row_bytes = (width + 7) / 8;
+                    if body.len() < row_bytes * height {
+                        return Err(failure::err_msg("unexpected end of PBM data"));
+                    }
+                    (0..height)
+                        .flat_map(|y| (0..width).map(move |x| (y, x)))
+                        .map(|(y, x)| {
+                            if body[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0 {
+                                0
+                            } else {
+                                255
+                            }
+                        })
+                        .collect()
+                } else {
+                    if body.len() < width * height {
+                        return Err(failure::err_msg("unexpected end of PGM data"));
+                    }
+                    body[..width * height].iter().map(|p| scale(*p as usize)).collect()
+                }
+            }
+        };
+        Ok(GrayImage {
+            width: width,
+            height: height,
+            pixels: pixels,
+        })
+    }
+
+    /// 画素値が`level`未満(暗い)の画素を`true`にした2次元配列(行, 列)
+    /// 障害物のマスクなどに使う
+    pub fn threshold(&self, level: u8) -> Array2<bool> {
+        Array2::from_shape_fn((self.height, self.width), |(y, x)| self.pixel(x, y) < level)
+    }
+
+    /// 横幅
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// 縦幅
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// 画素値を左上から行ごとに並べたもの
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// (`x`, `y`)の画素値
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * self.width + x]
+    }
+
+    /// (`x`, `y`)の画素値を変更する。画像の外側の場合は何もしない
+    pub fn set_pixel(&mut self, x: usize, y: usize, value: u8) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = value;
+        }
+    }
+
+    /// `other`を左上が(`x`, `y`)になるように貼り付ける。はみ出した部分は捨てる
+    pub fn paste(&mut self, other: &GrayImage, x: usize, y: usize) {
+        for oy in 0..other.height {
+            for ox in 0..other.width {
+                self.set_pixel(x + ox, y + oy, other.pixel(ox, oy));
+            }
+        }
+    }
+
+    /// 拡張子(`pbm`, `pgm`, `png`)に応じた形式で保存する
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), failure::Error> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        let mut writer = BufWriter::new(File::create(path)?);
+        match extension.as_str() {
+            "pbm" => self.write_pbm(&mut writer),
+            "pgm" => self.write_pgm(&mut writer),
+            "png" => self.write_png(&mut writer),
+            _ => Err(failure::err_msg(format!(
+                "unsupported image format: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// バイナリ形式のPBM(P4)で書き出す。画素値が128未満の画素を黒とする
+    pub fn write_pbm<W: Write>(&self, writer: &mut W) -> Result<(), failure::Error> {
+        write!(writer, "P4\n{} {}\n", self.width, self.height)?;
+        let row_bytes = (self.width + 7) / 8;
+        for y in 0..self.height {
+            let mut row = vec![0u8; row_bytes];
+            for x in 0..self.width {
+                if self.pixel(x, y) < 128 {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            writer.write_all(&row)?;
+        }
+        Ok(())
+    }
+
+    /// バイナリ形式のPGM(P5)で書き出す
+    pub fn write_pgm<W: Write>(&self, writer: &mut W) -> Result<(), failure::Error> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.pixels)?;
+        Ok(())
+    }
+
+    /// 8bitグレースケールのPNGで書き出す
+    /// 圧縮はせず、deflateの無圧縮ブロックにそのまま詰める
+    pub fn write_png<W: Write>(&self, writer: &mut W) -> Result<(), failure::Error> {
+        writer.write_all(&[0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a])?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&be_u32(self.width as u32));
+        header.extend_from_slice(&be_u32(self.height as u32));
+        // bit depth 8, color type 0(グレースケール), 圧縮方式, フィルタ方式, インターレースなし
+        header.extend_from_slice(&[8, 0, 0, 0, 0]);
+        write_png_chunk(writer, b"IHDR", &header)?;
+
+        // 各行の先頭にフィルタ(0: なし)を置く
+        let mut raw = Vec::with_capacity((self.width + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+        write_png_chunk(writer, b"IDAT", &zlib_stored(&raw))?;
+        write_png_chunk(writer, b"IEND", &[])?;
+        Ok(())
+    }
+}
+
+/// PNMのヘッダーや画素値の次のトークンを読む。空白と`#`から行末までのコメントは読み飛ばす
+fn pnm_token(data: &[u8], position: &mut usize) -> Result<String, failure::Error> {
+    while *position < data.len() {
+        match data[*position] {
+            b'#' => {
+                while *position < data.len() && data[*position] != b'\n' {
+                    *position += 1;
+                }
+            }
+            c if (c as char).is_whitespace() => *position += 1,
+            _ => break,
+        }
+    }
+    let start = *position;
+    while *position < data.len() && !(data[*position] as char).is_whitespace() && data[*position] != b'#' {
+        *position += 1;
+    }
+    if start == *position {
+        return Err(failure::err_msg("unexpected end of PNM data"));
+    }
+    Ok(String::from_utf8_lossy(&data[start..*position]).into_owned())
+}
+
+fn pnm_number(data: &[u8], position: &mut usize) -> Result<usize, failure::Error> {
+    let token = pnm_token(data, position)?;
+    token
+        .parse()
+        .map_err(|_| failure::err_msg(format!("invalid number in PNM data: {}", token)))
+}
+
+/// P1の画素を1つ読む。P1の画素は空白で区切られていなくても良い
+fn pnm_bit(data: &[u8], position: &mut usize) -> Result<bool, failure::Error> {
+    while *position < data.len() && (data[*position] as char).is_whitespace() {
+        *position += 1;
+    }
+    match data.get(*position) {
+        Some(&b'0') => {
+            *position += 1;
+            Ok(false)
+        }
+        Some(&b'1') => {
+            *position += 1;
+            Ok(true)
+        }
+        _ => Err(failure::err_msg("invalid PBM data")),
+    }
+}
+
+fn be_u32(value: u32) -> [u8; 4] {
+    [
+        (value >> 24) as u8,
+        (value >> 16) as u8,
+        (value >> 8) as u8,
+        value as u8,
+    ]
+}
+
+fn write_png_chunk<W: Write>(writer: &mut W, chunk_type: &[u8; 4], data: &[u8]) -> Result<(), failure::Error> {
+    writer.write_all(&be_u32(data.len() as u32))?;
+    let mut body = Vec::with_capacity(data.len() + 4);
+    body.extend_from_slice(chunk_type);
+    body.extend_from_slice(data);
+    writer.write_all(&body)?;
+    writer.write_all(&be_u32(crc32(&body)))?;
+    Ok(())
+}
+
+/// 無圧縮ブロックだけからなるzlibストリームを作る
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 65535;
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[0x01, 0x00, 0x00, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(if is_final { 0x01 } else { 0x00 });
+        out.extend_from_slice(&[len as u8, (len >> 8) as u8, !len as u8, (!len >> 8) as u8]);
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&be_u32(adler32(data)));
+    out
+}
@@ -0,0 +1,169 @@
+use algorithm::mesh::TriangleMesh;
+use failure;
+use ndarray::Array1;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// Wavefront OBJ形式のメッシュを読み込む
+/// `v`(頂点)と`f`(面)だけを使い、それ以外の行は無視する。四角形以上の面は扇形に三角形に分割する。
+/// `f 1/2/3`のようなテクスチャ座標・法線つきの書き方や、負の(後ろから数える)添字も読める
+///
+/// # Example
+/// ```
+/// use my_alife::format::mesh::read_obj;
+///
+/// let mut obj = b"# square\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1 4//1\n".as_ref();
+/// let mesh = read_obj(&mut obj).unwrap();
+/// assert_eq!(mesh.vertices.len(), 4);
+/// assert_eq!(mesh.triangles, vec![[0, 1, 2], [0, 2, 3]]);
+///
+/// let mut broken = b"v 0 0 0\nf 1 2 3\n".as_ref();
+/// assert!(read_obj(&mut broken).is_err());
+/// ```
+pub fn read_obj<R: Read>(reader: &mut R) -> Result<TriangleMesh, failure::Error> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut vertices = Vec::new();
+    let mut triangles = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let error = |message: &str| failure::err_msg(format!("line {}: {}", number + 1, message));
+        let mut tokens = line.split('#').next().unwrap_or("").split_whitespace();
+        match tokens.next() {
+            Some("v") => {
+                let coordinates = tokens
+                    .take(3)
+                    .map(|t| t.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| error("invalid vertex coordinate"))?;
+                if coordinates.len() < 3 {
+                    return Err(error("vertex needs 3 coordinates"));
+                }
+                vertices.push([coordinates[0], coordinates[1], coordinates[2]]);
+            }
+            Some("f") => {
+                let indices = tokens
+                    .map(|t| {
+                        let index = t
+                            .split('/')
+                            .next()
+                            .and_then(|i| i.parse::<isize>().ok())
+                            .ok_or_else(|| error("invalid face index"))?;
+                        // 添字は1から始まり、負の数は直前までの頂点を後ろから数える
+                        let resolved = if index < 0 {
+                            vertices.len() as isize + index
+                        } else {
+                            index - 1
+                        };
+                        if resolved < 0 || resolved >= vertices.len() as isize {
+                            return Err(error("face index out of range"));
+                        }
+                        Ok(resolved as usize)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if indices.len() < 3 {
+                    return Err(error("face needs at least 3 vertices"));
+                }
+                for k in 1..indices.len() - 1 {
+                    triangles.push([indices[0], indices[k], indices[k + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(TriangleMesh {
+        vertices: vertices,
+        triangles: triangles,
+    })
+}
+
+/// OBJファイルからメッシュを読み込む
+pub fn open_obj<P: AsRef<Path>>(path: P) -> Result<TriangleMesh, failure::Error> {
+    read_obj(&mut BufReader::new(File::open(path)?))
+}
+
+/// 頂点ごとの値`field`を色にしたメッシュを、ASCII形式のPLYで書き出す
+/// 値は`range`(最小, 最大)を黒から白に割り当て、範囲の外側は丸める。
+/// 色(`red`, `green`, `blue`)のほかに、値そのものも`quality`として書く
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::mesh::TriangleMesh;
+/// use my_alife::format::mesh::write_ply;
+/// use ndarray::arr1;
+///
+/// let mesh = TriangleMesh {
+///     vertices: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+///     triangles: vec![[0, 1, 2]],
+/// };
+/// let mut ply = Vec::new();
+/// write_ply(&mut ply, &mesh, &arr1(&[0.0, 0.5, 2.0]), (0.0, 1.0)).unwrap();
+/// let ply = String::from_utf8(ply).unwrap();
+/// assert!(ply.contains("element vertex 3\n"));
+/// assert!(ply.contains("\n1 0 0 127 127 127 0.5\n"));
+/// assert!(ply.contains("\n0 1 0 255 255 255 2\n"));
+/// assert!(ply.ends_with("\n3 0 1 2\n"));
+/// ```
+pub fn write_ply<W: Write>(
+    writer: &mut W,
+    mesh: &TriangleMesh,
+    field: &Array1<f32>,
+    range: (f32, f32),
+) -> Result<(), failure::Error> {
+    if field.len() != mesh.vertices.len() {
+        return Err(failure::err_msg(format!(
+            "field length mismatch: expected {}, found {}",
+            mesh.vertices.len(),
+            field.len()
+        )));
+    }
+    write!(
+        writer,
+        "ply\n\
+         format ascii 1.0\n\
+         element vertex {}\n\
+         property float x\n\
+         property float y\n\
+         property float z\n\
+         property uchar red\n\
+         property uchar green\n\
+         property uchar blue\n\
+         property float quality\n\
+         element face {}\n\
+         property list uchar int vertex_indices\n\
+         end_header\n",
+        mesh.vertices.len(),
+        mesh.triangles.len()
+    )?;
+    let width = range.1 - range.0;
+    for (vertex, value) in mesh.vertices.iter().zip(field.iter()) {
+        let ratio = if width != 0.0 { (value - range.0) / width } else { 0.0 };
+        let gray = (ratio.max(0.0).min(1.0) * 255.0) as u8;
+        writeln!(
+            writer,
+            "{} {} {} {} {} {} {}",
+            vertex[0], vertex[1], vertex[2], gray, gray, gray, value
+        )?;
+    }
+    for triangle in &mesh.triangles {
+        writeln!(writer, "3 {} {} {}", triangle[0], triangle[1], triangle[2])?;
+    }
+    Ok(())
+}
+
+/// 頂点ごとの値`field`を色にしたメッシュをPLYファイルに保存する
+pub fn save_ply<P: AsRef<Path>>(
+    path: P,
+    mesh: &TriangleMesh,
+    field: &Array1<f32>,
+    range: (f32, f32),
+) -> Result<(), failure::Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_ply(&mut writer, mesh, field, range)?;
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,12 @@
+/// チェックサム
+mod checksum;
+/// 画像ファイル(PBM/PGM/PNG)の書き出し
+pub mod image;
+/// 三角形メッシュのファイル(OBJ/PLY)の読み書き
+pub mod mesh;
+/// NumPyの配列ファイル(.npy/.npz)の読み書き
+pub mod npy;
+/// 反応ネットワークの記述ファイル(TOMLのごく一部の書き方だけを使う簡易形式)の読み込み
+pub mod reaction_network;
+/// ParaViewなどで読めるVTKのImageData(.vti)と時系列(.pvd)の書き出し
+pub mod vtk;
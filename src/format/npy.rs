@@ -0,0 +1,598 @@
+use failure;
+use format::checksum::crc32;
+use ndarray::{Array, Array2, ArrayBase, Data, Dimension, IxDyn};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+const ZIP_LOCAL_HEADER: u32 = 0x0403_4b50;
+const ZIP_CENTRAL_HEADER: u32 = 0x0201_4b50;
+const ZIP_END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_END_OF_CENTRAL_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+
+/// `.npy`に読み書きできる要素の型
+pub trait Element: Copy {
+    /// NumPyのdtypeを表す文字列(`<f4`など)
+    const DESCR: &'static str;
+    /// 1要素のバイト数
+    const SIZE: usize;
+    /// リトルエンディアンで`out`の末尾に書き足す
+    fn write_le(self, out: &mut Vec<u8>);
+    /// リトルエンディアンの`SIZE`バイトから読む
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+impl Element for u8 {
+    const DESCR: &'static str = "|u1";
+    const SIZE: usize = 1;
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.push(self);
+    }
+    fn read_le(bytes: &[u8]) -> u8 {
+        bytes[0]
+    }
+}
+
+impl Element for u32 {
+    const DESCR: &'static str = "<u4";
+    const SIZE: usize = 4;
+    fn write_le(self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&le_u32(self));
+    }
+    fn read_le(bytes: &[u8]) -> u32 {
+        read_le_u32(bytes)
+    }
+}
+
+impl Element for f32 {
+    const DESCR: &'static str = "<f4";
+    const SIZE: usize = 4;
+    fn write_le(self, out: &mut Vec<u8>) {
+        self.to_bits().write_le(out);
+    }
+    fn read_le(bytes: &[u8]) -> f32 {
+        f32::from_bits(u32::read_le(bytes))
+    }
+}
+
+impl Element for f64 {
+    const DESCR: &'static str = "<f8";
+    const SIZE: usize = 8;
+    fn write_le(self, out: &mut Vec<u8>) {
+        let bits = self.to_bits();
+        (bits as u32).write_le(out);
+        ((bits >> 32) as u32).write_le(out);
+    }
+    fn read_le(bytes: &[u8]) -> f64 {
+        let bits = read_le_u32(bytes) as u64 | (read_le_u32(&bytes[4..]) as u64) << 32;
+        f64::from_bits(bits)
+    }
+}
+
+/// 配列を`.npy`形式(version 1.0, C order)で書き出す
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::format::npy::{read_npy, write_npy};
+/// use ndarray::{arr1, arr2, Array1, Array2};
+///
+/// let matrix = arr2(&[[0.25f32, -1.0, 3.5], [1e-7, 0.0, 2.0]]);
+/// let mut bytes = Vec::new();
+/// write_npy(&mut bytes, &matrix).unwrap();
+/// assert_eq!(bytes.len() % 64, 6 * 4 % 64);
+/// let read: Array2<f32> = read_npy(&mut bytes.as_slice()).unwrap();
+/// assert_eq!(read, matrix);
+///
+/// // dtypeや次元が違うとエラーになる
+/// assert!(read_npy::<_, u32, _>(&mut bytes.as_slice()).map(|a: Array2<u32>| a).is_err());
+/// assert!(read_npy::<_, f32, _>(&mut bytes.as_slice()).map(|a: Array1<f32>| a).is_err());
+///
+/// let cells = arr1(&[0u32, 1, 1, 0]);
+/// let mut bytes = Vec::new();
+/// write_npy(&mut bytes, &cells).unwrap();
+/// let read: Array1<u32> = read_npy(&mut bytes.as_slice()).unwrap();
+/// assert_eq!(read, cells);
+/// ```
+pub fn write_npy<W, T, S, D>(writer: &mut W, array: &ArrayBase<S, D>) -> Result<(), failure::Error>
+where
+    W: Write,
+    T: Element,
+    S: Data<Elem = T>,
+    D: Dimension,
+{
+    writer.write_all(&npy_bytes(array))?;
+    Ok(())
+}
+
+/// `.npy`形式の配列を読み込む
+/// dtypeが`T`と、次元数が`D`と一致しない場合はエラーになる。Fortran orderのファイルも読める
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::format::npy::read_npy;
+/// use ndarray::{arr2, Array2};
+///
+/// // np.asfortranarray(np.array([[1, 2, 3], [4, 5, 6]], dtype=np.uint8))を保存したもの
+/// let mut bytes = b"\x93NUMPY\x01\x00\x46\x00".to_vec();
+/// let header = "{'descr': '|u1', 'fortran_order': True, 'shape': (2, 3), }";
+/// bytes.extend_from_slice(format!("{:<69}\n", header).as_bytes());
+/// bytes.extend_from_slice(&[1, 4, 2, 5, 3, 6]);
+/// let read: Array2<u8> = read_npy(&mut bytes.as_slice()).unwrap();
+/// assert_eq!(read, arr2(&[[1, 2, 3], [4, 5, 6]]));
+/// ```
+pub fn read_npy<R, T, D>(reader: &mut R) -> Result<Array<T, D>, failure::Error>
+where
+    R: Read,
+    T: Element,
+    D: Dimension,
+{
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    parse_npy(&bytes)
+}
+
+/// 配列を`.npy`ファイルに保存する
+pub fn save_npy<P, T, S, D>(path: P, array: &ArrayBase<S, D>) -> Result<(), failure::Error>
+where
+    P: AsRef<Path>,
+    T: Element,
+    S: Data<Elem = T>,
+    D: Dimension,
+{
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_npy(&mut writer, array)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// `.npy`ファイルから配列を読み込む
+pub fn open_npy<P, T, D>(path: P) -> Result<Array<T, D>, failure::Error>
+where
+    P: AsRef<Path>,
+    T: Element,
+    D: Dimension,
+{
+    read_npy(&mut BufReader::new(File::open(path)?))
+}
+
+/// ライフゲームの状態(`game_of_life_visualizer::Matrix`)のような行のリストを、`.npy`に書き出せる2次元配列に変換する
+/// 行の長さがそろっていない場合はエラーになる
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::format::npy::{grid_to_array, read_npy, write_npy};
+/// use ndarray::{arr2, Array2};
+///
+/// let glider: Vec<Vec<u8>> = vec![vec![0, 1, 0], vec![0, 0, 1], vec![1, 1, 1]];
+/// let array = grid_to_array(&glider).unwrap();
+/// assert_eq!(array, arr2(&[[0u8, 1, 0], [0, 0, 1], [1, 1, 1]]));
+/// let mut bytes = Vec::new();
+/// write_npy(&mut bytes, &array).unwrap();
+/// let read: Array2<u8> = read_npy(&mut bytes.as_slice()).unwrap();
+/// assert_eq!(read, array);
+///
+/// assert!(grid_to_array(&[vec![0u8, 1], vec![1]]).is_err());
+/// assert_eq!(grid_to_array::<u8>(&[]).unwrap().dim(), (0, 0));
+/// ```
+pub fn grid_to_array<T: Element>(grid: &[Vec<T>]) -> Result<Array2<T>, failure::Error> {
+    let cols = grid.first().map_or(0, |row| row.len());
+    if let Some(i) = grid.iter().position(|row| row.len() != cols) {
+        return Err(failure::err_msg(format!(
+            "row {} has {} cells, but row 0 has {}",
+            i,
+            grid[i].len(),
+            cols
+        )));
+    }
+    let cells = grid.iter().flat_map(|row| row.iter().cloned()).collect();
+    Ok(Array2::from_shape_vec((grid.len(), cols), cells)?)
+}
+
+/// 複数の配列を`.npz`形式(無圧縮のZIP)で書き出す
+/// 配列は`名前.npy`というエントリになり、NumPyの`np.load`で名前をキーにして読める
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::algorithm::gray_scott::InitialCondition;
+/// use my_alife::format::npy::{NpzReader, NpzWriter};
+/// use ndarray::{arr1, Array1, Array2};
+///
+/// let (u, v) = InitialCondition::RandomSquares { count: 3, seed: 1 }.state((64, 48));
+/// let mut npz = NpzWriter::new(Vec::new());
+/// npz.add_array("u", &u).unwrap();
+/// npz.add_array("v", &v).unwrap();
+/// npz.add_array("generation", &arr1(&[120u32])).unwrap();
+/// let bytes = npz.finish().unwrap();
+///
+/// let mut npz = NpzReader::new(&mut bytes.as_slice()).unwrap();
+/// assert_eq!(npz.names(), vec!["u", "v", "generation"]);
+/// let read_u: Array2<f32> = npz.by_name("u").unwrap();
+/// let read_v: Array2<f32> = npz.by_name("v").unwrap();
+/// assert!(read_u.iter().zip(u.iter()).all(|(a, b)| a.to_bits() == b.to_bits()));
+/// assert!(read_v.iter().zip(v.iter()).all(|(a, b)| a.to_bits() == b.to_bits()));
+/// let generation: Array1<u32> = npz.by_name("generation").unwrap();
+/// assert_eq!(generation[0], 120);
+/// assert!(npz.by_name::<f32, _>("w").map(|a: Array2<f32>| a).is_err());
+/// ```
+pub struct NpzWriter<W: Write> {
+    writer: W,
+    offset: usize,
+    central_directory: Vec<u8>,
+    entries: usize,
+}
+
+impl<W: Write> NpzWriter<W> {
+    pub fn new(writer: W) -> NpzWriter<W> {
+        NpzWriter {
+            writer: writer,
+            offset: 0,
+            central_directory: Vec::new(),
+            entries: 0,
+        }
+    }
+
+    /// 配列を`name`という名前で追加する
+    pub fn add_array<T, S, D>(&mut self, name: &str, array: &ArrayBase<S, D>) -> Result<(), failure::Error>
+    where
+        T: Element,
+        S: Data<Elem = T>,
+        D: Dimension,
+    {
+        let data = npy_bytes(array);
+        let file_name = format!("{}.npy", name);
+        if data.len() > u32::max_value() as usize || self.offset > u32::max_value() as usize {
+            return Err(failure::err_msg(format!("array is too large for npz: {}", name)));
+        }
+        let crc = crc32(&data);
+
+        // 展開に必要なversion 2.0, フラグなし, 無圧縮, 時刻は0
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        common.extend_from_slice(&le_u32(crc));
+        common.extend_from_slice(&le_u32(data.len() as u32));
+        common.extend_from_slice(&le_u32(data.len() as u32));
+        common.extend_from_slice(&le_u16(file_name.len() as u16));
+        common.extend_from_slice(&[0, 0]);
+
+        let mut local = Vec::with_capacity(30 + file_name.len());
+        local.extend_from_slice(&le_u32(ZIP_LOCAL_HEADER));
+        local.extend_from_slice(&common);
+        local.extend_from_slice(file_name.as_bytes());
+        self.writer.write_all(&local)?;
+        self.writer.write_all(&data)?;
+
+        // 作成したversion 2.0, コメントなし, ディスク番号0, 属性なし
+        self.central_directory.extend_from_slice(&le_u32(ZIP_CENTRAL_HEADER));
+        self.central_directory.extend_from_slice(&[20, 0]);
+        self.central_directory.extend_from_slice(&common);
+        self.central_directory.extend_from_slice(&[0; 10]);
+        self.central_directory.extend_from_slice(&le_u32(self.offset as u32));
+        self.central_directory.extend_from_slice(file_name.as_bytes());
+
+        self.offset += local.len() + data.len();
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// セントラルディレクトリを書き出して、中の`writer`を返す
+    pub fn finish(mut self) -> Result<W, failure::Error> {
+        if self.entries > u16::max_value() as usize || self.offset > u32::max_value() as usize {
+            return Err(failure::err_msg("too many arrays for npz"));
+        }
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&le_u32(ZIP_END_OF_CENTRAL_DIRECTORY));
+        end.extend_from_slice(&[0, 0, 0, 0]);
+        end.extend_from_slice(&le_u16(self.entries as u16));
+        end.extend_from_slice(&le_u16(self.entries as u16));
+        end.extend_from_slice(&le_u32(self.central_directory.len() as u32));
+        end.extend_from_slice(&le_u32(self.offset as u32));
+        end.extend_from_slice(&[0, 0]);
+        self.writer.write_all(&self.central_directory)?;
+        self.writer.write_all(&end)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+impl NpzWriter<BufWriter<File>> {
+    /// `.npz`ファイルを作って書き出しを始める
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<NpzWriter<BufWriter<File>>, failure::Error> {
+        Ok(NpzWriter::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+/// `.npz`形式の配列の集まり
+/// 無圧縮(`np.savez`)のものだけを読める。`np.savez_compressed`で保存したものはエラーになる
+pub struct NpzReader {
+    bytes: Vec<u8>,
+    entries: Vec<NpzEntry>,
+}
+
+struct NpzEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    size: usize,
+    offset: usize,
+}
+
+impl NpzReader {
+    /// `.npz`のデータをすべて読み込み、セントラルディレクトリを解析する
+    pub fn new<R: Read>(reader: &mut R) -> Result<NpzReader, failure::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let entries = zip_entries(&bytes)?;
+        Ok(NpzReader {
+            bytes: bytes,
+            entries: entries,
+        })
+    }
+
+    /// `.npz`ファイルを開く
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<NpzReader, failure::Error> {
+        Self::new(&mut BufReader::new(File::open(path)?))
+    }
+
+    /// 含まれている配列の名前(拡張子`.npy`を除いたもの)
+    pub fn names(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|e| if e.name.ends_with(".npy") { &e.name[..e.name.len() - 4] } else { &e.name })
+            .collect()
+    }
+
+    /// `name`という名前の配列を読む
+    pub fn by_name<T: Element, D: Dimension>(&mut self, name: &str) -> Result<Array<T, D>, failure::Error> {
+        let file_name = format!("{}.npy", name);
+        let entry = self
+            .entries
+            .iter()
+            .find(|e| e.name == name || e.name == file_name)
+            .ok_or_else(|| failure::err_msg(format!("array not found in npz: {}", name)))?;
+        if entry.method != 0 {
+            return Err(failure::err_msg(format!(
+                "compressed npz entries are not supported: {}",
+                entry.name
+            )));
+        }
+        let header = self
+            .bytes
+            .get(entry.offset..entry.offset + 30)
+            .ok_or_else(|| failure::err_msg("unexpected end of npz data"))?;
+        if read_le_u32(header) != ZIP_LOCAL_HEADER {
+            return Err(failure::err_msg("invalid local header in npz"));
+        }
+        let start = entry.offset + 30 + read_le_u16(&header[26..]) as usize + read_le_u16(&header[28..]) as usize;
+        let data = self
+            .bytes
+            .get(start..start + entry.size)
+            .ok_or_else(|| failure::err_msg("unexpected end of npz data"))?;
+        if crc32(data) != entry.crc {
+            return Err(failure::err_msg(format!("CRC mismatch in npz: {}", entry.name)));
+        }
+        parse_npy(data)
+    }
+}
+
+fn npy_bytes<T, S, D>(array: &ArrayBase<S, D>) -> Vec<u8>
+where
+    T: Element,
+    S: Data<Elem = T>,
+    D: Dimension,
+{
+    let shape = match array.shape() {
+        [n] => format!("({},)", n),
+        shape => format!(
+            "({})",
+            shape.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        T::DESCR,
+        shape
+    );
+    // データの先頭が64バイト境界に揃うように空白で埋め、改行で終える
+    let unpadded = MAGIC.len() + 4 + header.len() + 1;
+    let padding = (64 - unpadded % 64) % 64;
+    header.extend((0..padding).map(|_| ' '));
+    header.push('\n');
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + header.len() + array.len() * T::SIZE);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&[1, 0]);
+    bytes.extend_from_slice(&le_u16(header.len() as u16));
+    bytes.extend_from_slice(header.as_bytes());
+    for e in array.iter() {
+        e.write_le(&mut bytes);
+    }
+    bytes
+}
+
+fn parse_npy<T: Element, D: Dimension>(bytes: &[u8]) -> Result<Array<T, D>, failure::Error> {
+    if bytes.len() < 10 || &bytes[..6] != MAGIC {
+        return Err(failure::err_msg("not a npy file"));
+    }
+    let (header_len, header_start) = match bytes[6] {
+        1 => (read_le_u16(&bytes[8..]) as usize, 10),
+        2 | 3 if bytes.len() >= 12 => (read_le_u32(&bytes[8..]) as usize, 12),
+        major => return Err(failure::err_msg(format!("unsupported npy version: {}.{}", major, bytes[7]))),
+    };
+    let header = bytes
+        .get(header_start..header_start + header_len)
+        .ok_or_else(|| failure::err_msg("unexpected end of npy header"))?;
+    let header = String::from_utf8_lossy(header);
+
+    let descr = header_value(&header, "descr")?.trim_matches(|c| c == '\'' || c == '"');
+    // 1バイトの型はエンディアンの表記が`|`以外でも同じ
+    let same_dtype = descr == T::DESCR || (T::SIZE == 1 && descr.get(1..) == T::DESCR.get(1..));
+    if !same_dtype {
+        return Err(failure::err_msg(format!(
+            "dtype mismatch: expected {}, found {}",
+            T::DESCR,
+            descr
+        )));
+    }
+    let fortran_order = match header_value(&header, "fortran_order")? {
+        "True" => true,
+        "False" => false,
+        value => return Err(failure::err_msg(format!("invalid fortran_order: {}", value))),
+    };
+    let shape_value = header_value(&header, "shape")?;
+    let shape = shape_value
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(|n| n.trim())
+        .filter(|n| !n.is_empty())
+        .map(|n| n.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| failure::err_msg(format!("invalid shape: {}", shape_value)))?;
+    if let Some(ndim) = D::NDIM {
+        if ndim != shape.len() {
+            return Err(failure::err_msg(format!(
+                "shape mismatch: expected {} dimensions, found {}",
+                ndim, shape_value
+            )));
+        }
+    }
+
+    let len = shape.iter().product::<usize>();
+    let data = &bytes[header_start + header_len..];
+    if data.len() < len * T::SIZE {
+        return Err(failure::err_msg("unexpected end of npy data"));
+    }
+    let elements = data.chunks(T::SIZE).take(len).map(T::read_le).collect();
+    let array = if fortran_order {
+        let reversed: Vec<usize> = shape.iter().rev().cloned().collect();
+        Array::from_shape_vec(IxDyn(&reversed), elements)?.reversed_axes()
+    } else {
+        Array::from_shape_vec(IxDyn(&shape), elements)?
+    };
+    Ok(array.into_dimensionality::<D>()?)
+}
+
+/// `{'descr': '<f4', 'fortran_order': False, 'shape': (3, 4), }`のようなヘッダーから`key`の値を取り出す
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str, failure::Error> {
+    let missing = || failure::err_msg(format!("missing {} in npy header", key));
+    let position = header
+        .find(&format!("'{}'", key))
+        .or_else(|| header.find(&format!("\"{}\"", key)))
+        .ok_or_else(missing)?;
+    let rest = &header[position + key.len() + 2..];
+    let rest = rest[rest.find(':').ok_or_else(missing)? + 1..].trim();
+    let end = match rest.chars().next() {
+        Some('(') => rest.find(')').map(|i| i + 1),
+        Some(quote @ '\'') | Some(quote @ '"') => rest[1..].find(quote).map(|i| i + 2),
+        _ => rest.find(|c| c == ',' || c == '}'),
+    };
+    Ok(rest[..end.ok_or_else(missing)?].trim())
+}
+
+/// ZIPのセントラルディレクトリからエントリの一覧を読む
+/// `np.savez`はZIP64の拡張フィールドを付けることがあるので、それも解釈する
+fn zip_entries(bytes: &[u8]) -> Result<Vec<NpzEntry>, failure::Error> {
+    let invalid = || failure::err_msg("invalid npz data");
+    let end = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .find(|&i| read_le_u32(&bytes[i..]) == ZIP_END_OF_CENTRAL_DIRECTORY)
+        .ok_or_else(invalid)?;
+    let mut count = read_le_u16(&bytes[end + 10..]) as u64;
+    let mut position = read_le_u32(&bytes[end + 16..]) as u64;
+    if end >= 20 && read_le_u32(&bytes[end - 20..]) == ZIP64_LOCATOR {
+        let record = read_le_u64(&bytes[end - 12..]) as usize;
+        let record = bytes.get(record..record + 56).ok_or_else(invalid)?;
+        if read_le_u32(record) != ZIP64_END_OF_CENTRAL_DIRECTORY {
+            return Err(invalid());
+        }
+        count = read_le_u64(&record[32..]);
+        position = read_le_u64(&record[48..]);
+    }
+
+    let mut entries = Vec::new();
+    let mut position = position as usize;
+    for _ in 0..count {
+        let header = bytes.get(position..position + 46).ok_or_else(invalid)?;
+        if read_le_u32(header) != ZIP_CENTRAL_HEADER {
+            return Err(invalid());
+        }
+        let name_len = read_le_u16(&header[28..]) as usize;
+        let extra_len = read_le_u16(&header[30..]) as usize;
+        let comment_len = read_le_u16(&header[32..]) as usize;
+        let name = bytes.get(position + 46..position + 46 + name_len).ok_or_else(invalid)?;
+        let extra = bytes
+            .get(position + 46 + name_len..position + 46 + name_len + extra_len)
+            .ok_or_else(invalid)?;
+
+        let mut size = read_le_u32(&header[24..]) as u64;
+        let mut compressed_size = read_le_u32(&header[20..]) as u64;
+        let mut offset = read_le_u32(&header[42..]) as u64;
+        let mut field = extra;
+        while field.len() >= 4 {
+            let id = read_le_u16(field);
+            let len = (read_le_u16(&field[2..]) as usize).min(field.len() - 4);
+            if id == 0x0001 {
+                // 元の値が0xffffffffのものだけが、この順に並んでいる
+                let mut values = field[4..4 + len].chunks(8).filter(|v| v.len() == 8).map(read_le_u64);
+                for value in [&mut size, &mut compressed_size, &mut offset].iter_mut() {
+                    if **value == 0xffff_ffff {
+                        **value = values.next().ok_or_else(invalid)?;
+                    }
+                }
+            }
+            field = &field[4 + len..];
+        }
+        let method = read_le_u16(&header[10..]);
+        if method == 0 && size != compressed_size {
+            return Err(invalid());
+        }
+        entries.push(NpzEntry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method: method,
+            crc: read_le_u32(&header[16..]),
+            size: size as usize,
+            offset: offset as usize,
+        });
+        position += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+fn le_u16(value: u16) -> [u8; 2] {
+    [value as u8, (value >> 8) as u8]
+}
+
+fn le_u32(value: u32) -> [u8; 4] {
+    [
+        value as u8,
+        (value >> 8) as u8,
+        (value >> 16) as u8,
+        (value >> 24) as u8,
+    ]
+}
+
+fn read_le_u16(bytes: &[u8]) -> u16 {
+    bytes[0] as u16 | (bytes[1] as u16) << 8
+}
+
+fn read_le_u32(bytes: &[u8]) -> u32 {
+    bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24
+}
+
+fn read_le_u64(bytes: &[u8]) -> u64 {
+    read_le_u32(bytes) as u64 | (read_le_u32(&bytes[4..]) as u64) << 32
+}
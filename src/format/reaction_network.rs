@@ -0,0 +1,260 @@
+use algorithm::reaction_diffusion::Discretization;
+use algorithm::reaction_network::{ReactionNetwork, Species};
+use algorithm::stencil::Boundary;
+use failure;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+
+/// 読み込み中の表
+enum Section {
+    /// どの表にも入っていない
+    Top,
+    /// `[species.名前]`。`species`の添字
+    Species(usize),
+    /// `[[reaction]]`。`reactions`の添字
+    Reaction(usize),
+    /// `[discretization]`
+    Discretization,
+}
+
+/// 値は数値か文字列のどちらか
+enum Value {
+    Number(f32),
+    Text(String),
+}
+
+/// 読み込み途中の化学種。`seed`を省略すると`initial`と同じになる
+struct SpeciesEntry {
+    line: usize,
+    name: String,
+    diffusion: Option<f32>,
+    initial: f32,
+    seed: Option<f32>,
+    noise: f32,
+}
+
+/// 読み込み途中の反応
+struct ReactionEntry {
+    line: usize,
+    equation: Option<String>,
+    rate: Option<f32>,
+}
+
+/// TOMLのごく一部の書き方だけを使う簡易形式で記述した反応ネットワークと離散化の設定を読み込む
+/// TOMLのパーサーではなく、1行に1つの表の見出しか`キー = 値`を書いたものだけを読む。
+/// 使えるのは`[species.名前]`、`[[reaction]]`、`[discretization]`の表と、数値か`"`で囲んだ文字列
+/// (エスケープは使えない)の値だけで、`'`で囲んだ文字列、ドットでつないだキー、インラインテーブル、
+/// 配列、複数行の値はどれもエラーになる。知らない表やキーもエラーになる。化学種は書いた順に並ぶ
+///
+/// * `[species.名前]` - `diffusion`(必須)、`initial`、`seed`(省略すると`initial`と同じ)、`noise`
+/// * `[[reaction]]` - `equation`(`"U + 2V -> 3V"`のような反応式)と`rate`(速度定数)。どちらも必須
+/// * `[discretization]` - `dx`、`dt`、`substeps`、`boundary`(`"periodic"`、`"neumann"`、`"dirichlet"`)。
+///   表を省略すると`Discretization::new(1.0, 0.01, 10)`になる
+///
+/// # Example
+/// ```
+/// use my_alife::algorithm::stencil::Boundary;
+/// use my_alife::format::reaction_network::read_network;
+///
+/// let mut toml = r#"
+/// [species.X]  # 自己触媒で増える
+/// diffusion = 0.1
+/// initial = 0.5
+///
+/// [species.Y]  # Xを食べて増える
+/// diffusion = 1e-2
+/// seed = 1.0
+///
+/// [[reaction]]
+/// equation = "X -> 2X"
+/// rate = 1.0
+///
+/// [[reaction]]
+/// equation = "X + Y -> 2Y"  # 捕食
+/// rate = 0.5
+///
+/// [discretization]
+/// dt = 0.05
+/// boundary = "neumann"
+/// "#.as_bytes();
+/// let (network, discretization) = read_network(&mut toml).unwrap();
+/// assert_eq!(network.species.len(), 2);
+/// assert_eq!((network.species[0].initial, network.species[0].seed), (0.5, 0.5));
+/// assert_eq!(network.reactions[1].reactants, vec![(0, 1), (1, 1)]);
+/// assert_eq!(network.reactions[1].products, vec![(1, 2)]);
+/// assert_eq!((discretization.dx, discretization.dt), (1.0, 0.05));
+/// assert_eq!(discretization.boundary, Boundary::Neumann);
+///
+/// let mut broken = b"[species.X]\ndiffusion = 0.1\n\n[[reaction]]\nequation = \"X + Z -> 2X\"\nrate = 1\n".as_ref();
+/// assert!(read_network(&mut broken).is_err());
+
+/// // TOMLでは書けても、この形式では読めない書き方
+/// let mut quoted = b"[species.X]\ndiffusion = 0.1\n\n[[reaction]]\nequation = 'X -> 2X'\nrate = 1\n".as_ref();
+/// assert!(read_network(&mut quoted).is_err());
+/// let mut dotted = b"[species]\nX.diffusion = 0.1\n".as_ref();
+/// assert!(read_network(&mut dotted).is_err());
+/// ```
+pub fn read_network<R: Read>(reader: &mut R) -> Result<(ReactionNetwork, Discretization), failure::Error> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    let mut species: Vec<SpeciesEntry> = Vec::new();
+    let mut reactions: Vec<ReactionEntry> = Vec::new();
+    let mut discretization = Discretization::new(1.0, 0.01, 10);
+    let mut section = Section::Top;
+    for (number, line) in text.lines().enumerate() {
+        let error = |message: &str| failure::err_msg(format!("line {}: {}", number + 1, message));
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with("[[") {
+            if line != "[[reaction]]" {
+                return Err(error(&format!("unknown array of tables {}", line)));
+            }
+            reactions.push(ReactionEntry {
+                line: number + 1,
+                equation: None,
+                rate: None,
+            });
+            section = Section::Reaction(reactions.len() - 1);
+            continue;
+        }
+        if line.starts_with('[') {
+            if !line.ends_with(']') {
+                return Err(error("unclosed table header"));
+            }
+            let name = line[1..line.len() - 1].trim();
+            section = if name == "discretization" {
+                Section::Discretization
+            } else if name.starts_with("species.") {
+                let name = name["species.".len()..].trim();
+                if species.iter().any(|s| s.name == name) {
+                    return Err(error(&format!("duplicate species {}", name)));
+                }
+                species.push(SpeciesEntry {
+                    line: number + 1,
+                    name: name.to_string(),
+                    diffusion: None,
+                    initial: 0.0,
+                    seed: None,
+                    noise: 0.0,
+                });
+                Section::Species(species.len() - 1)
+            } else {
+                return Err(error(&format!("unknown table [{}]", name)));
+            };
+            continue;
+        }
+
+        let mut pair = line.splitn(2, '=');
+        let (key, value) = match (pair.next(), pair.next()) {
+            (Some(key), Some(value)) => (key.trim(), parse_value(value.trim()).ok_or_else(|| error("invalid value"))?),
+            _ => return Err(error("expected `key = value`")),
+        };
+        let number_value = || match value {
+            Value::Number(x) => Ok(x),
+            Value::Text(_) => Err(error(&format!("{} must be a number", key))),
+        };
+        match section {
+            Section::Top => return Err(error(&format!("key {} outside of a table", key))),
+            Section::Species(s) => match key {
+                "diffusion" => species[s].diffusion = Some(number_value()?),
+                "initial" => species[s].initial = number_value()?,
+                "seed" => species[s].seed = Some(number_value()?),
+                "noise" => species[s].noise = number_value()?,
+                _ => return Err(error(&format!("unknown species key {}", key))),
+            },
+            Section::Reaction(r) => match (key, &value) {
+                ("equation", &Value::Text(ref equation)) => reactions[r].equation = Some(equation.clone()),
+                ("equation", _) => return Err(error("equation must be a string")),
+                ("rate", _) => reactions[r].rate = Some(number_value()?),
+                _ => return Err(error(&format!("unknown reaction key {}", key))),
+            },
+            Section::Discretization => match (key, &value) {
+                ("dx", _) => discretization.dx = number_value()?,
+                ("dt", _) => discretization.dt = number_value()?,
+                ("substeps", _) => {
+                    let substeps = number_value()?;
+                    if !(substeps >= 0.0 && substeps.fract() == 0.0) {
+                        return Err(error("substeps must be a non-negative integer"));
+                    }
+                    discretization.substeps = substeps as usize;
+                }
+                ("boundary", &Value::Text(ref boundary)) => {
+                    discretization.boundary = match boundary.as_str() {
+                        "periodic" => Boundary::Periodic,
+                        "neumann" => Boundary::Neumann,
+                        // 外側の値は化学種ごとの`initial`を使うので、ここの値は使わない
+                        "dirichlet" => Boundary::Dirichlet(0.0, 0.0),
+                        _ => return Err(error(&format!("unknown boundary {:?}", boundary))),
+                    }
+                }
+                ("boundary", _) => return Err(error("boundary must be a string")),
+                _ => return Err(error(&format!("unknown discretization key {}", key))),
+            },
+        }
+    }
+
+    let mut network = ReactionNetwork::new();
+    for entry in species {
+        let error = |message: &str| failure::err_msg(format!("line {}: {}", entry.line, message));
+        let diffusion = entry
+            .diffusion
+            .ok_or_else(|| error(&format!("species {} needs diffusion", entry.name)))?;
+        network
+            .add_species(Species {
+                name: entry.name.clone(),
+                diffusion: diffusion,
+                initial: entry.initial,
+                seed: entry.seed.unwrap_or(entry.initial),
+                noise: entry.noise,
+            })
+            .map_err(|e| error(&e.to_string()))?;
+    }
+    for entry in reactions {
+        let line = entry.line;
+        let error = |message: &str| failure::err_msg(format!("line {}: {}", line, message));
+        match (entry.equation, entry.rate) {
+            (Some(equation), Some(rate)) => network
+                .add_reaction(&equation, rate)
+                .map_err(|e| error(&e.to_string()))?,
+            _ => return Err(error("reaction needs equation and rate")),
+        }
+    }
+    network.validate()?;
+    Ok((network, discretization))
+}
+
+/// ファイルから反応ネットワークと離散化の設定を読み込む
+pub fn open_network<P: AsRef<Path>>(path: P) -> Result<(ReactionNetwork, Discretization), failure::Error> {
+    read_network(&mut BufReader::new(File::open(path)?))
+}
+
+/// 文字列の外側にある`#`から行末までを取り除く
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// `"`で囲んだ文字列か数値を読む
+fn parse_value(text: &str) -> Option<Value> {
+    if text.len() >= 2 && text.starts_with('"') && text.ends_with('"') {
+        let inner = &text[1..text.len() - 1];
+        if inner.contains('"') {
+            None
+        } else {
+            Some(Value::Text(inner.to_string()))
+        }
+    } else {
+        text.replace('_', "").parse::<f32>().ok().map(Value::Number)
+    }
+}
@@ -0,0 +1,277 @@
+use failure;
+use format::npy::Element;
+use ndarray::{ArrayBase, Data, Dimension};
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// `.vti`ファイルでのデータの書き方
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    /// 値を10進数の文字列で書く。人が読めるがファイルが大きい
+    Ascii,
+    /// 値をリトルエンディアンのバイナリでファイルの末尾(`AppendedData`)にまとめて書く
+    Appended,
+}
+
+/// VTKのImageData(等間隔格子)
+/// 2次元の配列`(行, 列)`は列がx、行がyに、3次元の配列`(層, 行, 列)`は層がzになる
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::format::vtk::{Encoding, ImageData};
+/// use ndarray::arr2;
+///
+/// let u = arr2(&[[1.0f32, 0.5, 0.25], [0.0, 1.0, 0.75]]);
+/// let v = arr2(&[[0.0f32, 0.25, 0.5], [1.0, 0.0, 0.125]]);
+/// let mut image = ImageData::from_uv(&u, &v).unwrap();
+/// image.spacing = [0.01, 0.01, 0.01];
+///
+/// let mut ascii = Vec::new();
+/// image.write(&mut ascii, Encoding::Ascii).unwrap();
+/// let ascii = String::from_utf8(ascii).unwrap();
+/// assert!(ascii.contains(r#"WholeExtent="0 2 0 1 0 0""#));
+/// assert!(ascii.contains(r#"Spacing="0.01 0.01 0.01""#));
+/// assert!(ascii.contains("  1 0.5 0.25\n          0 1 0.75\n"));
+///
+/// // 配列ごとに8バイトの長さと6個の値(4バイト)が末尾に並ぶ
+/// let mut appended = Vec::new();
+/// image.write(&mut appended, Encoding::Appended).unwrap();
+/// let start = appended.windows(4).position(|w| w == b"   _").unwrap() + 4;
+/// assert_eq!(&appended[start..start + 8], &[24, 0, 0, 0, 0, 0, 0, 0]);
+/// assert_eq!(&appended[start + 8..start + 12], &[0, 0, 0x80, 0x3f]);
+/// assert!(appended.ends_with(b"\n  </AppendedData>\n</VTKFile>\n"));
+///
+/// // 形の違う配列は追加できない
+/// assert!(image.add_field("w", &arr2(&[[0.0f32; 2]; 3])).is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageData {
+    /// x, y, zの点の数
+    dimensions: [usize; 3],
+    /// 点の間隔(x, y, z)
+    pub spacing: [f32; 3],
+    /// 原点の座標(x, y, z)
+    pub origin: [f32; 3],
+    /// 点ごとの値の名前と、xが最も速く変わる順に並べた値
+    fields: Vec<(String, Vec<f32>)>,
+}
+
+impl ImageData {
+    /// 配列の形`shape`(1〜3次元)に合わせた、値を持たないImageDataを作る
+    pub fn new(shape: &[usize]) -> Result<ImageData, failure::Error> {
+        if shape.is_empty() || shape.len() > 3 {
+            return Err(failure::err_msg(format!(
+                "VTK image data must have 1 to 3 dimensions, found {}",
+                shape.len()
+            )));
+        }
+        let mut dimensions = [1; 3];
+        for (d, n) in dimensions.iter_mut().zip(shape.iter().rev()) {
+            *d = *n;
+        }
+        Ok(ImageData {
+            dimensions: dimensions,
+            spacing: [1.0; 3],
+            origin: [0.0; 3],
+            fields: Vec::new(),
+        })
+    }
+
+    /// 反応拡散系の状態(`u`, `v`)を、`u`と`v`という名前の値として持つImageDataを作る
+    pub fn from_uv<S, D>(u: &ArrayBase<S, D>, v: &ArrayBase<S, D>) -> Result<ImageData, failure::Error>
+    where
+        S: Data<Elem = f32>,
+        D: Dimension,
+    {
+        let mut image = Self::new(u.shape())?;
+        image.add_field("u", u)?;
+        image.add_field("v", v)?;
+        Ok(image)
+    }
+
+    /// 点ごとの値を`name`という名前で追加する。配列の形が格子と違う場合はエラーになる
+    pub fn add_field<S, D>(&mut self, name: &str, array: &ArrayBase<S, D>) -> Result<(), failure::Error>
+    where
+        S: Data<Elem = f32>,
+        D: Dimension,
+    {
+        let shape = Self::new(array.shape())?.dimensions;
+        if shape != self.dimensions {
+            return Err(failure::err_msg(format!(
+                "field shape mismatch: expected {:?}, found {:?}",
+                self.dimensions, shape
+            )));
+        }
+        self.fields.push((name.to_string(), array.iter().cloned().collect()));
+        Ok(())
+    }
+
+    /// x, y, zの点の数
+    pub fn dimensions(&self) -> [usize; 3] {
+        self.dimensions
+    }
+
+    /// `.vti`(XML形式のImageData)として書き出す
+    pub fn write<W: Write>(&self, writer: &mut W, encoding: Encoding) -> Result<(), failure::Error> {
+        let [nx, ny, nz] = self.dimensions;
+        let extent = format!("0 {} 0 {} 0 {}", nx.max(1) - 1, ny.max(1) - 1, nz.max(1) - 1);
+        write!(
+            writer,
+            "<?xml version=\"1.0\"?>\n\
+             <VTKFile type=\"ImageData\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\">\n  \
+             <ImageData WholeExtent=\"{}\" Origin=\"{} {} {}\" Spacing=\"{} {} {}\">\n    \
+             <Piece Extent=\"{}\">\n",
+            extent,
+            self.origin[0],
+            self.origin[1],
+            self.origin[2],
+            self.spacing[0],
+            self.spacing[1],
+            self.spacing[2],
+            extent
+        )?;
+        match self.fields.first() {
+            Some(&(ref name, _)) => writeln!(writer, "      <PointData Scalars=\"{}\">", escape(name))?,
+            None => writeln!(writer, "      <PointData>")?,
+        }
+
+        let mut offset = 0;
+        for &(ref name, ref values) in &self.fields {
+            match encoding {
+                Encoding::Ascii => {
+                    writeln!(
+                        writer,
+                        "        <DataArray type=\"Float32\" Name=\"{}\" format=\"ascii\">",
+                        escape(name)
+                    )?;
+                    for line in values.chunks(nx.max(1)) {
+                        let line = line.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                        writeln!(writer, "          {}", line.join(" "))?;
+                    }
+                    writeln!(writer, "        </DataArray>")?;
+                }
+                Encoding::Appended => {
+                    writeln!(
+                        writer,
+                        "        <DataArray type=\"Float32\" Name=\"{}\" format=\"appended\" offset=\"{}\"/>",
+                        escape(name),
+                        offset
+                    )?;
+                    offset += 8 + values.len() * f32::SIZE;
+                }
+            }
+        }
+        write!(writer, "      </PointData>\n    </Piece>\n  </ImageData>\n")?;
+
+        if encoding == Encoding::Appended {
+            // 各配列の前にバイト数(header_typeのUInt64)を置き、`_`の直後から詰めて並べる
+            write!(writer, "  <AppendedData encoding=\"raw\">\n   _")?;
+            for &(_, ref values) in &self.fields {
+                let mut bytes = Vec::with_capacity(8 + values.len() * f32::SIZE);
+                let len = (values.len() * f32::SIZE) as u64;
+                (len as u32).write_le(&mut bytes);
+                ((len >> 32) as u32).write_le(&mut bytes);
+                for e in values {
+                    e.write_le(&mut bytes);
+                }
+                writer.write_all(&bytes)?;
+            }
+            write!(writer, "\n  </AppendedData>\n")?;
+        }
+        writeln!(writer, "</VTKFile>")?;
+        Ok(())
+    }
+
+    /// `.vti`ファイルに保存する
+    pub fn save<P: AsRef<Path>>(&self, path: P, encoding: Encoding) -> Result<(), failure::Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer, encoding)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// 時刻と`.vti`ファイルの組を並べた`.pvd`(Collection)を書き出す
+/// ファイルのパスは`.pvd`ファイルからの相対パスで書く
+///
+/// # Example
+/// ```
+/// use my_alife::format::vtk::write_pvd;
+///
+/// let mut pvd = Vec::new();
+/// write_pvd(&mut pvd, &[(0.0, "u_00000.vti".to_string()), (0.5, "u_00001.vti".to_string())]).unwrap();
+/// let pvd = String::from_utf8(pvd).unwrap();
+/// assert!(pvd.contains(r#"<DataSet timestep="0.5" group="" part="0" file="u_00001.vti"/>"#));
+/// ```
+pub fn write_pvd<W: Write>(writer: &mut W, steps: &[(f64, String)]) -> Result<(), failure::Error> {
+    write!(
+        writer,
+        "<?xml version=\"1.0\"?>\n\
+         <VTKFile type=\"Collection\" version=\"0.1\" byte_order=\"LittleEndian\">\n  \
+         <Collection>\n"
+    )?;
+    for &(time, ref file) in steps {
+        writeln!(
+            writer,
+            "    <DataSet timestep=\"{}\" group=\"\" part=\"0\" file=\"{}\"/>",
+            time,
+            escape(file)
+        )?;
+    }
+    write!(writer, "  </Collection>\n</VTKFile>\n")?;
+    Ok(())
+}
+
+/// `.vti`ファイルの連番と、それをまとめた`.pvd`ファイルを書き出す
+/// `directory`に`名前_00000.vti`, `名前_00001.vti`, ...と`名前.pvd`を作る。
+/// `.pvd`はステップを追加するたびに書き直すので、途中で止めてもそこまでの時系列をParaViewで開ける
+pub struct TimeSeries {
+    directory: PathBuf,
+    name: String,
+    encoding: Encoding,
+    steps: Vec<(f64, String)>,
+}
+
+impl TimeSeries {
+    /// 書き出し先のディレクトリがなければ作る
+    pub fn new<P: AsRef<Path>>(directory: P, name: &str, encoding: Encoding) -> Result<TimeSeries, failure::Error> {
+        fs::create_dir_all(directory.as_ref())?;
+        Ok(TimeSeries {
+            directory: directory.as_ref().to_path_buf(),
+            name: name.to_string(),
+            encoding: encoding,
+            steps: Vec::new(),
+        })
+    }
+
+    /// 時刻`time`の`image`を書き出し、`.pvd`を更新する。書き出した`.vti`のパスを返す
+    pub fn add(&mut self, time: f64, image: &ImageData) -> Result<PathBuf, failure::Error> {
+        let file = format!("{}_{:05}.vti", self.name, self.steps.len());
+        let path = self.directory.join(&file);
+        image.save(&path, self.encoding)?;
+        self.steps.push((time, file));
+
+        let mut writer = BufWriter::new(File::create(self.pvd_path())?);
+        write_pvd(&mut writer, &self.steps)?;
+        writer.flush()?;
+        Ok(path)
+    }
+
+    /// `.pvd`ファイルのパス
+    pub fn pvd_path(&self) -> PathBuf {
+        self.directory.join(format!("{}.pvd", self.name))
+    }
+}
+
+/// XMLの属性値に使えない文字を置き換える
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
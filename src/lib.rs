@@ -30,6 +30,8 @@ extern crate piston_window;
 
 /// パターン生成のアルゴリズム
 pub mod algorithm;
+/// シミュレーション結果のファイル入出力
+pub mod format;
 /// 複数の描画方法をまとめたもの
 pub mod visualizer;
 
@@ -1,3 +1,4 @@
+use algorithm::domain_filter::DomainFilter;
 use failure;
 use ndarray::{Array1, Array2};
 use visualizer::matrix_visualizer::{Matrix, MatrixVisualizer};
@@ -10,6 +11,8 @@ pub struct ArrayVisualizer {
     history_size: usize,
     time_index: usize,
     matrix: Matrix<f32>,
+    domain_filter: Option<DomainFilter>,
+    previous: Option<Array1<u32>>,
 }
 
 impl ArrayVisualizer {
@@ -35,9 +38,20 @@ impl ArrayVisualizer {
             history_size: history_size,
             time_index: 0,
             matrix: matrix,
+            domain_filter: None,
+            previous: None,
         })
     }
 
+    /// 領域を取り除いた時空図を表示するようにする
+    /// 領域に含まれるセルは白、それ以外のセル(粒子)は黒で表示される
+    ///
+    /// # Arguments
+    /// * `domain_filter` - 領域を取り除くフィルタ
+    pub fn set_domain_filter(&mut self, domain_filter: DomainFilter) {
+        self.domain_filter = Some(domain_filter);
+    }
+
     /// メインループ
     ///
     /// # Arguments
@@ -70,9 +84,17 @@ impl ArrayVisualizer {
     }
 
     fn update_matrix(&mut self, array: &Array1<u32>) {
-        self.matrix
-            .slice_mut(s![self.time_index, ..])
-            .assign(&(1.0 - array.map(|e| *e as f32)));
+        let row = match self.domain_filter {
+            Some(ref filter) => {
+                let mask = filter.row_mask(self.previous.as_ref().map(|p| p.view()), array.view());
+                Array1::from_vec(mask.into_iter().map(|m| if m { 1.0 } else { 0.0 }).collect())
+            }
+            None => 1.0 - array.map(|e| *e as f32),
+        };
+        self.matrix.slice_mut(s![self.time_index, ..]).assign(&row);
         self.time_index = (self.time_index + 1) % self.history_size;
+        if self.domain_filter.is_some() {
+            self.previous = Some(array.clone());
+        }
     }
 }
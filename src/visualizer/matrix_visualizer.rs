@@ -1,3 +1,6 @@
+use algorithm::gray_scott::GrayScottParams;
+use algorithm::reaction_diffusion::{ReactionDiffusion, Simulator};
+use algorithm::stencil::{cast, Real};
 use failure;
 use glium::{glutin, index, texture, Display, Program, Surface, VertexBuffer};
 use ndarray::{ArrayBase, Dim, OwnedRepr};
@@ -6,6 +9,11 @@ use std::io;
 use std::io::prelude::*;
 use visualizer::WindowStatus;
 
+// ウィンドウの初期の大きさ
+const WINDOW_SIZE: (u32, u32) = (600, 600);
+// 矢印キーを1回押したときにF, Kを変える量。Shiftを押しながらだと1/10になる
+const TUNING_STEP: f32 = 0.001;
+
 /// 直交座標系(XY座標系)を用いてvisualizeする構造体
 pub struct MatrixVisualizer {
     program: Program,
@@ -13,6 +21,99 @@ pub struct MatrixVisualizer {
     vertex_buffer: VertexBuffer<Vertex>,
     indices: index::NoIndices,
     display: Display,
+    title: String,
+    brush: Brush,
+    // ウィンドウの中のカーソルの位置(ウィンドウの幅と高さに対する比)。ウィンドウの外ならNone
+    cursor: Option<(f64, f64)>,
+    // 左ボタンを押しているか
+    dragging: bool,
+    window_size: (f64, f64),
+}
+
+/// マウスでドラッグしたところのvに値を書き込むブラシ
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::visualizer::matrix_visualizer::Brush;
+/// use ndarray::Array2;
+///
+/// let mut v = Array2::<f32>::zeros((8, 8));
+/// Brush { radius: 1.5, value: 0.25 }.paint(&mut v, (2.0, 6.0));
+/// assert_eq!(v[[2, 6]], 0.25);
+/// assert_eq!(v[[3, 7]], 0.25);
+/// assert_eq!(v[[4, 6]], 0.0);
+/// assert_eq!(v.iter().filter(|&&e| e > 0.0).count(), 9);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Brush {
+    /// 半径(セル数)
+    pub radius: f32,
+    /// 書き込む値
+    pub value: f32,
+}
+
+impl Default for Brush {
+    /// Gray-Scottモデルの初期状態の正方形と同じ値を、半径5セルで書き込む
+    fn default() -> Brush {
+        Brush {
+            radius: 5.0,
+            value: 0.25,
+        }
+    }
+}
+
+impl Brush {
+    /// `center`(行, 列)から半径`radius`以内のセルを`value`にする。格子からはみ出した部分は無視する
+    pub fn paint<T: Real>(&self, matrix: &mut Matrix<T>, center: (f32, f32)) {
+        let (rows, cols) = matrix.dim();
+        let range = |c: f32, len: usize| {
+            let low = (c - self.radius).floor().max(0.0) as usize;
+            let high = ((c + self.radius).ceil().max(0.0) as usize + 1).min(len);
+            low..high
+        };
+        for i in range(center.0, rows) {
+            for j in range(center.1, cols) {
+                let (di, dj) = (i as f32 - center.0, j as f32 - center.1);
+                if di * di + dj * dj <= self.radius * self.radius {
+                    matrix[[i, j]] = T::from(self.value).unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// `draw_loop_tunable`でキー操作によって変えられるパラメーター
+pub trait Tunable {
+    /// 供給率Fと除去率Kをそれぞれ`df`, `dk`だけ変え(負にはしない)、変えたあとの(F, K)を返す
+    fn nudge(&mut self, df: f32, dk: f32) -> (f32, f32);
+}
+
+impl Tunable for (f32, f32) {
+    fn nudge(&mut self, df: f32, dk: f32) -> (f32, f32) {
+        self.0 = (self.0 + df).max(0.0);
+        self.1 = (self.1 + dk).max(0.0);
+        *self
+    }
+}
+
+impl Tunable for GrayScottParams {
+    fn nudge(&mut self, df: f32, dk: f32) -> (f32, f32) {
+        self.f = (self.f + df).max(0.0);
+        self.k = (self.k + dk).max(0.0);
+        (self.f, self.k)
+    }
+}
+
+/// 1フレームの間に受け取った入力
+#[derive(Default)]
+struct Input {
+    close: bool,
+    reset: bool,
+    // F, Kを変える量
+    nudge: (f32, f32),
 }
 
 impl MatrixVisualizer {
@@ -40,7 +141,7 @@ impl MatrixVisualizer {
     ) -> Result<MatrixVisualizer, failure::Error> {
         let events_loop = glutin::EventsLoop::new();
         let window = glutin::WindowBuilder::new()
-            .with_dimensions((600, 600).into())
+            .with_dimensions(WINDOW_SIZE.into())
             .with_title(title);
         let context = glutin::ContextBuilder::new();
         let display = Display::new(window, context, &events_loop).unwrap();
@@ -58,9 +159,24 @@ impl MatrixVisualizer {
             vertex_buffer: vertex_buffer,
             indices: index::NoIndices(index::PrimitiveType::TrianglesList),
             display: display,
+            title: title.to_string(),
+            brush: Brush::default(),
+            cursor: None,
+            dragging: false,
+            window_size: (f64::from(WINDOW_SIZE.0), f64::from(WINDOW_SIZE.1)),
         })
     }
 
+    /// `draw_loop`でドラッグしたときに使うブラシ
+    pub fn brush(&self) -> Brush {
+        self.brush
+    }
+
+    /// `draw_loop`でドラッグしたときに使うブラシを変更する
+    pub fn set_brush(&mut self, brush: Brush) {
+        self.brush = brush;
+    }
+
     fn glsl(path: &str) -> Result<String, io::Error> {
         let mut contents = String::new();
         File::open(path)?.read_to_string(&mut contents)?;
@@ -96,9 +212,13 @@ impl MatrixVisualizer {
     }
 
     /// メインループ
+    /// ウィンドウの上で左ボタンを押している間は、カーソルの位置の`state.1`(v)に`brush`で値を書き込む。
+    /// `[`と`]`でブラシの半径を変え、`R`で`state`を最初の状態に戻す。
+    /// ブラシとリセットは`state`を書き換えるので、`update_fn`が`state`とは別に状態を持っている場合は効かない
     ///
     /// # Arguments
     /// * `state` - 初期状態
+    /// * `params` - `update_fn`に渡すパラメーター。Gray-Scottモデルなら`(f, k)`の組や、場所ごとに変わる`ParameterMaps`など
     /// * `unpdate_fn` - 描画する状態をどのように変更するかの関数
     ///
     /// ## move
@@ -275,7 +395,7 @@ impl MatrixVisualizer {
     ///
     /// ~~~
     /// * `mut state: (Matrix<f32>, Matrix<f32>)`の`state`は左辺である(つまり、bindingを変更できる)
-    /// * `Fn(&mut (Matrix<f32>, Matrix<f32>), f32, f32)` の`&mut`は右辺である。(つまり、引数の参照先のデータを変更できる)
+    /// * `Fn(&mut (Matrix<f32>, Matrix<f32>), &P)` の`&mut`は右辺である。(つまり、引数の参照先のデータを変更できる)
     ///
     ///
     /// # Example
@@ -299,34 +419,128 @@ impl MatrixVisualizer {
     ///   Array2::<f32>::ones((256, 256))                             //      |
     /// );                                                            //      |
     /// fn update_nothing(uv: &mut (Matrix<f32>, Matrix<f32>),        //      |
-    ///                   f: f32,                                     //      |
-    ///                   k: f32) {                                   //      |
+    ///                   fk: &(f32, f32)) {                          //      |
     /// }                                                             //      |
     ///                                                               //      |
-    /// matrix.unwrap().draw_loop(state, 0.04, 0.06, update_nothing); // <----- move
+    /// matrix.unwrap().draw_loop(state, (0.04, 0.06), update_nothing); // <--- move
     ///                                                               // 以後stateは利用できない
     ///                                                               //
     ///                                                               //
     /// ```
     ///
-    pub fn draw_loop<F>(
+    pub fn draw_loop<T, P, F>(
         &mut self,
-        mut state: (Matrix<f32>, Matrix<f32>),
-        f: f32,
-        k: f32,
+        state: (Matrix<T>, Matrix<T>),
+        params: P,
         update_fn: F,
     ) -> Result<(), failure::Error>
     where
-        F: Fn(&mut (Matrix<f32>, Matrix<f32>), f32, f32),
+        T: Real,
+        F: Fn(&mut (Matrix<T>, Matrix<T>), &P),
     {
-        let mut window_status = WindowStatus::Open;
+        self.run_loop(state, params, update_fn, |_, _, _| None)
+    }
+
+    /// キー操作でFとKを変えながら描画する`draw_loop`
+    /// 上下の矢印キーでF、左右の矢印キーでKを0.001ずつ(Shiftを押しながらだと0.0001ずつ)変え、
+    /// 変えたあとの値をウィンドウのタイトルに表示する
+    ///
+    /// # Example
+    /// ```no_run
+    /// use my_alife::algorithm::gray_scott::{gray_scott, preset};
+    /// use my_alife::visualizer::matrix_visualizer::MatrixVisualizer;
+    ///
+    /// let preset = preset("spot").unwrap();
+    /// let mut matrix = MatrixVisualizer::new(
+    ///     "Gray Scott",
+    ///     "res/shaders/matrix_visualizer_vertex.glsl",
+    ///     "res/shaders/matrix_visualizer_fragment.glsl",
+    /// ).unwrap();
+    /// matrix.draw_loop_tunable(preset.initial_state((256, 256)), preset.params(), gray_scott).unwrap();
+    /// ```
+    pub fn draw_loop_tunable<T, P, F>(
+        &mut self,
+        state: (Matrix<T>, Matrix<T>),
+        params: P,
+        update_fn: F,
+    ) -> Result<(), failure::Error>
+    where
+        T: Real,
+        P: Tunable,
+        F: Fn(&mut (Matrix<T>, Matrix<T>), &P),
+    {
+        self.run_loop(state, params, update_fn, |params, df, dk| Some(params.nudge(df, dk)))
+    }
+
+    /// `draw_loop`と`draw_loop_tunable`の本体
+    /// `nudge`はF, Kを変える関数で、変えられない場合はNoneを返す
+    fn run_loop<T, P, F, N>(
+        &mut self,
+        mut state: (Matrix<T>, Matrix<T>),
+        mut params: P,
+        update_fn: F,
+        mut nudge: N,
+    ) -> Result<(), failure::Error>
+    where
+        T: Real,
+        F: Fn(&mut (Matrix<T>, Matrix<T>), &P),
+        N: FnMut(&mut P, f32, f32) -> Option<(f32, f32)>,
+    {
+        let initial = state.clone();
         loop {
-            if window_status == WindowStatus::Close {
+            update_fn(&mut state, &params);
+            self.draw(&state.0)?;
+
+            let input = self.poll_input();
+            if input.close {
                 break;
             }
-            update_fn(&mut state, f, k);
-            self.draw(&state.0)?;
+            if input.reset {
+                state.clone_from(&initial);
+            }
+            if input.nudge != (0.0, 0.0) {
+                if let Some((f, k)) = nudge(&mut params, input.nudge.0, input.nudge.1) {
+                    self.display
+                        .gl_window()
+                        .set_title(&format!("{} F = {:.4}, K = {:.4}", self.title, f, k));
+                }
+            }
+            if let (true, Some((x, y))) = (self.dragging, self.cursor) {
+                // テクスチャの1行目がウィンドウの上端に来るので、行はyの比、列はxの比で決まる
+                let (rows, cols) = state.1.dim();
+                let center = (y as f32 * rows as f32, x as f32 * cols as f32);
+                self.brush.paint(&mut state.1, center);
+            }
+        }
+        Ok(())
+    }
 
+    /// `ReactionDiffusion`を実装した任意のモデルのシミュレーションを、ウィンドウが閉じられるまで描画し続ける
+    /// uの値はモデルの`display_range`の範囲で白黒に割り当てる
+    ///
+    /// # Example
+    /// ```no_run
+    /// use my_alife::algorithm::reaction_diffusion::model_preset;
+    /// use my_alife::visualizer::matrix_visualizer::MatrixVisualizer;
+    ///
+    /// let mut matrix = MatrixVisualizer::new(
+    ///     "Barkley",
+    ///     "res/shaders/matrix_visualizer_vertex.glsl",
+    ///     "res/shaders/matrix_visualizer_fragment.glsl",
+    /// ).unwrap();
+    /// matrix.draw_simulation(model_preset("barkley").unwrap().simulator((256, 256)).unwrap()).unwrap();
+    /// ```
+    pub fn draw_simulation<M, T>(&mut self, mut simulator: Simulator<M, T>) -> Result<(), failure::Error>
+    where
+        M: ReactionDiffusion,
+        T: Real,
+    {
+        let (low, high) = simulator.model().display_range();
+        let (low, range) = (cast::<T>(low), cast::<T>(high - low));
+        let mut window_status = WindowStatus::Open;
+        while window_status == WindowStatus::Open {
+            simulator.step();
+            self.draw(&simulator.state().0.mapv(|e| (e - low) / range))?;
             window_status = self.hadling_event();
         }
         Ok(())
@@ -381,7 +595,7 @@ impl MatrixVisualizer {
     ///
     /// [資料](https://doc.rust-lang.org/book/2018-edition/ch04-02-references-and-borrowing.html)
     /// [日本語訳](https://github.com/hazama-yuinyan/book/blob/master/second-edition/src/ch04-02-references-and-borrowing.md)
-    pub fn draw(&self, matrix: &Matrix<f32>) -> Result<(), failure::Error> {
+    pub fn draw<T: Real>(&self, matrix: &Matrix<T>) -> Result<(), failure::Error> {
         let image = make_texture_image(matrix);
         let texture = texture::Texture2d::new(&self.display, image);
         let mut target = self.display.draw();
@@ -399,7 +613,21 @@ impl MatrixVisualizer {
 
     /// event handler
     pub fn hadling_event(&mut self) -> WindowStatus {
-        let mut status = WindowStatus::Open;
+        if self.poll_input().close {
+            WindowStatus::Close
+        } else {
+            WindowStatus::Open
+        }
+    }
+
+    /// 溜まっているイベントを処理して、このフレームの入力をまとめる
+    /// マウスの状態(カーソルの位置とボタン)はフレームをまたいで覚えておく
+    fn poll_input(&mut self) -> Input {
+        let mut input = Input::default();
+        let brush = &mut self.brush;
+        let cursor = &mut self.cursor;
+        let dragging = &mut self.dragging;
+        let window_size = &mut self.window_size;
         self.events_loop.poll_events(|event| {
             // matchさせたいパターンが1つしかない場合、if let 形式で書ける
             // matchでやると
@@ -410,7 +638,21 @@ impl MatrixVisualizer {
             // みたいにcatch節的なものが必要になる(rustのパターンマッチは取り得る全パターンを明示的に書かせるため)
             if let glutin::Event::WindowEvent { event, .. } = event {
                 match event {
-                    glutin::WindowEvent::CloseRequested => status = WindowStatus::Close,
+                    glutin::WindowEvent::CloseRequested => input.close = true,
+                    glutin::WindowEvent::Resized(size) => *window_size = (size.width, size.height),
+                    glutin::WindowEvent::CursorMoved { position, .. } => {
+                        *cursor = Some((position.x / window_size.0, position.y / window_size.1));
+                    }
+                    // ウィンドウの外でボタンを離しても届かないので、出たときにドラッグも終える
+                    glutin::WindowEvent::CursorLeft { .. } => {
+                        *cursor = None;
+                        *dragging = false;
+                    }
+                    glutin::WindowEvent::MouseInput {
+                        state,
+                        button: glutin::MouseButton::Left,
+                        ..
+                    } => *dragging = state == glutin::ElementState::Pressed,
                     glutin::WindowEvent::KeyboardInput {
                         device_id: _,
                         input: keyboard_input,
@@ -418,24 +660,38 @@ impl MatrixVisualizer {
                         glutin::KeyboardInput { // 構造体の各fieldをdestructuringできる
                             virtual_keycode, // virtual_keycode: virtual_keycode を省略形
                             modifiers, // modifiers: my_modifiers の様に省略しないで別名をつけても良い
-                            .. // 使わないfieldのscancode: _, state: _, を省略できる
-                        } => match (virtual_keycode, modifiers) { // 複数のパターンマッチにはタプルを使う
-                            #[cfg(target_os = "linux")] // conditional compile https://doc.rust-lang.org/reference/attributes.html#conditional-compilation
-                            (Some(glutin::VirtualKeyCode::W), glutin::ModifiersState { ctrl, .. }) => {
-                              if ctrl { status = WindowStatus::Close }
-                            },
-                            #[cfg(target_os = "macos")]
-                            (Some(glutin::VirtualKeyCode::W), glutin::ModifiersState { logo, .. }) => {
-                              if logo { status = WindowStatus::Close }
-                            },
-                            (_, _) => {}
+                            state: glutin::ElementState::Pressed, // 押したときだけmatchさせ、離したときは無視する
+                            .. // 使わないfieldのscancode: _, を省略できる
+                        } => {
+                            let step = if modifiers.shift { TUNING_STEP / 10.0 } else { TUNING_STEP };
+                            match (virtual_keycode, modifiers) { // 複数のパターンマッチにはタプルを使う
+                                #[cfg(target_os = "linux")] // conditional compile https://doc.rust-lang.org/reference/attributes.html#conditional-compilation
+                                (Some(glutin::VirtualKeyCode::W), glutin::ModifiersState { ctrl, .. }) => {
+                                  if ctrl { input.close = true }
+                                },
+                                #[cfg(target_os = "macos")]
+                                (Some(glutin::VirtualKeyCode::W), glutin::ModifiersState { logo, .. }) => {
+                                  if logo { input.close = true }
+                                },
+                                (Some(glutin::VirtualKeyCode::R), _) => input.reset = true,
+                                (Some(glutin::VirtualKeyCode::Up), _) => input.nudge.0 += step,
+                                (Some(glutin::VirtualKeyCode::Down), _) => input.nudge.0 -= step,
+                                (Some(glutin::VirtualKeyCode::Right), _) => input.nudge.1 += step,
+                                (Some(glutin::VirtualKeyCode::Left), _) => input.nudge.1 -= step,
+                                (Some(glutin::VirtualKeyCode::LBracket), _) => {
+                                    brush.radius = (brush.radius - 1.0).max(1.0)
+                                }
+                                (Some(glutin::VirtualKeyCode::RBracket), _) => brush.radius += 1.0,
+                                (_, _) => {}
+                            }
                         },
+                        _ => {}
                     },
                     _ => {}
                 }
             };
         });
-        return status;
+        input
     }
 }
 
@@ -451,16 +707,18 @@ struct Vertex {
 implement_vertex!(Vertex, a_position, a_texcoord);
 
 /// 各要素が画素値を意味する2次元配列から画像データを生成する
-fn make_texture_image<'a>(u: &Matrix<f32>) -> texture::RawImage2d<'a, u8> {
+/// 行数が画像の縦幅、列数が画像の横幅になる。`f64`の値は描画するときに`f32`に変換する
+fn make_texture_image<'a, T: Real>(u: &Matrix<T>) -> texture::RawImage2d<'a, u8> {
     let mut texture_data = Vec::new();
     for row in u.outer_iter() {
         for e in row.iter() {
-            let v = (if *e < 0.0 {
+            let e = e.to_f32().unwrap_or(0.0);
+            let v = (if e < 0.0 {
                 0.0
-            } else if *e > 1.0 {
+            } else if e > 1.0 {
                 1.0
             } else {
-                *e
+                e
             } * 255.0) as u8;
 
             texture_data.push(v);
@@ -469,5 +727,5 @@ fn make_texture_image<'a>(u: &Matrix<f32>) -> texture::RawImage2d<'a, u8> {
             texture_data.push(v);
         }
     }
-    texture::RawImage2d::from_raw_rgba(texture_data, (u.shape()[0] as u32, u.shape()[1] as u32))
+    texture::RawImage2d::from_raw_rgba(texture_data, (u.cols() as u32, u.rows() as u32))
 }
@@ -4,6 +4,8 @@ pub mod matrix_visualizer;
 /// 一次元配列を用いてvisualizeするためのモジュール
 pub mod array_visualizer;
 pub mod game_of_life_visualizer;
+/// ウィンドウを開かずに時空図を画像にするためのモジュール
+pub mod spacetime_diagram;
 
 /// windowの状態
 #[derive(PartialEq)]
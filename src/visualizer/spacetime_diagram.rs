@@ -0,0 +1,124 @@
+use algorithm::cellular_automata::spacetime_diagram;
+use format::image::GrayImage;
+use ndarray::Array1;
+
+// ラベル用の3x5ドットの数字フォント。各行の下位3bitが左から右のドットを表す
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+// フォントの拡大率
+const FONT_SCALE: usize = 2;
+// ラベル部分の高さ
+const LABEL_HEIGHT: usize = 5 * FONT_SCALE + 4;
+// タイル同士の間隔
+const MARGIN: usize = 4;
+
+/// セルラーオートマトンの時空図を、ウィンドウを開かずに画像として作る
+/// `ArrayVisualizer`と違い履歴を巻き戻さないので、1行目が`initial_state`、`steps`行目が最後のフェーズになる
+///
+/// # Arguments
+/// * `initial_state` - 初期状態
+/// * `rule` - ウルフラムのルールコーディングの数字
+/// * `steps` - 何フェーズ分描くか(画像の縦幅)
+///
+/// # Example
+/// ```no_run
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::visualizer::spacetime_diagram::spacetime_image;
+/// use ndarray::Array1;
+///
+/// let mut initial_state = Array1::<u32>::zeros(600);
+/// initial_state[300] = 1;
+/// spacetime_image(&initial_state, 30, 300).save("rule30.png").unwrap();
+/// ```
+pub fn spacetime_image(initial_state: &Array1<u32>, rule: u8, steps: usize) -> GrayImage {
+    GrayImage::from_cells(&spacetime_diagram(initial_state, rule, steps))
+}
+
+/// 256通り全てのルールの時空図をルール番号のラベル付きで1枚の画像に並べる(コンタクトシート)
+///
+/// # Arguments
+/// * `initial_state` - 全てのルールで共通の初期状態
+/// * `steps` - 各ルールで何フェーズ分描くか
+/// * `columns` - 横に何個並べるか
+///
+/// # Example
+/// ```
+/// extern crate ndarray;
+/// extern crate my_alife;
+///
+/// use my_alife::visualizer::spacetime_diagram::rule_contact_sheet;
+/// use ndarray::Array1;
+///
+/// let mut initial_state = Array1::<u32>::zeros(31);
+/// initial_state[15] = 1;
+/// let sheet = rule_contact_sheet(&initial_state, 16, 16);
+/// assert_eq!(sheet.width(), 4 + 16 * (31 + 4));
+/// ```
+pub fn rule_contact_sheet(initial_state: &Array1<u32>, steps: usize, columns: usize) -> GrayImage {
+    let columns = columns.max(1);
+    let rows = (256 + columns - 1) / columns;
+    let tile_width = initial_state.len();
+    let tile_height = LABEL_HEIGHT + steps;
+    let mut sheet = GrayImage::new(
+        MARGIN + columns * (tile_width + MARGIN),
+        MARGIN + rows * (tile_height + MARGIN),
+        255,
+    );
+    for rule in 0..256usize {
+        let x = MARGIN + (rule % columns) * (tile_width + MARGIN);
+        let y = MARGIN + (rule / columns) * (tile_height + MARGIN);
+        draw_number(&mut sheet, rule, x, y + 2);
+        let tile = spacetime_image(initial_state, rule as u8, steps);
+        sheet.paste(&tile, x, y + LABEL_HEIGHT);
+        // 白いセルばかりのルールでもタイルの範囲がわかるように枠を描く
+        draw_frame(&mut sheet, x, y + LABEL_HEIGHT, tile_width, steps);
+    }
+    sheet
+}
+
+/// 数字を左上が(`x`, `y`)になるように黒で描く
+fn draw_number(image: &mut GrayImage, number: usize, x: usize, y: usize) {
+    let text = number.to_string();
+    for (n, c) in text.chars().enumerate() {
+        let glyph = DIGITS[c.to_digit(10).unwrap() as usize];
+        let left = x + n * 4 * FONT_SCALE;
+        for (dy, bits) in glyph.iter().enumerate() {
+            for dx in 0..3 {
+                if (bits >> (2 - dx)) & 1 == 1 {
+                    for sy in 0..FONT_SCALE {
+                        for sx in 0..FONT_SCALE {
+                            image.set_pixel(left + dx * FONT_SCALE + sx, y + dy * FONT_SCALE + sy, 0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// (`x`, `y`)から始まる`width`x`height`の領域を囲む灰色の枠を描く
+fn draw_frame(image: &mut GrayImage, x: usize, y: usize, width: usize, height: usize) {
+    if x == 0 || y == 0 {
+        return;
+    }
+    for dx in 0..width + 2 {
+        image.set_pixel(x - 1 + dx, y - 1, 128);
+        image.set_pixel(x - 1 + dx, y + height, 128);
+    }
+    for dy in 0..height + 2 {
+        image.set_pixel(x - 1, y - 1 + dy, 128);
+        image.set_pixel(x + width, y - 1 + dy, 128);
+    }
+}